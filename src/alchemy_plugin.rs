@@ -0,0 +1,395 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cat::{CatSheet, MagicSchool},
+    cat_ai_plugin::CatAiSet,
+    designation_plugin::{DesignationKind, JobDone, JobId, JobQueue},
+    experience_plugin::Experience,
+    game_map_plugin::LayerObject,
+    game_state_plugin::{GameObject, GameState},
+    item::{Inventory, ItemEffect, ItemKind, ItemQuality},
+    needs_plugin::{NEED_MAX, Needs},
+    pathfinding::cell_center,
+    player_control_plugin::PlayerCommand,
+    player_input_stage::PlayerInputPostUpdate,
+    rng_plugin::GameRng,
+    roll::{ContestOutcome, Roll},
+    selection_plugin::{Selectable, Selected},
+    simulation_clock_plugin::SimulationSet,
+    spell_plugin::casting_roll,
+};
+
+/// Cats brew potions and enchant items at workstations.
+/// Crafting is done as jobs of `DesignationPlugin`
+pub struct AlchemyPlugin;
+
+/// Alchemy recipes
+const RECIPES: &str = include_str!("../assets/recipes.ron");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum StationKind {
+    /// Potions and broths
+    Cauldron,
+    /// Enchantment of items
+    AlchemyTable,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recipe {
+    pub name: String,
+    pub station: StationKind,
+    pub ingredients: Vec<(ItemKind, u32)>,
+    pub product: ItemKind,
+    /// Rolled against the alchemy aptitude of the crafter
+    pub difficulty: Roll,
+}
+
+#[derive(Debug)]
+pub enum RecipeError {
+    Parse(ron::error::SpannedError),
+    Duplicate(String),
+}
+
+impl std::fmt::Display for RecipeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "parse error: {e}"),
+            Self::Duplicate(name) => write!(f, "{name} is defined twice"),
+        }
+    }
+}
+
+impl From<ron::error::SpannedError> for RecipeError {
+    fn from(value: ron::error::SpannedError) -> Self {
+        Self::Parse(value)
+    }
+}
+
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct RecipeBook(pub Vec<Recipe>);
+
+impl RecipeBook {
+    pub fn from_ron(text: &str) -> Result<Self, RecipeError> {
+        let recipes: Vec<Recipe> = ron::from_str(text)?;
+        for (idx, recipe) in recipes.iter().enumerate() {
+            if recipes[..idx].iter().any(|other| other.name == recipe.name) {
+                return Err(RecipeError::Duplicate(recipe.name.clone()));
+            }
+        }
+        Ok(Self(recipes))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Recipe> {
+        self.0.iter().find(|recipe| recipe.name == name)
+    }
+}
+
+/// Cauldron or table standing on the map cell. Ingredients and products are kept in its `Inventory`
+#[derive(Component, Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Workstation {
+    pub kind: StationKind,
+    pub layer: usize,
+    /// (column, row)
+    pub cell: UVec2,
+    /// Recipes to craft, next one first
+    pub orders: Vec<String>,
+    /// Recipe being crafted and its job
+    pub crafting: Option<(String, JobId)>,
+}
+
+/// Trigger to put a workstation on the cell. Every cell holds one workstation at most
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlaceWorkstation {
+    pub kind: StationKind,
+    pub layer: usize,
+    pub cell: UVec2,
+}
+
+/// Trigger to queue the recipe at the workstation
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct OrderCraft {
+    pub station: Entity,
+    pub recipe: String,
+}
+
+/// Trigger for the cat to use up an item of its inventory, the best one of the kind
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UseItem {
+    pub cat: Entity,
+    pub kind: ItemKind,
+}
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct Crafted {
+    pub cat: Entity,
+    pub station: Entity,
+    pub recipe: String,
+    /// `None` if the crafting failed and the ingredients are lost
+    pub quality: Option<ItemQuality>,
+}
+
+/// Quality of the product, `None` if the crafter loses the roll against the difficulty
+pub fn craft_quality(
+    sheet: &CatSheet,
+    experience: Option<&Experience>,
+    recipe: &Recipe,
+    rng: &mut GameRng,
+) -> Option<ItemQuality> {
+    let skill = casting_roll(sheet, experience, MagicSchool::Alchemy).roll(&mut rng.0);
+    let difficulty = recipe.difficulty.roll(&mut rng.0);
+    (ContestOutcome::of(skill, difficulty) == ContestOutcome::Win)
+        .then(|| ItemQuality::from_margin(skill - difficulty))
+}
+
+fn add_inventory(ev: Trigger<OnAdd, CatSheet>, mut cmds: Commands) {
+    cmds.entity(ev.target()).insert_if_new(Inventory::default());
+}
+
+/// Workstation with its items. Headless tests have no render assets
+pub fn spawn_workstation(
+    cmds: &mut Commands,
+    station: Workstation,
+    inventory: Inventory,
+    meshes: Option<&mut Assets<Mesh>>,
+    materials: Option<&mut Assets<StandardMaterial>>,
+) -> Entity {
+    let (kind, translation) = (
+        station.kind,
+        cell_center(station.cell, station.layer as f32 + 0.75),
+    );
+    let mut entity = cmds.spawn((
+        Name::new(format!("{kind:?}")),
+        GameObject,
+        LayerObject(station.layer),
+        Selectable,
+        station,
+        inventory,
+        Transform::from_translation(translation),
+    ));
+    if let (Some(meshes), Some(materials)) = (meshes, materials) {
+        let (mesh, color) = match kind {
+            StationKind::Cauldron => (
+                meshes.add(Cylinder::new(0.35, 0.5)),
+                Color::srgb(0.2, 0.2, 0.25),
+            ),
+            StationKind::AlchemyTable => (
+                meshes.add(Cuboid::new(0.8, 0.5, 0.6)),
+                Color::srgb(0.55, 0.35, 0.2),
+            ),
+        };
+        entity.insert((Mesh3d(mesh), MeshMaterial3d(materials.add(color))));
+    }
+    entity.id()
+}
+
+fn place_workstation(
+    ev: Trigger<PlaceWorkstation>,
+    stations: Query<&Workstation>,
+    mut meshes: Option<ResMut<Assets<Mesh>>>,
+    mut materials: Option<ResMut<Assets<StandardMaterial>>>,
+    mut cmds: Commands,
+) {
+    if stations
+        .iter()
+        .any(|station| station.layer == ev.layer && station.cell == ev.cell)
+    {
+        warn!("Cell {} of layer {} is taken", ev.cell, ev.layer);
+        return;
+    }
+    spawn_workstation(
+        &mut cmds,
+        Workstation {
+            kind: ev.kind,
+            layer: ev.layer,
+            cell: ev.cell,
+            orders: vec![],
+            crafting: None,
+        },
+        Inventory::default(),
+        meshes.as_deref_mut(),
+        materials.as_deref_mut(),
+    );
+}
+
+fn order_craft(
+    ev: Trigger<OrderCraft>,
+    book: Res<RecipeBook>,
+    mut stations: Query<&mut Workstation>,
+) {
+    let Ok(mut station) = stations.get_mut(ev.station) else {
+        warn!("No workstation {:?}", ev.station);
+        return;
+    };
+    match book.get(&ev.recipe) {
+        Some(recipe) if recipe.station == station.kind => station.orders.push(ev.recipe.clone()),
+        _ => warn!("{} can't be crafted at {:?}", ev.recipe, station.kind),
+    }
+}
+
+/// Recipes of the station cycle in the order of the recipe book
+fn player_cmd_order(
+    mut evs: EventReader<PlayerCommand>,
+    book: Res<RecipeBook>,
+    selected: Query<(Entity, &Workstation), With<Selected>>,
+    mut cmds: Commands,
+) {
+    let orders = evs
+        .read()
+        .filter(|ev| matches!(ev, PlayerCommand::OrderNextRecipe))
+        .count();
+    let Ok((entity, station)) = selected.single() else {
+        return;
+    };
+    let recipes: Vec<&Recipe> = book
+        .0
+        .iter()
+        .filter(|recipe| recipe.station == station.kind)
+        .collect();
+    if recipes.is_empty() {
+        return;
+    }
+    let mut last = station
+        .orders
+        .last()
+        .or(station.crafting.as_ref().map(|(name, _)| name))
+        .and_then(|last| recipes.iter().position(|recipe| recipe.name == *last));
+    for _ in 0..orders {
+        let next = last.map_or(0, |idx| (idx + 1) % recipes.len());
+        cmds.trigger(OrderCraft {
+            station: entity,
+            recipe: recipes[next].name.clone(),
+        });
+        last = Some(next);
+    }
+}
+
+fn use_item(
+    ev: Trigger<UseItem>,
+    mut cats: Query<(&mut Inventory, &mut CatSheet, Option<&mut Needs>)>,
+) {
+    let Ok((mut inventory, mut sheet, needs)) = cats.get_mut(ev.cat) else {
+        return;
+    };
+    let Some(effect) = ev.kind.effect() else {
+        return;
+    };
+    let Some(quality) = inventory.take_best(ev.kind) else {
+        return;
+    };
+    match quality.scale(effect) {
+        ItemEffect::RestoreNeed(need, amount) => {
+            if let Some(mut needs) = needs {
+                let value = needs.get_mut(need);
+                *value = (*value + amount).min(NEED_MAX);
+            }
+        }
+        ItemEffect::RaiseAttribute(attr, amount) => *sheet.secondary.get_mut(attr) += amount,
+    }
+}
+
+type Crafter<'a> = (&'a CatSheet, Option<&'a Experience>);
+
+/// Finished crafting jobs give products, cancelled ones give ingredients back.
+/// Idle workstations with enough ingredients and no job on their cell start the next order
+fn craft(
+    mut stations: Query<(Entity, &mut Workstation, &mut Inventory)>,
+    crafters: Query<Crafter>,
+    book: Res<RecipeBook>,
+    mut queue: ResMut<JobQueue>,
+    mut done: EventReader<JobDone>,
+    mut rng: ResMut<GameRng>,
+    mut crafted: EventWriter<Crafted>,
+) {
+    let mut order: Vec<_> = stations.iter_mut().collect();
+    order.sort_by_key(|(entity, ..)| *entity);
+
+    for job in done.read().filter(|job| job.kind == DesignationKind::Craft) {
+        let Some((station, workstation, inventory)) = order
+            .iter_mut()
+            .find(|(_, station, _)| station.layer == job.layer && station.cell == job.cell)
+        else {
+            continue;
+        };
+        let Some((name, _)) = workstation.crafting.take() else {
+            continue;
+        };
+        let (Some(recipe), Ok((sheet, experience))) = (book.get(&name), crafters.get(job.cat))
+        else {
+            continue;
+        };
+        let quality = craft_quality(sheet, experience, recipe, &mut rng);
+        if let Some(quality) = quality {
+            inventory.add(recipe.product, quality, 1);
+        }
+        crafted.write(Crafted {
+            cat: job.cat,
+            station: *station,
+            recipe: name,
+            quality,
+        });
+    }
+
+    for (_, station, inventory) in order.iter_mut() {
+        if let Some((name, id)) = &station.crafting {
+            if queue.get(*id).is_some() {
+                continue;
+            }
+            // Cancelled by the player
+            if let Some(recipe) = book.get(name) {
+                for (kind, count) in &recipe.ingredients {
+                    inventory.add(*kind, ItemQuality::Common, *count);
+                }
+            }
+            station.crafting = None;
+        }
+        let Some(name) = station.orders.first().cloned() else {
+            continue;
+        };
+        let Some(recipe) = book.get(&name) else {
+            station.orders.remove(0);
+            continue;
+        };
+        let ready = recipe
+            .ingredients
+            .iter()
+            .all(|(kind, count)| inventory.count(*kind) >= *count);
+        // Designating the cell would cancel deliveries to the station
+        if !ready || queue.at(station.layer, station.cell).is_some() {
+            continue;
+        }
+        for (kind, count) in &recipe.ingredients {
+            inventory.remove(*kind, *count);
+        }
+        let id = queue.designate(DesignationKind::Craft, station.layer, station.cell);
+        station.orders.remove(0);
+        station.crafting = Some((name, id));
+    }
+}
+
+impl Plugin for AlchemyPlugin {
+    fn build(&self, app: &mut App) {
+        let book = RecipeBook::from_ron(RECIPES).unwrap_or_else(|e| {
+            error!("Failed to load recipes: {e}");
+            RecipeBook::default()
+        });
+        app.register_type::<Inventory>();
+        app.register_type::<Workstation>();
+        app.insert_resource(book);
+        app.add_event::<Crafted>();
+        app.add_observer(add_inventory);
+        app.add_observer(place_workstation);
+        app.add_observer(order_craft);
+        app.add_observer(use_item);
+        app.add_systems(
+            PlayerInputPostUpdate,
+            player_cmd_order.run_if(in_state(GameState::Game)),
+        );
+        app.add_systems(FixedUpdate, craft.before(CatAiSet).in_set(SimulationSet));
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_alchemy_plugin.rs"]
+mod test_alchemy_plugin;
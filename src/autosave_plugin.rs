@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    calendar_plugin::SeasonChanged,
+    game_state_plugin::GameState,
+    save_plugin::{SaveGame, SavePath},
+    simulation_clock_plugin::{
+        SIMULATION_TICKS_PER_SECOND, SimulationClock, simulation_is_ticking,
+    },
+};
+
+/// Saves the game every season and every few minutes of game time
+pub struct AutosavePlugin;
+
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct AutosaveConfig {
+    /// Autosave files, the oldest one is overwritten
+    pub slots: usize,
+    /// Minutes of game time between autosaves, 0 to only save on season change
+    pub interval_minutes: u32,
+    pub on_season_change: bool,
+}
+
+impl Default for AutosaveConfig {
+    fn default() -> Self {
+        Self {
+            slots: 3,
+            interval_minutes: 10,
+            on_season_change: true,
+        }
+    }
+}
+
+/// Simulation tick of the last autosave, or of the start of the game
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct LastAutosave(u64);
+
+/// File of the slot [0..slots), next to the save of the player
+pub fn autosave_path(save: &Path, slot: usize) -> PathBuf {
+    let stem = save.file_stem().unwrap_or_default().to_string_lossy();
+    save.with_file_name(format!("{stem}-autosave{}.ron", slot + 1))
+}
+
+fn modified(path: &Path) -> Option<std::time::SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+/// First empty slot, or the one saved longest ago
+pub fn next_autosave_path(save: &Path, slots: usize) -> PathBuf {
+    (0..slots.max(1))
+        .map(|slot| autosave_path(save, slot))
+        .min_by_key(|path| modified(path))
+        .unwrap_or_else(|| autosave_path(save, 0))
+}
+
+/// The most recent of the save of the player and the autosaves
+pub fn newest_save(save: &Path, slots: usize) -> Option<PathBuf> {
+    std::iter::once(save.to_path_buf())
+        .chain((0..slots).map(|slot| autosave_path(save, slot)))
+        .filter_map(|path| Some((modified(&path)?, path)))
+        .max_by_key(|(time, _)| *time)
+        .map(|(_, path)| path)
+}
+
+fn reset_autosave_timer(clock: Res<SimulationClock>, mut last: ResMut<LastAutosave>) {
+    *last = LastAutosave(clock.tick);
+}
+
+/// Runs after all the simulation systems, so the tick is saved whole
+fn autosave(
+    mut evs: EventReader<SeasonChanged>,
+    config: Res<AutosaveConfig>,
+    clock: Res<SimulationClock>,
+    mut last: ResMut<LastAutosave>,
+    path: Res<SavePath>,
+    mut cmds: Commands,
+) {
+    let season_changed = evs.read().count() > 0 && config.on_season_change;
+    let interval = config.interval_minutes as u64 * 60 * SIMULATION_TICKS_PER_SECOND as u64;
+    let due = interval > 0 && clock.tick >= last.0 + interval;
+    if config.slots == 0 || !(season_changed || due) {
+        return;
+    }
+    *last = LastAutosave(clock.tick);
+    cmds.trigger(SaveGame(next_autosave_path(&path.0, config.slots)));
+}
+
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<AutosaveConfig>();
+        app.init_resource::<AutosaveConfig>();
+        app.init_resource::<LastAutosave>();
+        app.add_systems(OnEnter(GameState::Game), reset_autosave_timer);
+        app.add_systems(
+            FixedPostUpdate,
+            autosave
+                .run_if(in_state(GameState::Game))
+                .run_if(simulation_is_ticking),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_autosave_plugin.rs"]
+mod test_autosave_plugin;
@@ -0,0 +1,238 @@
+//! Batch of cats generated with the game code, for balance review.
+//! Cats go to stdout as CSV or JSON, aggregate histograms go to stderr.
+//!
+//! ```sh
+//! cargo run --bin catgen -- --count 1000 --seed 7 > cats.csv
+//! cargo run --bin catgen -- --count 10 --format json
+//! ```
+
+use std::{fmt::Write, process::ExitCode};
+
+use macatemy::cat::{
+    CatSheet, CatTrait, MagicSchool, PrimaryAttribute, SecondaryAttribute, generate_cat_name,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+const USAGE: &str = "usage: catgen [--count N] [--seed SEED] [--format csv|json]";
+/// Width of the attribute value buckets in histograms
+const BUCKET: i32 = 4;
+/// Attribute values are in `4..=40`, plus trait bonuses and experience
+const BUCKETS: i32 = 12;
+const BAR_WIDTH: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Csv,
+    Json,
+}
+
+struct Args {
+    count: usize,
+    seed: u64,
+    format: Format,
+}
+
+/// `None` when only the usage is asked for
+fn parse_args() -> Result<Option<Args>, String> {
+    let mut args = Args {
+        count: 100,
+        seed: 1,
+        format: Format::Csv,
+    };
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(None);
+        }
+        let value = iter.next().ok_or_else(|| format!("no value for {arg}"))?;
+        match arg.as_str() {
+            "--count" => args.count = value.parse().map_err(|_| format!("bad count {value}"))?,
+            "--seed" => args.seed = value.parse().map_err(|_| format!("bad seed {value}"))?,
+            "--format" => {
+                args.format = match value.as_str() {
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    _ => return Err(format!("bad format {value}")),
+                }
+            }
+            _ => return Err(format!("unknown argument {arg}")),
+        }
+    }
+    Ok(Some(args))
+}
+
+/// `MeleeCombat` -> `melee_combat`
+fn snake_case(name: impl std::fmt::Debug) -> String {
+    let mut snake = String::new();
+    for (idx, c) in format!("{name:?}").chars().enumerate() {
+        if c.is_uppercase() && idx > 0 {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
+
+/// Named numeric columns of the cat, in output order
+fn columns(sheet: &CatSheet) -> Vec<(String, i32)> {
+    let primary = PrimaryAttribute::ALL
+        .into_iter()
+        .map(|attr| (snake_case(attr), sheet.primary.get(attr)));
+    let secondary = SecondaryAttribute::ALL
+        .into_iter()
+        .map(|attr| (snake_case(attr), sheet.secondary.get(attr)));
+    let schools = MagicSchool::ALL.into_iter().map(|school| {
+        (
+            format!("{}_aptitude", snake_case(school)),
+            sheet.school_aptitude(school),
+        )
+    });
+    primary.chain(secondary).chain(schools).collect()
+}
+
+fn traits(sheet: &CatSheet) -> Vec<String> {
+    sheet.traits.0.iter().map(|t| snake_case(*t)).collect()
+}
+
+fn to_csv(cats: &[(String, CatSheet)]) -> String {
+    let mut out = String::new();
+    let Some((_, first)) = cats.first() else {
+        return out;
+    };
+    let header: Vec<String> = columns(first).into_iter().map(|(name, _)| name).collect();
+    writeln!(out, "name,{},traits,best_school", header.join(",")).unwrap();
+    for (name, sheet) in cats {
+        let values: Vec<String> = columns(sheet)
+            .into_iter()
+            .map(|(_, value)| value.to_string())
+            .collect();
+        writeln!(
+            out,
+            "{name},{},{},{}",
+            values.join(","),
+            traits(sheet).join(";"),
+            snake_case(sheet.best_school())
+        )
+        .unwrap();
+    }
+    out
+}
+
+fn to_json(cats: &[(String, CatSheet)]) -> String {
+    // Names are generated from plain syllables, nothing needs escaping
+    let cats: Vec<String> = cats
+        .iter()
+        .map(|(name, sheet)| {
+            let values: Vec<String> = columns(sheet)
+                .into_iter()
+                .map(|(column, value)| format!("\"{column}\": {value}"))
+                .collect();
+            let traits: Vec<String> = traits(sheet).iter().map(|t| format!("\"{t}\"")).collect();
+            format!(
+                "  {{\"name\": \"{name}\", {}, \"traits\": [{}], \"best_school\": \"{}\"}}",
+                values.join(", "),
+                traits.join(", "),
+                snake_case(sheet.best_school())
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", cats.join(",\n"))
+}
+
+fn bar(count: usize, max: usize) -> String {
+    "#".repeat((count * BAR_WIDTH).div_ceil(max.max(1)))
+}
+
+fn histograms(cats: &[(String, CatSheet)]) -> String {
+    let mut out = String::new();
+    let sheets: Vec<&CatSheet> = cats.iter().map(|(_, sheet)| sheet).collect();
+    let count = sheets.len().max(1) as f64;
+
+    write!(out, "{:<22}", "attribute").unwrap();
+    for bucket in 0..BUCKETS {
+        let from = bucket * BUCKET;
+        // Larger values go to the last bucket
+        let label = if bucket == BUCKETS - 1 {
+            format!("{from}+")
+        } else {
+            format!("{from}-{}", from + BUCKET - 1)
+        };
+        write!(out, "{label:>6}").unwrap();
+    }
+    writeln!(out, "{:>8}", "mean").unwrap();
+    let Some(first) = sheets.first() else {
+        return out;
+    };
+    for (column, (name, _)) in columns(first).into_iter().enumerate() {
+        let values: Vec<i32> = sheets
+            .iter()
+            .map(|sheet| columns(sheet)[column].1)
+            .collect();
+        write!(out, "{name:<22}").unwrap();
+        for bucket in 0..BUCKETS {
+            let in_bucket = values
+                .iter()
+                .filter(|value| (**value / BUCKET).clamp(0, BUCKETS - 1) == bucket)
+                .count();
+            write!(out, "{in_bucket:>6}").unwrap();
+        }
+        let mean = values.iter().sum::<i32>() as f64 / count;
+        writeln!(out, "{mean:>8.2}").unwrap();
+    }
+
+    writeln!(out, "\ntraits").unwrap();
+    let trait_counts: Vec<(String, usize)> = CatTrait::ALL
+        .into_iter()
+        .map(|t| {
+            let cats = sheets.iter().filter(|sheet| sheet.traits.0.contains(&t));
+            (snake_case(t), cats.count())
+        })
+        .collect();
+    let max = trait_counts.iter().map(|(_, n)| *n).max().unwrap_or(0);
+    for (name, n) in &trait_counts {
+        writeln!(out, "{name:<22}{n:>6} {}", bar(*n, max)).unwrap();
+    }
+
+    writeln!(out, "\nbest school").unwrap();
+    let school_counts: Vec<(String, usize)> = MagicSchool::ALL
+        .into_iter()
+        .map(|school| {
+            let cats = sheets.iter().filter(|sheet| sheet.best_school() == school);
+            (snake_case(school), cats.count())
+        })
+        .collect();
+    let max = school_counts.iter().map(|(_, n)| *n).max().unwrap_or(0);
+    for (name, n) in &school_counts {
+        writeln!(out, "{name:<22}{n:>6} {}", bar(*n, max)).unwrap();
+    }
+    out
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("{err}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let mut rng = ChaCha8Rng::seed_from_u64(args.seed);
+    // Same order of rolls as newcomers spawned by the game
+    let cats: Vec<(String, CatSheet)> = (0..args.count)
+        .map(|_| {
+            let sheet = CatSheet::generate(&mut rng);
+            (generate_cat_name(&mut rng), sheet)
+        })
+        .collect();
+    match args.format {
+        Format::Csv => print!("{}", to_csv(&cats)),
+        Format::Json => print!("{}", to_json(&cats)),
+    }
+    eprint!("{}", histograms(&cats));
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,74 @@
+//! Exact distribution of a roll, or odds of a contest between two rolls.
+//!
+//! ```sh
+//! cargo run --bin rollstat -- "8d10(drop 4 high)"
+//! cargo run --bin rollstat -- "8d10(drop 4 high)" "1d13+3"
+//! ```
+
+use std::process::ExitCode;
+
+use macatemy::roll::{ContestOdds, Roll};
+
+const BAR_WIDTH: f64 = 50.0;
+const PERCENTILES: [f64; 5] = [0.05, 0.25, 0.5, 0.75, 0.95];
+
+fn print_distribution(roll: &Roll) {
+    let distribution = roll.distribution();
+    println!("{roll}");
+    println!(
+        "  min {}, max {}, mean {:.2}",
+        distribution.lowest(),
+        distribution.highest(),
+        distribution.mean()
+    );
+    let percentiles: Vec<String> = PERCENTILES
+        .iter()
+        .map(|fraction| {
+            format!(
+                "{}%: {}",
+                fraction * 100.0,
+                distribution.percentile(*fraction)
+            )
+        })
+        .collect();
+    println!("  percentiles {}", percentiles.join(", "));
+    let most_likely = distribution.totals().map(|(_, p)| p).fold(0.0, f64::max);
+    for (total, p) in distribution.totals() {
+        let bar = "#".repeat((p / most_likely * BAR_WIDTH).round() as usize);
+        println!("  {total:>4} {:>6.2}% {bar}", p * 100.0);
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.len() > 2 {
+        eprintln!(
+            "usage: rollstat <roll> [<opposing roll>], e.g. rollstat \"8d10(drop 4 high)\" 1d13+3"
+        );
+        return ExitCode::from(2);
+    }
+    let mut rolls = vec![];
+    for arg in &args {
+        match arg.parse::<Roll>() {
+            Ok(roll) => rolls.push(roll),
+            Err(err) => {
+                eprintln!("{err}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    for roll in &rolls {
+        print_distribution(roll);
+    }
+    if let [a, b] = rolls[..] {
+        let odds = ContestOdds::of(&a, &b);
+        println!(
+            "{a} vs {b}: win {:.2}%, tie {:.2}%, lose {:.2}%",
+            odds.win * 100.0,
+            odds.tie * 100.0,
+            odds.lose * 100.0
+        );
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{game_state_plugin::GameResourceAppExt, simulation_clock_plugin::SimulationSet};
+
+/// In-game date advanced by the simulation clock
+pub struct CalendarPlugin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Reflect, Serialize, Deserialize)]
+pub enum Season {
+    #[default]
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    pub const ALL: [Season; 4] = [Self::Spring, Self::Summer, Self::Autumn, Self::Winter];
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Spring => Self::Summer,
+            Self::Summer => Self::Autumn,
+            Self::Autumn => Self::Winter,
+            Self::Winter => Self::Spring,
+        }
+    }
+}
+
+/// Length of calendar units
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct CalendarConfig {
+    pub ticks_per_day: u32,
+    pub days_per_season: u32,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            // Two minutes of real time at normal speed
+            ticks_per_day: 1200,
+            days_per_season: 28,
+        }
+    }
+}
+
+/// Current date. Days are counted from 0 within the season, years from 1
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Calendar {
+    pub tick_of_day: u32,
+    pub day: u32,
+    pub season: Season,
+    pub year: u32,
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Self {
+            tick_of_day: 0,
+            day: 0,
+            season: Season::default(),
+            year: 1,
+        }
+    }
+}
+
+impl Calendar {
+    /// Number of seasons passed since the start of the first year.
+    /// Useful to measure durations, e.g. how long a kitten studies
+    pub fn season_index(&self) -> u32 {
+        (self.year - 1) * Season::ALL.len() as u32 + self.season as u32
+    }
+
+    /// Advance the date by a single tick, return which calendar units have rolled over
+    pub fn advance(&mut self, config: &CalendarConfig) -> CalendarRollover {
+        let mut rollover = CalendarRollover::default();
+        self.tick_of_day += 1;
+        if self.tick_of_day < config.ticks_per_day.max(1) {
+            return rollover;
+        }
+        self.tick_of_day = 0;
+        self.day += 1;
+        rollover.day = true;
+        if self.day < config.days_per_season.max(1) {
+            return rollover;
+        }
+        self.day = 0;
+        self.season = self.season.next();
+        rollover.season = true;
+        if self.season == Season::Spring {
+            self.year += 1;
+            rollover.year = true;
+        }
+        rollover
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CalendarRollover {
+    pub day: bool,
+    pub season: bool,
+    pub year: bool,
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayChanged(pub Calendar);
+
+/// Sent when a new season begins. Enrollment, graduation and other
+/// seasonal activities hook into it
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeasonChanged {
+    pub previous: Season,
+    pub calendar: Calendar,
+}
+
+/// Systems which react to calendar events should run after it
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct CalendarSet;
+
+fn advance_calendar(
+    mut calendar: ResMut<Calendar>,
+    config: Res<CalendarConfig>,
+    mut day_changed: EventWriter<DayChanged>,
+    mut season_changed: EventWriter<SeasonChanged>,
+) {
+    let previous = calendar.season;
+    let rollover = calendar.advance(&config);
+    if rollover.day {
+        day_changed.write(DayChanged(*calendar));
+    }
+    if rollover.season {
+        season_changed.write(SeasonChanged {
+            previous,
+            calendar: *calendar,
+        });
+    }
+}
+
+impl Plugin for CalendarPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Calendar>();
+        app.register_type::<CalendarConfig>();
+        app.init_game_resource::<Calendar>();
+        app.init_resource::<CalendarConfig>();
+        app.add_event::<DayChanged>();
+        app.add_event::<SeasonChanged>();
+        app.configure_sets(FixedUpdate, CalendarSet.in_set(SimulationSet));
+        app.add_systems(FixedUpdate, advance_calendar.in_set(CalendarSet));
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_calendar_plugin.rs"]
+mod test_calendar_plugin;
@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+
+use crate::{
+    game_map_plugin::{GameMapData, ShiftActiveLayerEvent},
+    game_state_plugin::{GameResourceAppExt, GameState},
+    orbit_camera_plugin::{OrbitCameraRig, OrbitPose},
+    player_control_plugin::{CameraMode, Player, PlayerCommand},
+    player_input_stage::PlayerInputPostUpdate,
+    selection_plugin::Selected,
+};
+
+pub struct CameraBookmarksPlugin;
+
+pub const CAMERA_BOOKMARKS: usize = 9;
+/// How fast the camera flies to its destination, higher is snappier
+const CAMERA_TRANSITION_SMOOTHNESS: f32 = 8.0;
+/// Transition is finished once the camera is this close to the destination
+const CAMERA_TRANSITION_EPS: f32 = 1e-3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraBookmark {
+    pub transform: Transform,
+    pub layer: usize,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct CameraBookmarks(pub [Option<CameraBookmark>; CAMERA_BOOKMARKS]);
+
+/// Entity followed by the camera
+#[derive(Resource, Debug, Default)]
+pub struct CameraFollow(pub Option<Entity>);
+
+/// Observable event to smoothly move the player camera to the given transform
+#[derive(Event)]
+pub struct CameraFlyTo(pub Transform);
+
+/// Destination of the free fly camera, which is eased towards it
+#[derive(Component, Debug)]
+pub struct CameraTransition(pub Transform);
+
+/// Transform of the camera looking at the `target` the same way it looks at its current focus
+pub fn view_of(target: Vec3, camera: &Transform) -> Transform {
+    OrbitPose {
+        focus: target,
+        ..OrbitPose::from_transform(camera)
+    }
+    .to_transform()
+}
+
+fn player_cmd_camera_bookmarks(
+    mut evs: EventReader<PlayerCommand>,
+    player: Single<&Transform, With<Player>>,
+    map_data: Option<Res<GameMapData>>,
+    mut bookmarks: ResMut<CameraBookmarks>,
+    mut cmds: Commands,
+) {
+    let current_layer = map_data.map_or(0, |map_data| map_data.current_layer());
+    for ev in evs.read() {
+        match ev {
+            PlayerCommand::StoreCameraBookmark(slot) => {
+                if let Some(bookmark) = bookmarks.0.get_mut(*slot) {
+                    *bookmark = Some(CameraBookmark {
+                        transform: **player,
+                        layer: current_layer,
+                    });
+                }
+            }
+            PlayerCommand::RecallCameraBookmark(slot) => {
+                let Some(Some(bookmark)) = bookmarks.0.get(*slot) else {
+                    continue;
+                };
+                cmds.trigger(CameraFlyTo(bookmark.transform));
+                let layer_delta = bookmark.layer as isize - current_layer as isize;
+                if layer_delta != 0 {
+                    cmds.trigger(ShiftActiveLayerEvent(layer_delta));
+                }
+            }
+            _ => continue,
+        }
+    }
+}
+
+fn player_cmd_jump_to_selected(
+    mut evs: EventReader<PlayerCommand>,
+    player: Single<&Transform, With<Player>>,
+    selected: Query<(Entity, &Transform), With<Selected>>,
+    mut follow: ResMut<CameraFollow>,
+    mut cmds: Commands,
+) {
+    for ev in evs.read() {
+        match ev {
+            PlayerCommand::JumpToSelected => {
+                if let Ok((_, tr)) = selected.single() {
+                    cmds.trigger(CameraFlyTo(view_of(tr.translation, &player)));
+                }
+            }
+            PlayerCommand::FollowSelected => {
+                follow.0 = match follow.0 {
+                    Some(_) => None,
+                    None => selected.single().ok().map(|(entity, _)| entity),
+                };
+            }
+            // Player took over the camera
+            PlayerCommand::MoveCameraXZ(_) => follow.0 = None,
+            _ => continue,
+        }
+    }
+}
+
+fn follow_entity(
+    mut follow: ResMut<CameraFollow>,
+    player: Single<&Transform, With<Player>>,
+    transforms: Query<&Transform, Without<Player>>,
+    mut cmds: Commands,
+) {
+    let Some(entity) = follow.0 else {
+        return;
+    };
+    match transforms.get(entity) {
+        Ok(tr) => cmds.trigger(CameraFlyTo(view_of(tr.translation, &player))),
+        Err(_) => follow.0 = None,
+    }
+}
+
+fn fly_camera_to(
+    ev: Trigger<CameraFlyTo>,
+    player: Single<(Entity, Option<&mut OrbitCameraRig>), With<Player>>,
+    mut cmds: Commands,
+) {
+    let (entity, rig) = player.into_inner();
+    match rig {
+        // Orbit camera eases on its own
+        Some(mut rig) => rig.target = OrbitPose::from_transform(&ev.0).clamped(),
+        None => {
+            cmds.entity(entity).insert(CameraTransition(ev.0));
+        }
+    }
+}
+
+fn camera_transition(
+    mut evs: EventReader<PlayerCommand>,
+    player: Query<(Entity, &mut Transform, &CameraTransition)>,
+    time: Res<Time<Real>>,
+    mut cmds: Commands,
+) {
+    // Player took over the camera
+    let interrupted = evs
+        .read()
+        .filter(|ev| {
+            matches!(
+                ev,
+                PlayerCommand::MoveCameraXZ(_) | PlayerCommand::MoveCameraInOut(_)
+            )
+        })
+        .count()
+        > 0;
+    let t = 1.0 - (-CAMERA_TRANSITION_SMOOTHNESS * time.delta_secs()).exp();
+
+    for (entity, mut tr, transition) in player {
+        let destination = transition.0;
+        let arrived = tr.translation.distance(destination.translation) < CAMERA_TRANSITION_EPS
+            && tr.rotation.angle_between(destination.rotation) < CAMERA_TRANSITION_EPS;
+        if arrived {
+            *tr = destination;
+        }
+        if interrupted || arrived {
+            cmds.entity(entity).remove::<CameraTransition>();
+            continue;
+        }
+        tr.translation = tr.translation.lerp(destination.translation, t);
+        tr.rotation = tr.rotation.slerp(destination.rotation, t);
+    }
+}
+
+fn cancel_camera_transition(players: Query<Entity, With<CameraTransition>>, mut cmds: Commands) {
+    for entity in players {
+        cmds.entity(entity).remove::<CameraTransition>();
+    }
+}
+
+impl Plugin for CameraBookmarksPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_game_resource::<CameraBookmarks>();
+        app.init_game_resource::<CameraFollow>();
+        app.add_observer(fly_camera_to);
+        app.add_systems(OnExit(CameraMode::FreeFly), cancel_camera_transition);
+        app.add_systems(
+            PlayerInputPostUpdate,
+            (
+                player_cmd_camera_bookmarks,
+                player_cmd_jump_to_selected,
+                follow_entity,
+                camera_transition.run_if(in_state(CameraMode::FreeFly)),
+            )
+                .chain()
+                .run_if(in_state(GameState::Game)),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_camera_bookmarks_plugin.rs"]
+mod test_camera_bookmarks_plugin;
@@ -0,0 +1,408 @@
+use bevy::prelude::*;
+use rand::{Rng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::roll::{ContestOutcome, Roll, contest};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum PrimaryAttribute {
+    Strength,
+    Intelligence,
+    Luck,
+    Agility,
+    Magic,
+    Charm,
+}
+
+impl PrimaryAttribute {
+    pub const ALL: [PrimaryAttribute; 6] = [
+        Self::Strength,
+        Self::Intelligence,
+        Self::Luck,
+        Self::Agility,
+        Self::Magic,
+        Self::Charm,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect, Serialize, Deserialize)]
+pub struct PrimaryAttributes {
+    pub strength: i32,
+    pub intelligence: i32,
+    pub luck: i32,
+    pub agility: i32,
+    pub magic: i32,
+    pub charm: i32,
+}
+
+impl PrimaryAttributes {
+    pub fn get(&self, attr: PrimaryAttribute) -> i32 {
+        match attr {
+            PrimaryAttribute::Strength => self.strength,
+            PrimaryAttribute::Intelligence => self.intelligence,
+            PrimaryAttribute::Luck => self.luck,
+            PrimaryAttribute::Agility => self.agility,
+            PrimaryAttribute::Magic => self.magic,
+            PrimaryAttribute::Charm => self.charm,
+        }
+    }
+
+    pub fn get_mut(&mut self, attr: PrimaryAttribute) -> &mut i32 {
+        match attr {
+            PrimaryAttribute::Strength => &mut self.strength,
+            PrimaryAttribute::Intelligence => &mut self.intelligence,
+            PrimaryAttribute::Luck => &mut self.luck,
+            PrimaryAttribute::Agility => &mut self.agility,
+            PrimaryAttribute::Magic => &mut self.magic,
+            PrimaryAttribute::Charm => &mut self.charm,
+        }
+    }
+
+    /// `Default` roll for every attribute, then two attributes are upgraded
+    /// against luck and other two are downgraded against chaos
+    pub fn generate(rng: &mut impl Rng) -> Self {
+        let mut attrs = Self::default();
+        for attr in PrimaryAttribute::ALL {
+            *attrs.get_mut(attr) = Roll::DEFAULT.roll(rng);
+        }
+
+        let mut order = PrimaryAttribute::ALL;
+        order.shuffle(rng);
+        // Luck may be upgraded first, the second upgrade still uses the original value
+        let luck = attrs.luck;
+        for attr in &order[0..2] {
+            let value = attrs.get_mut(*attr);
+            let luck_roll = Roll::attribute(luck, 0);
+            if contest(&luck_roll, &Roll::attribute(*value, 0), rng) == ContestOutcome::Win {
+                *value = (*value).max(Roll::DEFAULT.roll(rng));
+            }
+        }
+        for attr in &order[2..4] {
+            let value = attrs.get_mut(*attr);
+            if contest(&Roll::CHAOS, &Roll::attribute(*value, 0), rng) == ContestOutcome::Win {
+                *value = (*value).min(Roll::DEFAULT.roll(rng));
+            }
+        }
+        attrs
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum SecondaryAttribute {
+    Constitution,
+    Speed,
+    Perception,
+    MeleeCombat,
+    RangedCombat,
+    MagicCombat,
+    Willpower,
+}
+
+impl SecondaryAttribute {
+    pub const ALL: [SecondaryAttribute; 7] = [
+        Self::Constitution,
+        Self::Speed,
+        Self::Perception,
+        Self::MeleeCombat,
+        Self::RangedCombat,
+        Self::MagicCombat,
+        Self::Willpower,
+    ];
+
+    /// Generated value must not beat the roll of this attribute
+    pub fn ceiling(self) -> PrimaryAttribute {
+        use PrimaryAttribute::*;
+        match self {
+            Self::Constitution | Self::MeleeCombat => Strength,
+            Self::Speed | Self::RangedCombat => Agility,
+            Self::Perception | Self::Willpower => Intelligence,
+            Self::MagicCombat => Magic,
+        }
+    }
+
+    /// Generated value must not lose to the roll of this attribute
+    pub fn floor(self) -> PrimaryAttribute {
+        use PrimaryAttribute::*;
+        match self {
+            Self::Constitution | Self::MeleeCombat => Agility,
+            Self::Speed => Strength,
+            Self::Perception | Self::RangedCombat | Self::MagicCombat => Luck,
+            Self::Willpower => Charm,
+        }
+    }
+}
+
+/// Number of attempts to generate a secondary attribute between its ceiling and floor
+const SECONDARY_ATTRIBUTE_ATTEMPTS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect, Serialize, Deserialize)]
+pub struct SecondaryAttributes {
+    pub constitution: i32,
+    pub speed: i32,
+    pub perception: i32,
+    pub melee_combat: i32,
+    pub ranged_combat: i32,
+    pub magic_combat: i32,
+    pub willpower: i32,
+}
+
+impl SecondaryAttributes {
+    pub fn get(&self, attr: SecondaryAttribute) -> i32 {
+        match attr {
+            SecondaryAttribute::Constitution => self.constitution,
+            SecondaryAttribute::Speed => self.speed,
+            SecondaryAttribute::Perception => self.perception,
+            SecondaryAttribute::MeleeCombat => self.melee_combat,
+            SecondaryAttribute::RangedCombat => self.ranged_combat,
+            SecondaryAttribute::MagicCombat => self.magic_combat,
+            SecondaryAttribute::Willpower => self.willpower,
+        }
+    }
+
+    pub fn get_mut(&mut self, attr: SecondaryAttribute) -> &mut i32 {
+        match attr {
+            SecondaryAttribute::Constitution => &mut self.constitution,
+            SecondaryAttribute::Speed => &mut self.speed,
+            SecondaryAttribute::Perception => &mut self.perception,
+            SecondaryAttribute::MeleeCombat => &mut self.melee_combat,
+            SecondaryAttribute::RangedCombat => &mut self.ranged_combat,
+            SecondaryAttribute::MagicCombat => &mut self.magic_combat,
+            SecondaryAttribute::Willpower => &mut self.willpower,
+        }
+    }
+
+    pub fn generate(primary: &PrimaryAttributes, rng: &mut impl Rng) -> Self {
+        let mut attrs = Self::default();
+        for attr in SecondaryAttribute::ALL {
+            let ceiling = primary.get(attr.ceiling());
+            let floor = primary.get(attr.floor());
+            let mut value = 0;
+            for _ in 0..SECONDARY_ATTRIBUTE_ATTEMPTS {
+                value = Roll::DEFAULT.roll(rng);
+                let generated = Roll::attribute(value, 0).roll(rng);
+                let ceiling_roll = Roll::attribute(ceiling, 0).roll(rng);
+                if ContestOutcome::of(generated, ceiling_roll) == ContestOutcome::Win {
+                    continue;
+                }
+                let floor_roll = Roll::attribute(floor, 0).roll(rng);
+                if ContestOutcome::of(generated, floor_roll) == ContestOutcome::Lose {
+                    continue;
+                }
+                break;
+            }
+            *attrs.get_mut(attr) = value;
+        }
+        attrs
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum MagicSchool {
+    /// Fire, water, wind, ground
+    Elemental,
+    /// Transmutation, healing, necromancy
+    Medical,
+    /// Potion making, chemistry, enchantment of tools and books
+    Alchemy,
+    /// Summoning, control, transformation, illusion
+    Evocation,
+    /// Astral projection, prophecy, prediction, divine shielding
+    Divination,
+}
+
+impl MagicSchool {
+    pub const ALL: [MagicSchool; 5] = [
+        Self::Elemental,
+        Self::Medical,
+        Self::Alchemy,
+        Self::Evocation,
+        Self::Divination,
+    ];
+
+    pub fn base_attribute(self) -> PrimaryAttribute {
+        match self {
+            Self::Elemental | Self::Evocation => PrimaryAttribute::Magic,
+            Self::Medical | Self::Alchemy => PrimaryAttribute::Intelligence,
+            Self::Divination => PrimaryAttribute::Charm,
+        }
+    }
+}
+
+/// Bonus or penalty to a primary attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum CatTrait {
+    Mighty,
+    Weakly,
+    Smarty,
+    Dumby,
+    Lucky,
+    Unlucky,
+    Swifty,
+    Clumsy,
+    Wizardly,
+    Dully,
+    Pretty,
+    Scruffy,
+}
+
+pub const TRAIT_BONUS: i32 = 3;
+/// Cats are generated with up to this number of traits
+const MAX_GENERATED_TRAITS: usize = 3;
+
+impl CatTrait {
+    pub const ALL: [CatTrait; 12] = [
+        Self::Mighty,
+        Self::Weakly,
+        Self::Smarty,
+        Self::Dumby,
+        Self::Lucky,
+        Self::Unlucky,
+        Self::Swifty,
+        Self::Clumsy,
+        Self::Wizardly,
+        Self::Dully,
+        Self::Pretty,
+        Self::Scruffy,
+    ];
+
+    pub fn attribute(self) -> PrimaryAttribute {
+        use PrimaryAttribute::*;
+        match self {
+            Self::Mighty | Self::Weakly => Strength,
+            Self::Smarty | Self::Dumby => Intelligence,
+            Self::Lucky | Self::Unlucky => Luck,
+            Self::Swifty | Self::Clumsy => Agility,
+            Self::Wizardly | Self::Dully => Magic,
+            Self::Pretty | Self::Scruffy => Charm,
+        }
+    }
+
+    pub fn bonus(self) -> i32 {
+        match self {
+            Self::Mighty
+            | Self::Smarty
+            | Self::Lucky
+            | Self::Swifty
+            | Self::Wizardly
+            | Self::Pretty => TRAIT_BONUS,
+            _ => -TRAIT_BONUS,
+        }
+    }
+
+    fn of(attr: PrimaryAttribute, positive: bool) -> Self {
+        use PrimaryAttribute::*;
+        match (attr, positive) {
+            (Strength, true) => Self::Mighty,
+            (Strength, false) => Self::Weakly,
+            (Intelligence, true) => Self::Smarty,
+            (Intelligence, false) => Self::Dumby,
+            (Luck, true) => Self::Lucky,
+            (Luck, false) => Self::Unlucky,
+            (Agility, true) => Self::Swifty,
+            (Agility, false) => Self::Clumsy,
+            (Magic, true) => Self::Wizardly,
+            (Magic, false) => Self::Dully,
+            (Charm, true) => Self::Pretty,
+            (Charm, false) => Self::Scruffy,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Reflect, Serialize, Deserialize)]
+pub struct CatTraits(pub Vec<CatTrait>);
+
+impl CatTraits {
+    /// Random traits, at most one per attribute so they never contradict each other
+    pub fn generate(rng: &mut impl Rng) -> Self {
+        let count = rng.gen_range(0..=MAX_GENERATED_TRAITS);
+        let mut attrs = PrimaryAttribute::ALL;
+        attrs.shuffle(rng);
+        Self(
+            attrs[0..count]
+                .iter()
+                .map(|attr| CatTrait::of(*attr, rng.gen_bool(0.5)))
+                .collect(),
+        )
+    }
+
+    /// Sum of trait bonuses to the attribute
+    pub fn modifier(&self, attr: PrimaryAttribute) -> i32 {
+        self.0
+            .iter()
+            .filter(|t| t.attribute() == attr)
+            .map(|t| t.bonus())
+            .sum()
+    }
+}
+
+/// Everything which makes a cat what it is
+#[derive(Component, Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct CatSheet {
+    pub primary: PrimaryAttributes,
+    pub secondary: SecondaryAttributes,
+    pub traits: CatTraits,
+}
+
+impl CatSheet {
+    pub fn generate(rng: &mut impl Rng) -> Self {
+        let primary = PrimaryAttributes::generate(rng);
+        let secondary = SecondaryAttributes::generate(&primary, rng);
+        let traits = CatTraits::generate(rng);
+        Self {
+            primary,
+            secondary,
+            traits,
+        }
+    }
+
+    /// Affinity with the school of magic. The base attribute affects it more than the aux one
+    pub fn school_aptitude(&self, school: MagicSchool) -> i32 {
+        let aux = match school {
+            MagicSchool::Elemental => 0,
+            MagicSchool::Medical => self.secondary.perception,
+            MagicSchool::Alchemy => self.secondary.willpower,
+            MagicSchool::Evocation => self.primary.charm,
+            MagicSchool::Divination => self.primary.luck,
+        };
+        self.primary.get(school.base_attribute()) + aux / 2
+    }
+
+    /// School with the highest aptitude, the first one listed on ties
+    pub fn best_school(&self) -> MagicSchool {
+        let mut best = MagicSchool::ALL[0];
+        for school in MagicSchool::ALL {
+            if self.school_aptitude(school) > self.school_aptitude(best) {
+                best = school;
+            }
+        }
+        best
+    }
+
+    /// Attribute roll, e.g. `luck roll` is `1d{luck}+{lucky}`
+    pub fn attribute_roll(&self, attr: PrimaryAttribute) -> Roll {
+        Roll::attribute(self.primary.get(attr), self.traits.modifier(attr))
+    }
+}
+
+const NAME_SYLLABLES: [&str; 16] = [
+    "mi", "nya", "ko", "ta", "ru", "shi", "po", "yu", "ke", "mo", "chi", "ra", "fu", "ne", "so",
+    "ki",
+];
+
+/// Name of two or three syllables, e.g. "Mochi"
+pub fn generate_cat_name(rng: &mut impl Rng) -> String {
+    let syllables = rng.gen_range(2..=3);
+    let name: String = (0..syllables)
+        .map(|_| *NAME_SYLLABLES.choose(rng).unwrap())
+        .collect();
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name,
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_cat.rs"]
+mod test_cat;
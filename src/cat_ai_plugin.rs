@@ -0,0 +1,556 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    alchemy_plugin::UseItem,
+    calendar_plugin::{Calendar, CalendarConfig, CalendarSet},
+    cat::{CatSheet, PrimaryAttribute},
+    combat_plugin::Fighting,
+    designation_plugin::{JOB_RETRY_TICKS, Job, JobId, JobQueue, Working},
+    experience_plugin::{Practising, Skill},
+    game_map_plugin::{GameMap, GameMapData, LayerObject},
+    item::{Inventory, ItemStack, carry_capacity, overload_speed},
+    needs_plugin::{NEED_MAX, Need, NeedLevel, NeedSource, Needs, Using},
+    pathfinding::{cell_center, cell_of, find_path, is_walkable, neighbours},
+    rng_plugin::GameRng,
+    simulation_clock_plugin::{SimulationClock, SimulationSet},
+    student_plugin::Enrollment,
+    timetable_plugin::{Class, Classrooms, LessonSlot, Timetable},
+};
+
+/// Autonomous cats: every cat without a task scores the possible actions
+/// and walks off to do the best one
+pub struct CatAiPlugin;
+
+/// Cells walked per tick by a cat of average agility
+pub const WALK_SPEED: f32 = 0.1;
+/// Utility of attending the class the cat is scheduled for
+pub const CLASS_UTILITY: f32 = 0.6;
+/// Utility of doing the job assigned to the cat
+pub const WORK_UTILITY: f32 = 0.3;
+/// Utility of studying alone for a student
+pub const HOMEWORK_UTILITY: f32 = 0.15;
+/// Utility of wandering around for a cat with all needs satisfied
+pub const WANDER_UTILITY: f32 = 0.1;
+/// Utility added by every point of trait modifier of the attribute the action relies on
+const TRAIT_UTILITY: f32 = 0.01;
+/// Tasks are abandoned after this many ticks
+pub const TASK_TIMEOUT: u64 = 600;
+/// Students study alone for this many ticks
+pub const HOMEWORK_TICKS: u64 = 100;
+/// Wander destinations are at most this many cells away
+const WANDER_RADIUS: i32 = 3;
+const WANDER_ATTEMPTS: usize = 8;
+/// Attribute value which keeps the base walking speed
+const AVERAGE_ATTRIBUTE: f32 = 13.0;
+/// Cats live on the ground layer
+pub const CAT_LAYER: usize = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum Action {
+    Eat,
+    Sleep,
+    Groom,
+    Play,
+    Study,
+    Teach,
+    Work,
+    Wander,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Self::Eat,
+        Self::Sleep,
+        Self::Groom,
+        Self::Play,
+        Self::Study,
+        Self::Teach,
+        Self::Work,
+        Self::Wander,
+    ];
+
+    /// Need satisfied by using a map object or an item
+    pub fn need(self) -> Option<Need> {
+        match self {
+            Self::Eat => Some(Need::Hunger),
+            Self::Sleep => Some(Need::Energy),
+            Self::Groom => Some(Need::Hygiene),
+            Self::Play => Some(Need::Fun),
+            _ => None,
+        }
+    }
+}
+
+/// What the cat considers when choosing an action
+pub struct Situation<'a> {
+    pub sheet: &'a CatSheet,
+    pub needs: &'a Needs,
+    pub student: bool,
+    /// Class going on now which the cat attends
+    pub attending: Option<&'a Class>,
+    /// Class going on now which the cat teaches
+    pub teaching: Option<&'a Class>,
+    /// Job assigned to the cat
+    pub job: Option<&'a Job>,
+}
+
+/// Grows quadratically as the need drops. Critical needs beat any schedule
+fn urgency(needs: &Needs, need: Need) -> f32 {
+    let lack = 1.0 - needs.get(need) / NEED_MAX;
+    let critical = match needs.level(need) {
+        NeedLevel::Critical => 1.0,
+        _ => 0.0,
+    };
+    lack * lack + critical
+}
+
+/// How much the cat wants to do the action right now
+pub fn utility(action: Action, situation: &Situation) -> f32 {
+    let traits = |attr| situation.sheet.traits.modifier(attr) as f32 * TRAIT_UTILITY;
+    let utility = match action {
+        Action::Eat | Action::Sleep | Action::Groom | Action::Play => action
+            .need()
+            .map_or(0.0, |need| urgency(situation.needs, need)),
+        Action::Study => match situation.attending {
+            Some(_) => CLASS_UTILITY + traits(PrimaryAttribute::Intelligence),
+            None if situation.student => HOMEWORK_UTILITY + traits(PrimaryAttribute::Intelligence),
+            None => 0.0,
+        },
+        Action::Teach => situation
+            .teaching
+            .map_or(0.0, |_| CLASS_UTILITY + traits(PrimaryAttribute::Charm)),
+        Action::Work => situation
+            .job
+            .map_or(0.0, |job| WORK_UTILITY + traits(job.kind.attribute())),
+        Action::Wander => {
+            WANDER_UTILITY
+                + urgency(situation.needs, Need::Fun) / 2.0
+                + traits(PrimaryAttribute::Agility)
+        }
+    };
+    utility.max(0.0)
+}
+
+/// Action the cat is busy with
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Task {
+    pub action: Action,
+    /// Map object to interact with
+    pub target: Option<Entity>,
+    pub destination: UVec2,
+    /// Lesson the task belongs to, it ends with the lesson
+    pub lesson: Option<LessonSlot>,
+    pub job: Option<JobId>,
+    /// Simulation tick when the task was chosen
+    pub started: u64,
+    /// The cat reached the destination and began the interaction
+    pub arrived: bool,
+}
+
+/// Cells left to walk, next one first
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct WalkPath(pub Vec<UVec2>);
+
+/// Sent when a cat picks a new task
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskChosen {
+    pub cat: Entity,
+    pub action: Action,
+}
+
+/// Without a map cats walk straight to the destination
+fn plan_path(map: Option<&GameMap>, from: UVec2, to: UVec2) -> Option<Vec<UVec2>> {
+    match map {
+        Some(map) => find_path(map, CAT_LAYER, from, to),
+        None if from == to => Some(vec![]),
+        None => Some(vec![to]),
+    }
+}
+
+/// Place of the cat in the classroom: the teacher takes the first cell, students follow
+fn seat(
+    class: &Class,
+    cat: Entity,
+    classrooms: &Classrooms,
+    map: Option<&GameMap>,
+) -> Option<UVec2> {
+    let room = classrooms.0.get(class.classroom)?;
+    let cells: Vec<UVec2> = (room.min.y..=room.max.y)
+        .flat_map(|row| (room.min.x..=room.max.x).map(move |col| UVec2::new(col, row)))
+        .filter(|cell| map.is_none_or(|map| is_walkable(map, room.layer, *cell)))
+        .collect();
+    let index = class
+        .students
+        .iter()
+        .position(|student| *student == cat)
+        .map_or(0, |index| index + 1);
+    (!cells.is_empty()).then(|| cells[index % cells.len()])
+}
+
+/// Random reachable cell near the cat, or the cell it stands on
+fn wander_destination(rng: &mut impl Rng, map: Option<&GameMap>, from: UVec2) -> UVec2 {
+    for _ in 0..WANDER_ATTEMPTS {
+        let offset = IVec2::new(
+            rng.gen_range(-WANDER_RADIUS..=WANDER_RADIUS),
+            rng.gen_range(-WANDER_RADIUS..=WANDER_RADIUS),
+        );
+        let cell = (from.as_ivec2() + offset).max(IVec2::ZERO).as_uvec2();
+        if map.is_none_or(|map| is_walkable(map, CAT_LAYER, cell)) {
+            return cell;
+        }
+    }
+    from
+}
+
+type IdleCat<'a> = (
+    Entity,
+    &'a CatSheet,
+    &'a Needs,
+    &'a Transform,
+    Has<Enrollment>,
+    Option<&'a Inventory>,
+);
+
+/// Stockpile or workstation which may hold items
+type ItemHolder<'a> = (
+    Entity,
+    &'a Inventory,
+    &'a Transform,
+    Option<&'a LayerObject>,
+);
+
+/// Idle cats score every feasible action and take the best one.
+/// Needs are met by map objects, by items the cat carries or by items stored nearby.
+/// Ties are broken by the game RNG, cats choose in a stable order to keep it reproducible
+#[allow(clippy::too_many_arguments)]
+fn choose_tasks(
+    cats: Query<IdleCat, (Without<Task>, Without<Fighting>)>,
+    sources: Query<(Entity, &NeedSource, &Transform)>,
+    holders: Query<ItemHolder, Without<CatSheet>>,
+    timetable: Res<Timetable>,
+    classrooms: Res<Classrooms>,
+    calendar: Res<Calendar>,
+    config: Res<CalendarConfig>,
+    clock: Res<SimulationClock>,
+    map_data: Option<Res<GameMapData>>,
+    mut jobs: Option<ResMut<JobQueue>>,
+    mut rng: ResMut<GameRng>,
+    mut chosen: EventWriter<TaskChosen>,
+    mut cmds: Commands,
+) {
+    // The map is empty until it is spawned
+    let map = map_data
+        .as_deref()
+        .map(|data| data.map())
+        .filter(|map| map.layers > 0);
+    let slot = LessonSlot::at(&calendar, &config);
+    let mut sources: Vec<(Entity, Need, UVec2)> = sources
+        .iter()
+        .map(|(entity, source, tr)| (entity, source.need, cell_of(tr.translation)))
+        .collect();
+    for (holder, inventory, tr, layer) in &holders {
+        if layer.is_some_and(|layer| layer.0 != CAT_LAYER) {
+            continue;
+        }
+        for need in inventory.0.iter().filter_map(|stack| stack.kind.restores()) {
+            sources.push((holder, need, cell_of(tr.translation)));
+        }
+    }
+    sources.sort_by_key(|(entity, need, _)| (*entity, *need as usize));
+    sources.dedup();
+    let mut order: Vec<_> = cats.iter().collect();
+    order.sort_by_key(|(cat, ..)| *cat);
+    let mut unreachable = vec![];
+
+    for (cat, sheet, needs, tr, student, inventory) in order {
+        let from = cell_of(tr.translation);
+        let classes: Vec<&Class> = timetable.classes_at(slot).collect();
+        let situation = Situation {
+            sheet,
+            needs,
+            student,
+            attending: classes
+                .iter()
+                .find(|class| class.students.contains(&cat))
+                .copied(),
+            teaching: classes.iter().find(|class| class.teacher == cat).copied(),
+            // Cats only reach jobs on their own layer
+            job: jobs
+                .as_deref()
+                .and_then(|jobs| jobs.assigned_to(cat))
+                .filter(|job| job.layer == CAT_LAYER),
+        };
+        // (action, target, destination, path) of every action the cat can do now
+        let mut feasible: Vec<(Action, Option<Entity>, UVec2, Vec<UVec2>)> = vec![];
+        for action in Action::ALL {
+            let plan = match action {
+                // Carried items are used on the spot
+                Action::Eat | Action::Sleep | Action::Groom | Action::Play
+                    if inventory.is_some_and(|inventory| {
+                        inventory
+                            .0
+                            .iter()
+                            .any(|stack| stack.kind.restores() == action.need())
+                    }) =>
+                {
+                    Some((Some(cat), from, vec![]))
+                }
+                Action::Eat | Action::Sleep | Action::Groom | Action::Play => {
+                    let mut near: Vec<&(Entity, Need, UVec2)> = sources
+                        .iter()
+                        .filter(|(_, need, _)| Some(*need) == action.need())
+                        .collect();
+                    near.sort_by_key(|(_, _, cell)| {
+                        cell.as_ivec2().distance_squared(from.as_ivec2())
+                    });
+                    near.into_iter().find_map(|(source, _, cell)| {
+                        let path = plan_path(map, from, *cell)?;
+                        Some((Some(*source), *cell, path))
+                    })
+                }
+                Action::Study | Action::Teach => {
+                    let class = match action {
+                        Action::Study => situation.attending,
+                        _ => situation.teaching,
+                    };
+                    match class {
+                        Some(class) => seat(class, cat, &classrooms, map)
+                            .and_then(|cell| Some((None, cell, plan_path(map, from, cell)?))),
+                        // Homework is done on the spot
+                        None => Some((None, from, vec![])),
+                    }
+                }
+                // Work from the closest cell next to the job
+                Action::Work => situation.job.and_then(|job| {
+                    let spots: Vec<UVec2> = match map {
+                        Some(map) => neighbours(job.cell)
+                            .filter(|cell| is_walkable(map, CAT_LAYER, *cell))
+                            .collect(),
+                        None => vec![job.cell],
+                    };
+                    spots
+                        .into_iter()
+                        .filter_map(|cell| Some((None, cell, plan_path(map, from, cell)?)))
+                        .min_by_key(|(_, _, path)| path.len())
+                }),
+                // Destination is picked after the choice to not waste random numbers
+                Action::Wander => Some((None, from, vec![])),
+            };
+            if let Some((target, destination, path)) = plan {
+                feasible.push((action, target, destination, path));
+            }
+        }
+        // Other cats may reach the job, the cat takes another one meanwhile
+        let can_work = feasible.iter().any(|(action, ..)| *action == Action::Work);
+        unreachable.extend(situation.job.filter(|_| !can_work).map(|job| job.id));
+
+        let scores: Vec<f32> = feasible
+            .iter()
+            .map(|(action, ..)| utility(*action, &situation))
+            .collect();
+        let best = scores.iter().copied().fold(0.0, f32::max);
+        let ties: Vec<usize> = (0..feasible.len())
+            .filter(|i| scores[*i] > 0.0 && best - scores[*i] <= f32::EPSILON)
+            .collect();
+        let index = match ties.len() {
+            0 => continue,
+            1 => ties[0],
+            len => ties[rng.gen_range(0..len)],
+        };
+        let (action, target, mut destination, mut path) = feasible.swap_remove(index);
+        if action == Action::Wander {
+            destination = wander_destination(&mut rng.0, map, from);
+            path = plan_path(map, from, destination).unwrap_or_default();
+            destination = path.last().copied().unwrap_or(from);
+        }
+        let lesson = match action {
+            Action::Study => situation.attending.map(|_| slot),
+            Action::Teach => Some(slot),
+            _ => None,
+        };
+        cmds.entity(cat).insert((
+            Task {
+                action,
+                target,
+                destination,
+                lesson,
+                job: situation
+                    .job
+                    .filter(|_| action == Action::Work)
+                    .map(|job| job.id),
+                started: clock.tick,
+                arrived: false,
+            },
+            WalkPath(path),
+        ));
+        chosen.write(TaskChosen { cat, action });
+    }
+    if let Some(jobs) = jobs.as_mut() {
+        for id in unreachable {
+            jobs.postpone(id, clock.tick + JOB_RETRY_TICKS);
+        }
+    }
+}
+
+type Walker<'a> = (
+    &'a mut Transform,
+    &'a mut WalkPath,
+    Option<&'a CatSheet>,
+    Option<&'a Inventory>,
+);
+
+/// Move cats along their paths, agile cats walk faster and overloaded ones slower
+fn walk(mut cats: Query<Walker>) {
+    for (mut tr, mut path, sheet, inventory) in &mut cats {
+        let Some(next) = path.0.first().copied() else {
+            continue;
+        };
+        let agility = sheet.map_or(AVERAGE_ATTRIBUTE, |sheet| {
+            let agility = PrimaryAttribute::Agility;
+            (sheet.primary.get(agility) + sheet.traits.modifier(agility)) as f32
+        });
+        let load = match (sheet, inventory) {
+            (Some(sheet), Some(inventory)) => {
+                overload_speed(inventory.weight(), carry_capacity(sheet))
+            }
+            _ => 1.0,
+        };
+        let step = WALK_SPEED * (agility / AVERAGE_ATTRIBUTE).clamp(0.5, 2.0) * load;
+        let target = cell_center(next, tr.translation.y);
+        let offset = target - tr.translation;
+        if offset.length() <= step {
+            tr.translation = target;
+            path.0.remove(0);
+        } else {
+            tr.translation += offset.normalize() * step;
+        }
+    }
+}
+
+/// Cats who reached the destination begin the interaction.
+/// Items are taken from the target and used straight away
+fn start_interactions(
+    mut cats: Query<(Entity, &mut Task, &WalkPath, &CatSheet)>,
+    mut inventories: Query<&mut Inventory>,
+    mut cmds: Commands,
+) {
+    for (cat, mut task, path, sheet) in &mut cats {
+        if task.arrived || !path.0.is_empty() {
+            continue;
+        }
+        task.arrived = true;
+        match (task.action, task.target) {
+            (Action::Eat | Action::Sleep | Action::Groom | Action::Play, Some(target)) => {
+                let need = task.action.need();
+                let item = inventories.get_mut(target).ok().and_then(|mut inventory| {
+                    let kind = inventory
+                        .0
+                        .iter()
+                        .map(|stack| stack.kind)
+                        .find(|kind| kind.restores() == need)?;
+                    Some((kind, inventory.take_best(kind)?))
+                });
+                let Some((kind, quality)) = item else {
+                    cmds.entity(cat).insert(Using(target));
+                    continue;
+                };
+                match inventories.get_mut(cat) {
+                    Ok(mut inventory) => inventory.add(kind, quality, 1),
+                    Err(_) => {
+                        cmds.entity(cat).insert(Inventory(vec![ItemStack {
+                            kind,
+                            quality,
+                            count: 1,
+                        }]));
+                    }
+                }
+                cmds.trigger(UseItem { cat, kind });
+            }
+            (Action::Work, _) => {
+                if let Some(job) = task.job {
+                    cmds.entity(cat).insert(Working(job));
+                }
+            }
+            (Action::Study, None) if task.lesson.is_none() => {
+                cmds.entity(cat)
+                    .insert(Practising(Skill::School(sheet.best_school())));
+            }
+            _ => {}
+        }
+    }
+}
+
+type BusyCat<'a> = (
+    Entity,
+    &'a Task,
+    &'a Needs,
+    Has<Using>,
+    Has<Working>,
+    Has<Fighting>,
+);
+
+/// Drop tasks which are done, timed out or interrupted by a critical need or a fight
+fn finish_tasks(
+    cats: Query<BusyCat>,
+    calendar: Res<Calendar>,
+    config: Res<CalendarConfig>,
+    clock: Res<SimulationClock>,
+    mut cmds: Commands,
+) {
+    let slot = LessonSlot::at(&calendar, &config);
+    for (cat, task, needs, using, working, fighting) in &cats {
+        let elapsed = clock.tick.saturating_sub(task.started);
+        let is_critical = |need: Need| needs.level(need) == NeedLevel::Critical;
+        let interrupted = fighting
+            || !task.action.need().is_some_and(is_critical)
+                && Action::ALL
+                    .into_iter()
+                    .filter_map(Action::need)
+                    .any(is_critical);
+        let done = match task.action {
+            // Using stops once the need is satisfied
+            Action::Eat | Action::Sleep | Action::Groom | Action::Play => task.arrived && !using,
+            Action::Study | Action::Teach => match task.lesson {
+                Some(lesson) => lesson != slot,
+                None => task.arrived && elapsed >= HOMEWORK_TICKS,
+            },
+            // Working stops once the job is done or cancelled
+            Action::Work => task.arrived && !working,
+            Action::Wander => task.arrived,
+        };
+        if done || interrupted || elapsed >= TASK_TIMEOUT {
+            cmds.entity(cat)
+                .remove::<(Task, WalkPath, Using, Practising, Working)>();
+        }
+    }
+}
+
+/// Systems of autonomous cats
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct CatAiSet;
+
+impl Plugin for CatAiPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Task>();
+        app.register_type::<WalkPath>();
+        app.add_event::<TaskChosen>();
+        app.configure_sets(
+            FixedUpdate,
+            CatAiSet.in_set(SimulationSet).after(CalendarSet),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (finish_tasks, choose_tasks, walk, start_interactions)
+                .chain()
+                .in_set(CatAiSet),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_cat_ai_plugin.rs"]
+mod test_cat_ai_plugin;
@@ -0,0 +1,571 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    calendar_plugin::CalendarSet,
+    cat::{CatSheet, SecondaryAttribute, SecondaryAttributes},
+    cat_ai_plugin::{CAT_LAYER, CatAiSet},
+    game_map_plugin::LayerObject,
+    game_state_plugin::GameObject,
+    rng_plugin::GameRng,
+    roll::{ContestOutcome, Roll},
+    simulation_clock_plugin::{SimulationClock, SimulationSet},
+};
+
+/// Encounters between cats and summoned creatures, resolved turn by turn
+/// with contested rolls of combat attributes
+pub struct CombatPlugin;
+
+/// Every point of constitution gives this much health
+pub const HEALTH_PER_CONSTITUTION: i32 = 2;
+/// Fighters act once per this many ticks
+pub const TURN_TICKS: u64 = 10;
+/// Every this many points of the attack beating the defence add a point of damage
+pub const DAMAGE_DIVISOR: i32 = 3;
+/// Attack beating the defence by this much also applies the status effect of the style
+pub const EFFECT_MARGIN: i32 = 5;
+/// Number of turns of the fighter a status effect lasts
+pub const EFFECT_TURNS: u32 = 3;
+/// Damage taken by a bleeding fighter at the start of every turn
+pub const BLEEDING_DAMAGE: i32 = 1;
+/// Cats out of combat heal a point of health per this many ticks
+pub const HEAL_TICKS: u64 = 50;
+/// Creatures attack cats within this distance
+pub const ENGAGE_DISTANCE: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum CombatStyle {
+    Melee,
+    Ranged,
+    Magic,
+}
+
+impl CombatStyle {
+    pub const ALL: [CombatStyle; 3] = [Self::Melee, Self::Ranged, Self::Magic];
+
+    pub fn attack(self) -> SecondaryAttribute {
+        match self {
+            Self::Melee => SecondaryAttribute::MeleeCombat,
+            Self::Ranged => SecondaryAttribute::RangedCombat,
+            Self::Magic => SecondaryAttribute::MagicCombat,
+        }
+    }
+
+    /// Blows are parried, arrows are dodged and spells are countered
+    pub fn defence(self) -> SecondaryAttribute {
+        match self {
+            Self::Melee => SecondaryAttribute::MeleeCombat,
+            Self::Ranged => SecondaryAttribute::Speed,
+            Self::Magic => SecondaryAttribute::MagicCombat,
+        }
+    }
+
+    /// Effect of a strong hit
+    pub fn effect(self) -> StatusEffect {
+        match self {
+            Self::Melee => StatusEffect::Bleeding,
+            Self::Ranged => StatusEffect::Stunned,
+            Self::Magic => StatusEffect::Frightened,
+        }
+    }
+
+    /// Style with the highest attack, the first one listed on ties
+    pub fn best(attrs: &SecondaryAttributes) -> Self {
+        let mut best = Self::ALL[0];
+        for style in Self::ALL {
+            if attrs.get(style.attack()) > attrs.get(best.attack()) {
+                best = style;
+            }
+        }
+        best
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum StatusEffect {
+    /// Loses health at the start of every turn
+    Bleeding,
+    /// Skips turns
+    Stunned,
+    /// Keeps the distance and only attacks from range
+    Frightened,
+}
+
+impl StatusEffect {
+    /// Mental effects are resisted by willpower
+    pub fn is_mental(self) -> bool {
+        matches!(self, Self::Frightened)
+    }
+}
+
+/// Summoned creature taking part in encounters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum CreatureKind {
+    /// Quick and nasty in melee
+    Imp,
+    /// Tough, slow and mindless
+    Golem,
+    /// Frail spellcaster with a strong will
+    Wisp,
+}
+
+impl CreatureKind {
+    pub fn attributes(self) -> SecondaryAttributes {
+        let (constitution, speed, melee_combat, ranged_combat, magic_combat, willpower) = match self
+        {
+            Self::Imp => (10, 18, 16, 6, 8, 8),
+            Self::Golem => (24, 6, 14, 4, 4, 4),
+            Self::Wisp => (6, 14, 4, 8, 18, 20),
+        };
+        SecondaryAttributes {
+            constitution,
+            speed,
+            melee_combat,
+            ranged_combat,
+            magic_combat,
+            willpower,
+            ..default()
+        }
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Creature(pub CreatureKind);
+
+/// Combat attributes of a cat or a creature
+pub fn combat_attributes(
+    sheet: Option<&CatSheet>,
+    creature: Option<&Creature>,
+) -> SecondaryAttributes {
+    match (sheet, creature) {
+        (Some(sheet), _) => sheet.secondary,
+        (None, Some(creature)) => creature.0.attributes(),
+        (None, None) => SecondaryAttributes::default(),
+    }
+}
+
+pub fn max_health(attrs: &SecondaryAttributes) -> i32 {
+    (attrs.constitution * HEALTH_PER_CONSTITUTION).max(1)
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Health {
+    pub current: i32,
+    pub max: i32,
+}
+
+impl Health {
+    pub fn full(max: i32) -> Self {
+        Self { current: max, max }
+    }
+}
+
+/// Result of a single attack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Strike {
+    pub attack: i32,
+    pub defence: i32,
+    /// Zero for a miss
+    pub damage: i32,
+    /// Effect landed on the defender
+    pub effect: Option<StatusEffect>,
+    /// The defender shrugged off a mental effect
+    pub resisted: bool,
+}
+
+/// Attack roll contested by the defence roll. A hit deals more damage the more it wins by,
+/// a strong hit also applies the effect of the style unless the defender resists it
+pub fn strike(
+    attacker: &SecondaryAttributes,
+    defender: &SecondaryAttributes,
+    style: CombatStyle,
+    rng: &mut impl Rng,
+) -> Strike {
+    let attack = Roll::attribute(attacker.get(style.attack()), 0).roll(rng);
+    let defence = Roll::attribute(defender.get(style.defence()), 0).roll(rng);
+    let mut strike = Strike {
+        attack,
+        defence,
+        damage: 0,
+        effect: None,
+        resisted: false,
+    };
+    if ContestOutcome::of(attack, defence) != ContestOutcome::Win {
+        return strike;
+    }
+    strike.damage = 1 + (attack - defence) / DAMAGE_DIVISOR;
+    if attack - defence >= EFFECT_MARGIN {
+        let effect = style.effect();
+        if effect.is_mental() {
+            let will = Roll::attribute(defender.willpower, 0).roll(rng);
+            let power = Roll::attribute(attacker.magic_combat, 0).roll(rng);
+            strike.resisted = ContestOutcome::of(will, power) == ContestOutcome::Win;
+        }
+        if !strike.resisted {
+            strike.effect = Some(effect);
+        }
+    }
+    strike
+}
+
+/// Fight between two sides
+#[derive(Component, Debug, Clone, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Encounter {
+    pub sides: [Vec<Entity>; 2],
+    /// Simulation tick when the encounter started
+    pub started: u64,
+}
+
+/// Fighter taking part in the encounter
+#[derive(Component, Debug, Clone, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Fighting {
+    pub encounter: Entity,
+    pub side: usize,
+    /// Status effects with the number of turns left
+    pub effects: Vec<(StatusEffect, u32)>,
+}
+
+impl Fighting {
+    pub fn has(&self, effect: StatusEffect) -> bool {
+        self.effects.iter().any(|(e, _)| *e == effect)
+    }
+}
+
+/// Trigger to start a fight, e.g. a duel of two students or students against summoned creatures.
+/// Fighters busy with another encounter or without health stay out of it
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct StartEncounter {
+    pub sides: [Vec<Entity>; 2],
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attacked {
+    pub attacker: Entity,
+    pub defender: Entity,
+    pub style: CombatStyle,
+    pub strike: Strike,
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Defeated {
+    pub fighter: Entity,
+    pub encounter: Entity,
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncounterEnded {
+    pub encounter: Entity,
+    /// Side left standing, `None` if nobody is
+    pub winner: Option<usize>,
+}
+
+/// Systems of encounters, they run before autonomous cats pick tasks
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct CombatSet;
+
+fn add_cat_health(ev: Trigger<OnAdd, CatSheet>, sheets: Query<&CatSheet>, mut cmds: Commands) {
+    let Ok(sheet) = sheets.get(ev.target()) else {
+        return;
+    };
+    cmds.entity(ev.target())
+        .insert_if_new(Health::full(max_health(&sheet.secondary)));
+}
+
+fn add_creature_health(
+    ev: Trigger<OnAdd, Creature>,
+    creatures: Query<&Creature>,
+    mut cmds: Commands,
+) {
+    let Ok(creature) = creatures.get(ev.target()) else {
+        return;
+    };
+    cmds.entity(ev.target())
+        .insert_if_new(Health::full(max_health(&creature.0.attributes())));
+}
+
+fn start_encounter(
+    ev: Trigger<StartEncounter>,
+    fighters: Query<&Health, Without<Fighting>>,
+    clock: Res<SimulationClock>,
+    mut cmds: Commands,
+) {
+    let sides = ev.sides.clone().map(|side| -> Vec<Entity> {
+        let mut side: Vec<Entity> = side
+            .into_iter()
+            .filter(|fighter| fighters.get(*fighter).is_ok_and(|hp| hp.current > 0))
+            .collect();
+        side.sort();
+        side.dedup();
+        side
+    });
+    if sides.iter().any(Vec::is_empty) || sides[0].iter().any(|f| sides[1].contains(f)) {
+        warn!("Encounter {:?} can't start", ev.sides);
+        return;
+    }
+    let encounter = cmds
+        .spawn((
+            Name::new("Encounter"),
+            GameObject,
+            Encounter {
+                sides: sides.clone(),
+                started: clock.tick,
+            },
+        ))
+        .id();
+    for (side, fighters) in sides.iter().enumerate() {
+        for fighter in fighters {
+            cmds.entity(*fighter).insert(Fighting {
+                encounter,
+                side,
+                effects: vec![],
+            });
+        }
+    }
+}
+
+type Hunter<'a> = (Entity, &'a Transform, &'a Health, Option<&'a LayerObject>);
+type Prey<'a> = (Entity, &'a Transform, &'a Health);
+
+/// Creatures attack the cats near them, together with the other creatures near those cats.
+/// Cats live on the ground layer
+fn engage_cats(
+    creatures: Query<Hunter, (With<Creature>, Without<Fighting>)>,
+    cats: Query<Prey, (With<CatSheet>, Without<Fighting>)>,
+    mut cmds: Commands,
+) {
+    let near = |a: &Transform, b: &Transform| {
+        a.translation.xz().distance(b.translation.xz()) <= ENGAGE_DISTANCE
+    };
+    let mut creatures: Vec<(Entity, &Transform)> = creatures
+        .iter()
+        .filter(|(_, _, hp, layer)| hp.current > 0 && layer.is_none_or(|l| l.0 == CAT_LAYER))
+        .map(|(creature, tr, ..)| (creature, tr))
+        .collect();
+    creatures.sort_by_key(|(creature, _)| *creature);
+    let mut cats: Vec<(Entity, &Transform)> = cats
+        .iter()
+        .filter(|(_, _, hp)| hp.current > 0)
+        .map(|(cat, tr, _)| (cat, tr))
+        .collect();
+    cats.sort_by_key(|(cat, _)| *cat);
+
+    while let Some((creature, tr)) = creatures.first().copied() {
+        let (prey, rest): (Vec<_>, Vec<_>) = cats.iter().partition(|(_, cat_tr)| near(tr, cat_tr));
+        cats = rest;
+        let (pack, rest): (Vec<_>, Vec<_>) = creatures.iter().partition(|(other, other_tr)| {
+            *other == creature || prey.iter().any(|(_, cat_tr)| near(other_tr, cat_tr))
+        });
+        creatures = rest;
+        if prey.is_empty() {
+            continue;
+        }
+        cmds.trigger(StartEncounter {
+            sides: [
+                prey.into_iter().map(|(cat, _)| cat).collect(),
+                pack.into_iter().map(|(creature, _)| creature).collect(),
+            ],
+        });
+    }
+}
+
+type Fighter<'a> = (
+    &'a mut Health,
+    &'a mut Fighting,
+    Option<&'a CatSheet>,
+    Option<&'a Creature>,
+);
+
+/// Every turn the fighters act in the order of their initiative (speed roll):
+/// bleeding hurts, stunned fighters skip the turn, the rest attack the weakest opponent.
+/// Defeated cats leave the fight, defeated creatures vanish
+#[allow(clippy::too_many_arguments)]
+fn resolve_encounters(
+    mut encounters: Query<(Entity, &mut Encounter)>,
+    mut fighters: Query<Fighter>,
+    clock: Res<SimulationClock>,
+    mut rng: ResMut<GameRng>,
+    mut attacks: EventWriter<Attacked>,
+    mut defeats: EventWriter<Defeated>,
+    mut ends: EventWriter<EncounterEnded>,
+    mut cmds: Commands,
+) {
+    let mut order: Vec<_> = encounters.iter_mut().collect();
+    order.sort_by_key(|(encounter, _)| *encounter);
+    for (encounter, mut state) in order {
+        let elapsed = clock.tick.saturating_sub(state.started);
+        if elapsed == 0 || !elapsed.is_multiple_of(TURN_TICKS) {
+            continue;
+        }
+        let standing = |fighters: &Query<Fighter>, fighter: Entity| {
+            fighters
+                .get(fighter)
+                .is_ok_and(|(hp, fighting, ..)| hp.current > 0 && fighting.encounter == encounter)
+        };
+        let mut initiative: Vec<(i32, Entity)> = state
+            .sides
+            .iter()
+            .flatten()
+            .filter(|fighter| standing(&fighters, **fighter))
+            .map(|fighter| {
+                let (_, _, sheet, creature) = fighters.get(*fighter).unwrap();
+                let speed = combat_attributes(sheet, creature).speed;
+                (-Roll::attribute(speed, 0).roll(&mut rng.0), *fighter)
+            })
+            .collect();
+        initiative.sort();
+
+        // Fighters could be wounded between the turns, e.g. by spells
+        let mut defeated: Vec<Entity> = state
+            .sides
+            .iter()
+            .flatten()
+            .filter(|fighter| {
+                fighters.get(**fighter).is_ok_and(|(hp, fighting, ..)| {
+                    hp.current <= 0 && fighting.encounter == encounter
+                })
+            })
+            .copied()
+            .collect();
+        for (_, attacker) in initiative {
+            if !standing(&fighters, attacker) {
+                continue;
+            }
+            let (attrs, side, stunned, frightened, health) = {
+                let (mut hp, mut fighting, sheet, creature) = fighters.get_mut(attacker).unwrap();
+                if fighting.has(StatusEffect::Bleeding) {
+                    hp.current -= BLEEDING_DAMAGE;
+                }
+                let state = (
+                    combat_attributes(sheet, creature),
+                    fighting.side,
+                    fighting.has(StatusEffect::Stunned),
+                    fighting.has(StatusEffect::Frightened),
+                    hp.current,
+                );
+                fighting.effects.retain_mut(|(_, turns)| {
+                    *turns -= 1;
+                    *turns > 0
+                });
+                state
+            };
+            if health <= 0 {
+                defeated.push(attacker);
+                continue;
+            }
+            if stunned {
+                continue;
+            }
+            let target = state.sides[1 - side]
+                .iter()
+                .filter(|fighter| standing(&fighters, **fighter))
+                .min_by_key(|fighter| (fighters.get(**fighter).unwrap().0.current, **fighter))
+                .copied();
+            let Some(defender) = target else {
+                break;
+            };
+            let style = match frightened {
+                true => CombatStyle::Ranged,
+                false => CombatStyle::best(&attrs),
+            };
+            let (mut hp, mut fighting, sheet, creature) = fighters.get_mut(defender).unwrap();
+            let strike = strike(
+                &attrs,
+                &combat_attributes(sheet, creature),
+                style,
+                &mut rng.0,
+            );
+            hp.current -= strike.damage;
+            if let Some(effect) = strike.effect {
+                fighting.effects.retain(|(e, _)| *e != effect);
+                fighting.effects.push((effect, EFFECT_TURNS));
+            }
+            if hp.current <= 0 {
+                defeated.push(defender);
+            }
+            attacks.write(Attacked {
+                attacker,
+                defender,
+                style,
+                strike,
+            });
+        }
+
+        for fighter in defeated {
+            let (mut hp, _, _, creature) = fighters.get_mut(fighter).unwrap();
+            hp.current = 0;
+            if creature.is_some() {
+                cmds.entity(fighter).despawn();
+            } else {
+                cmds.entity(fighter).remove::<Fighting>();
+            }
+            defeats.write(Defeated { fighter, encounter });
+        }
+        for side in state.sides.iter_mut() {
+            side.retain(|fighter| standing(&fighters, *fighter));
+        }
+        let alive: Vec<usize> = (0..2).filter(|i| !state.sides[*i].is_empty()).collect();
+        if alive.len() < 2 {
+            for fighter in state.sides.iter().flatten() {
+                cmds.entity(*fighter).remove::<Fighting>();
+            }
+            cmds.entity(encounter).despawn();
+            ends.write(EncounterEnded {
+                encounter,
+                winner: alive.first().copied(),
+            });
+        }
+    }
+}
+
+type Healing<'a> = (&'a mut Health, Option<&'a CatSheet>, Option<&'a Creature>);
+
+/// Fighters out of combat slowly get their health back.
+/// Maximum health follows the constitution, which grows with experience
+fn heal(mut fighters: Query<Healing, Without<Fighting>>, clock: Res<SimulationClock>) {
+    if !clock.tick.is_multiple_of(HEAL_TICKS) {
+        return;
+    }
+    for (mut hp, sheet, creature) in &mut fighters {
+        hp.max = max_health(&combat_attributes(sheet, creature));
+        if hp.current < hp.max {
+            hp.current += 1;
+        }
+    }
+}
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Health>();
+        app.register_type::<Fighting>();
+        app.register_type::<Encounter>();
+        app.register_type::<Creature>();
+        app.add_event::<Attacked>();
+        app.add_event::<Defeated>();
+        app.add_event::<EncounterEnded>();
+        app.add_observer(add_cat_health);
+        app.add_observer(add_creature_health);
+        app.add_observer(start_encounter);
+        app.configure_sets(
+            FixedUpdate,
+            CombatSet
+                .in_set(SimulationSet)
+                .after(CalendarSet)
+                .before(CatAiSet),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (engage_cats, resolve_encounters, heal)
+                .chain()
+                .in_set(CombatSet),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_combat_plugin.rs"]
+mod test_combat_plugin;
@@ -0,0 +1,472 @@
+use std::cmp::Reverse;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    alchemy_plugin::{PlaceWorkstation, StationKind},
+    cat::{CatSheet, MagicSchool, PrimaryAttribute},
+    cat_ai_plugin::{CAT_LAYER, CatAiSet},
+    game_map_plugin::{GameMapCellFloor, GameMapData, LayerObject, SetCellFloorEvent},
+    game_state_plugin::{GameObject, GameResourceAppExt, GameState},
+    hauling_plugin::PlaceStockpile,
+    item::ItemKind,
+    pathfinding::cell_center,
+    player_control_plugin::PlayerCommand,
+    player_input_stage::PlayerInputPostUpdate,
+    simulation_clock_plugin::{SimulationClock, SimulationSet},
+};
+
+/// The player designates map cells for work, cats take the jobs
+pub struct DesignationPlugin;
+
+/// Work needed to finish a job
+pub const JOB_WORK: f32 = 100.0;
+/// Work done per tick by a cat with average job attribute
+pub const WORK_PER_TICK: f32 = 1.0;
+/// Cats need at least this value of the job attribute to take the job
+pub const MIN_JOB_ATTRIBUTE: i32 = 8;
+/// Attribute value which keeps the base work speed
+const AVERAGE_ATTRIBUTE: f32 = 13.0;
+/// Ticks before a job nobody could reach is assigned again
+pub const JOB_RETRY_TICKS: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum DesignationKind {
+    Dig,
+    Build,
+    Haul,
+    ClearGrass,
+    /// Crafting at a workstation, ordered with the workstation rather than designated
+    Craft,
+}
+
+impl DesignationKind {
+    /// Attribute which decides who does the job and how fast
+    pub fn attribute(self) -> PrimaryAttribute {
+        match self {
+            Self::Dig | Self::Build | Self::Haul => PrimaryAttribute::Strength,
+            Self::ClearGrass => PrimaryAttribute::Agility,
+            Self::Craft => MagicSchool::Alchemy.base_attribute(),
+        }
+    }
+
+    /// Whether the job can be done on a cell with the floor
+    pub fn applies_to(self, floor: GameMapCellFloor) -> bool {
+        match self {
+            Self::Dig | Self::Haul | Self::Craft => floor != GameMapCellFloor::None,
+            Self::Build => floor == GameMapCellFloor::None,
+            Self::ClearGrass => floor == GameMapCellFloor::Grass,
+        }
+    }
+
+    /// Floor of the cell once the job is done
+    pub fn result(self) -> Option<GameMapCellFloor> {
+        match self {
+            Self::Dig => Some(GameMapCellFloor::None),
+            Self::Build => Some(GameMapCellFloor::Stone),
+            Self::ClearGrass => Some(GameMapCellFloor::Ground),
+            Self::Haul | Self::Craft => None,
+        }
+    }
+
+    /// Items one of which is found when the job is done
+    pub fn yields(self) -> &'static [ItemKind] {
+        match self {
+            Self::Dig => &[ItemKind::Crystal],
+            Self::ClearGrass => &[
+                ItemKind::Herb,
+                ItemKind::Mushroom,
+                ItemKind::Catnip,
+                ItemKind::Feather,
+            ],
+            Self::Build | Self::Haul | Self::Craft => &[],
+        }
+    }
+}
+
+/// What happens to the cells the player selects with the mouse
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub enum DesignationTool {
+    #[default]
+    Off,
+    Designate(DesignationKind),
+    /// Selected cells become stockpiles
+    Stockpile,
+    /// First selected cell gets the workstation
+    Workstation(StationKind),
+    Cancel,
+}
+
+impl DesignationTool {
+    pub fn next(self) -> Self {
+        use DesignationKind::*;
+        match self {
+            Self::Off => Self::Designate(Dig),
+            Self::Designate(Dig) => Self::Designate(Build),
+            Self::Designate(Build) => Self::Designate(ClearGrass),
+            // Hauls and crafting are requested by the stockpiles and workstations
+            Self::Designate(ClearGrass | Haul | Craft) => Self::Stockpile,
+            Self::Stockpile => Self::Workstation(StationKind::Cauldron),
+            Self::Workstation(StationKind::Cauldron) => {
+                Self::Workstation(StationKind::AlchemyTable)
+            }
+            Self::Workstation(StationKind::AlchemyTable) => Self::Cancel,
+            Self::Cancel => Self::Off,
+        }
+    }
+}
+
+pub type JobId = u32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum JobState {
+    Pending,
+    Assigned(Entity),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct Job {
+    pub id: JobId,
+    pub kind: DesignationKind,
+    pub layer: usize,
+    /// (column, row)
+    pub cell: UVec2,
+    pub state: JobState,
+    pub progress: f32,
+    /// Tick before which the job is not assigned
+    pub retry_at: u64,
+}
+
+/// Designated jobs in the order they were designated. At most one job per cell
+#[derive(Resource, Debug, Default, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct JobQueue {
+    pub jobs: Vec<Job>,
+    next_id: JobId,
+}
+
+impl JobQueue {
+    /// Queue of the jobs, new designations get ids after theirs
+    pub fn from_jobs(jobs: Vec<Job>) -> Self {
+        let next_id = jobs.iter().map(|job| job.id + 1).max().unwrap_or(0);
+        Self { jobs, next_id }
+    }
+
+    /// Designate the cell, replacing its previous designation
+    pub fn designate(&mut self, kind: DesignationKind, layer: usize, cell: UVec2) -> JobId {
+        self.cancel(layer, cell);
+        let id = self.next_id;
+        self.next_id += 1;
+        self.jobs.push(Job {
+            id,
+            kind,
+            layer,
+            cell,
+            state: JobState::Pending,
+            progress: 0.0,
+            retry_at: 0,
+        });
+        id
+    }
+
+    pub fn cancel(&mut self, layer: usize, cell: UVec2) -> Option<Job> {
+        let idx = self
+            .jobs
+            .iter()
+            .position(|job| job.layer == layer && job.cell == cell)?;
+        Some(self.jobs.remove(idx))
+    }
+
+    pub fn at(&self, layer: usize, cell: UVec2) -> Option<&Job> {
+        self.jobs
+            .iter()
+            .find(|job| job.layer == layer && job.cell == cell)
+    }
+
+    pub fn get(&self, id: JobId) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+
+    /// Take the job away from its cat until the tick
+    pub fn postpone(&mut self, id: JobId, until: u64) {
+        if let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) {
+            job.state = JobState::Pending;
+            job.retry_at = until;
+        }
+    }
+
+    pub fn assigned_to(&self, cat: Entity) -> Option<&Job> {
+        self.jobs
+            .iter()
+            .find(|job| job.state == JobState::Assigned(cat))
+    }
+}
+
+/// Cat is working on the job. Removed once the job is done or cancelled
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Working(pub JobId);
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobDone {
+    pub cat: Entity,
+    pub kind: DesignationKind,
+    pub layer: usize,
+    pub cell: UVec2,
+}
+
+/// How good the cat is at the job
+pub fn job_aptitude(sheet: &CatSheet, kind: DesignationKind) -> i32 {
+    let attr = kind.attribute();
+    let base = match kind {
+        DesignationKind::Craft => sheet.school_aptitude(MagicSchool::Alchemy),
+        _ => sheet.primary.get(attr),
+    };
+    base + sheet.traits.modifier(attr)
+}
+
+/// Marker showing the job state of the cell
+#[derive(Component)]
+struct JobMarker(JobId);
+
+struct JobMarkerAssets {
+    mesh: Handle<Mesh>,
+    pending: Handle<StandardMaterial>,
+    assigned: Handle<StandardMaterial>,
+    in_progress: Handle<StandardMaterial>,
+}
+
+fn player_cmd_designation(
+    mut evs: EventReader<PlayerCommand>,
+    mut tool: ResMut<DesignationTool>,
+    mut queue: ResMut<JobQueue>,
+    map_data: Option<Res<GameMapData>>,
+    mut cmds: Commands,
+) {
+    for ev in evs.read() {
+        let (from, to) = match ev {
+            PlayerCommand::CycleDesignationTool => {
+                *tool = tool.next();
+                info!("Designation tool: {:?}", *tool);
+                continue;
+            }
+            PlayerCommand::DesignateArea { from, to } => (*from, *to),
+            _ => continue,
+        };
+        let layer = map_data.as_ref().map_or(0, |data| data.current_layer());
+        let map = map_data.as_deref().map(|data| data.map());
+        let (min, max) = (from.min(to), from.max(to));
+        for row in min.y..=max.y {
+            for col in min.x..=max.x {
+                let cell = UVec2::new(col, row);
+                let floor = map.and_then(|map| {
+                    let row = map.cells.get(layer)?.get(row as usize)?;
+                    Some(row.get(col as usize)?.floor())
+                });
+                // Without a map every designation is accepted
+                let applies = |kind: DesignationKind| match (map, floor) {
+                    (None, _) => true,
+                    (Some(_), Some(floor)) => kind.applies_to(floor),
+                    (Some(_), None) => false,
+                };
+                match *tool {
+                    DesignationTool::Off => {}
+                    DesignationTool::Designate(kind) => {
+                        // Same designation again keeps the progress
+                        let designated = queue.at(layer, cell).is_some_and(|job| job.kind == kind);
+                        if applies(kind) && !designated {
+                            queue.designate(kind, layer, cell);
+                        }
+                    }
+                    // Items are stored where they could be hauled from
+                    DesignationTool::Stockpile => {
+                        if applies(DesignationKind::Haul) {
+                            cmds.trigger(PlaceStockpile { layer, cell });
+                        }
+                    }
+                    // Crafting jobs are done on the cell of the workstation
+                    DesignationTool::Workstation(kind) => {
+                        if cell == from && applies(DesignationKind::Craft) {
+                            cmds.trigger(PlaceWorkstation { kind, layer, cell });
+                        }
+                    }
+                    DesignationTool::Cancel => {
+                        queue.cancel(layer, cell);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Pending jobs go to the most capable idle cats, in the order they were designated.
+/// Cats only reach jobs on their own layer
+fn assign_jobs(
+    mut queue: ResMut<JobQueue>,
+    cats: Query<(Entity, &CatSheet)>,
+    clock: Res<SimulationClock>,
+) {
+    let mut assignments = vec![];
+    let mut busy = vec![];
+    for (idx, job) in queue.jobs.iter().enumerate() {
+        match job.state {
+            // Departed cats leave their jobs
+            JobState::Assigned(cat) if !cats.contains(cat) => {
+                assignments.push((idx, JobState::Pending));
+            }
+            JobState::Assigned(cat) => busy.push(cat),
+            JobState::Pending => {}
+        }
+    }
+    for (idx, job) in queue.jobs.iter().enumerate() {
+        if job.state != JobState::Pending || job.layer != CAT_LAYER || job.retry_at > clock.tick {
+            continue;
+        }
+        let best = cats
+            .iter()
+            .filter(|(cat, _)| !busy.contains(cat))
+            .map(|(cat, sheet)| (cat, job_aptitude(sheet, job.kind)))
+            .filter(|(_, aptitude)| *aptitude >= MIN_JOB_ATTRIBUTE)
+            .min_by_key(|(cat, aptitude)| (Reverse(*aptitude), *cat));
+        if let Some((cat, _)) = best {
+            busy.push(cat);
+            assignments.push((idx, JobState::Assigned(cat)));
+        }
+    }
+    // Keep change detection quiet when nothing changes
+    for (idx, state) in assignments {
+        queue.jobs[idx].state = state;
+    }
+}
+
+/// Working cats make progress, capable ones faster. Finished jobs change the map
+fn work_jobs(
+    mut queue: ResMut<JobQueue>,
+    workers: Query<(Entity, &Working, &CatSheet)>,
+    mut done: EventWriter<JobDone>,
+    mut cmds: Commands,
+) {
+    let mut workers: Vec<_> = workers.iter().collect();
+    workers.sort_by_key(|(cat, ..)| *cat);
+    for (cat, working, sheet) in workers {
+        let Some(idx) = queue
+            .jobs
+            .iter()
+            .position(|job| job.id == working.0 && job.state == JobState::Assigned(cat))
+        else {
+            // Cancelled or reassigned
+            cmds.entity(cat).remove::<Working>();
+            continue;
+        };
+        let job = &mut queue.jobs[idx];
+        let rate = job_aptitude(sheet, job.kind).max(1) as f32 / AVERAGE_ATTRIBUTE;
+        job.progress += WORK_PER_TICK * rate;
+        if job.progress < JOB_WORK {
+            continue;
+        }
+        let job = queue.jobs.remove(idx);
+        cmds.entity(cat).remove::<Working>();
+        if let Some(floor) = job.kind.result() {
+            cmds.trigger(SetCellFloorEvent {
+                layer: job.layer,
+                cell: job.cell,
+                floor,
+            });
+        }
+        done.write(JobDone {
+            cat,
+            kind: job.kind,
+            layer: job.layer,
+            cell: job.cell,
+        });
+    }
+}
+
+fn show_job_markers(
+    queue: Res<JobQueue>,
+    mut markers: Query<(Entity, &JobMarker, &mut MeshMaterial3d<StandardMaterial>)>,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<StandardMaterial>>>,
+    mut marker_assets: Local<Option<JobMarkerAssets>>,
+    mut cmds: Commands,
+) {
+    // Headless tests have no render assets
+    let (Some(mut meshes), Some(mut materials)) = (meshes, materials) else {
+        return;
+    };
+    if !queue.is_changed() {
+        return;
+    }
+    let assets = marker_assets.get_or_insert_with(|| {
+        let mut material = |color: Color| {
+            materials.add(StandardMaterial {
+                base_color: color.with_alpha(0.5),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            })
+        };
+        JobMarkerAssets {
+            mesh: meshes.add(Cuboid::new(0.9, 0.05, 0.9)),
+            pending: material(Color::srgb(0.9, 0.9, 0.2)),
+            assigned: material(Color::srgb(0.9, 0.5, 0.1)),
+            in_progress: material(Color::srgb(0.2, 0.8, 0.2)),
+        }
+    });
+    let material = |job: &Job| match job.state {
+        JobState::Pending => assets.pending.clone(),
+        JobState::Assigned(_) if job.progress > 0.0 => assets.in_progress.clone(),
+        JobState::Assigned(_) => assets.assigned.clone(),
+    };
+
+    let mut shown = vec![];
+    for (entity, marker, mut marker_material) in &mut markers {
+        match queue.get(marker.0) {
+            Some(job) => {
+                let job_material = material(job);
+                if marker_material.0 != job_material {
+                    marker_material.0 = job_material;
+                }
+                shown.push(marker.0);
+            }
+            None => cmds.entity(entity).despawn(),
+        }
+    }
+    for job in queue.jobs.iter().filter(|job| !shown.contains(&job.id)) {
+        cmds.spawn((
+            GameObject,
+            Name::new(format!("{:?} job", job.kind)),
+            JobMarker(job.id),
+            LayerObject(job.layer),
+            Mesh3d(assets.mesh.clone()),
+            MeshMaterial3d(material(job)),
+            Transform::from_translation(cell_center(job.cell, job.layer as f32 + 0.55)),
+        ));
+    }
+}
+
+impl Plugin for DesignationPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<DesignationTool>();
+        app.register_type::<JobQueue>();
+        app.register_type::<Working>();
+        app.init_resource::<DesignationTool>();
+        app.init_game_resource::<JobQueue>();
+        app.add_event::<JobDone>();
+        app.add_systems(
+            PlayerInputPostUpdate,
+            player_cmd_designation.run_if(in_state(GameState::Game)),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (assign_jobs.before(CatAiSet), work_jobs.after(CatAiSet)).in_set(SimulationSet),
+        );
+        app.add_systems(Update, show_job_markers.run_if(in_state(GameState::Game)));
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_designation_plugin.rs"]
+mod test_designation_plugin;
@@ -0,0 +1,231 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    calendar_plugin::{CalendarSet, SeasonChanged},
+    cat::CatSheet,
+    game_state_plugin::GameResourceAppExt,
+    hauling_plugin::Stockpile,
+    item::{Inventory, ItemKind},
+    simulation_clock_plugin::SimulationSet,
+    student_plugin::{Enrollment, Teacher},
+};
+
+/// Academy-wide resources. Tuition and salaries are settled every season
+pub struct EconomyPlugin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum Commodity {
+    Food,
+    Herbs,
+    Gold,
+    Books,
+    ManaCrystals,
+}
+
+impl Commodity {
+    pub const ALL: [Commodity; 5] = [
+        Self::Food,
+        Self::Herbs,
+        Self::Gold,
+        Self::Books,
+        Self::ManaCrystals,
+    ];
+
+    /// Item kept in stockpiles, `None` for commodities kept in the treasury
+    pub fn item(self) -> Option<ItemKind> {
+        match self {
+            Self::Food => Some(ItemKind::Mushroom),
+            Self::Herbs => Some(ItemKind::Herb),
+            Self::ManaCrystals => Some(ItemKind::Crystal),
+            Self::Gold | Self::Books => None,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct EconomyConfig {
+    /// Gold paid by every enrolled kitten each season
+    pub tuition: i64,
+    /// Gold paid to every teacher each season
+    pub salary: i64,
+    /// Food eaten by every cat each season
+    pub food_per_cat: u32,
+    /// Seasons kept in the ledger history
+    pub history_seasons: usize,
+}
+
+impl Default for EconomyConfig {
+    fn default() -> Self {
+        Self {
+            tuition: 30,
+            salary: 50,
+            food_per_cat: 5,
+            history_seasons: 40,
+        }
+    }
+}
+
+/// Commodities which are not stored in stockpiles. Gold may go into debt
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Treasury {
+    pub gold: i64,
+    pub books: i64,
+}
+
+impl Default for Treasury {
+    fn default() -> Self {
+        Self {
+            gold: 200,
+            books: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum LedgerReason {
+    Tuition,
+    Salaries,
+    Food,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    /// Season index of the settled season
+    pub season: u32,
+    pub reason: LedgerReason,
+    pub commodity: Commodity,
+    /// Income is positive, expenses are negative
+    pub amount: i64,
+}
+
+/// Holdings at the end of the season, by `Commodity::ALL`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct LedgerSnapshot {
+    pub season: u32,
+    pub holdings: [i64; 5],
+}
+
+/// Settled entries and holdings of the last seasons, oldest first
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Ledger {
+    pub entries: Vec<LedgerEntry>,
+    pub history: Vec<LedgerSnapshot>,
+}
+
+impl Ledger {
+    /// Net change of the commodity during the season
+    pub fn net(&self, season: u32, commodity: Commodity) -> i64 {
+        self.entries
+            .iter()
+            .filter(|entry| entry.season == season && entry.commodity == commodity)
+            .map(|entry| entry.amount)
+            .sum()
+    }
+
+    /// (season, holding) points of the commodity for charting
+    pub fn series(&self, commodity: Commodity) -> Vec<(u32, i64)> {
+        let idx = commodity as usize;
+        self.history
+            .iter()
+            .map(|snapshot| (snapshot.season, snapshot.holdings[idx]))
+            .collect()
+    }
+}
+
+/// Current amount of every commodity, by `Commodity::ALL`
+pub fn holdings<'a>(
+    treasury: &Treasury,
+    stockpiles: impl Iterator<Item = &'a Inventory> + Clone,
+) -> [i64; 5] {
+    Commodity::ALL.map(|commodity| match (commodity, commodity.item()) {
+        (_, Some(kind)) => stockpiles
+            .clone()
+            .map(|inventory| inventory.count(kind) as i64)
+            .sum(),
+        (Commodity::Gold, None) => treasury.gold,
+        (_, None) => treasury.books,
+    })
+}
+
+/// At the start of the season kittens pay tuition for the last one,
+/// teachers get their salaries and everyone eats from the stockpiles
+#[allow(clippy::too_many_arguments)]
+fn settle_season(
+    mut evs: EventReader<SeasonChanged>,
+    config: Res<EconomyConfig>,
+    mut treasury: ResMut<Treasury>,
+    mut ledger: ResMut<Ledger>,
+    mut stockpiles: Query<(Entity, &mut Inventory), With<Stockpile>>,
+    students: Query<(), With<Enrollment>>,
+    teachers: Query<(), With<Teacher>>,
+    cats: Query<(), With<CatSheet>>,
+) {
+    for ev in evs.read() {
+        let season = ev.calendar.season_index().saturating_sub(1);
+        let tuition = students.iter().count() as i64 * config.tuition;
+        let salaries = teachers.iter().count() as i64 * config.salary;
+        treasury.gold += tuition - salaries;
+
+        // Oldest stockpiles are emptied first
+        let mut order: Vec<_> = stockpiles.iter_mut().collect();
+        order.sort_by_key(|(entity, _)| *entity);
+        let food = ItemKind::Mushroom;
+        let needed = cats.iter().count() as u32 * config.food_per_cat;
+        let mut eaten = 0;
+        for (_, inventory) in order.iter_mut() {
+            eaten += inventory
+                .take(food, needed - eaten)
+                .iter()
+                .map(|stack| stack.count)
+                .sum::<u32>();
+        }
+        if eaten < needed {
+            warn!("Academy is short of {} food", needed - eaten);
+        }
+
+        for (reason, commodity, amount) in [
+            (LedgerReason::Tuition, Commodity::Gold, tuition),
+            (LedgerReason::Salaries, Commodity::Gold, -salaries),
+            (LedgerReason::Food, Commodity::Food, -(eaten as i64)),
+        ] {
+            ledger.entries.push(LedgerEntry {
+                season,
+                reason,
+                commodity,
+                amount,
+            });
+        }
+        let holdings = holdings(&treasury, order.iter().map(|(_, inventory)| &**inventory));
+        ledger.history.push(LedgerSnapshot { season, holdings });
+
+        // Forget seasons which fell out of the history
+        let excess = ledger.history.len().saturating_sub(config.history_seasons);
+        ledger.history.drain(..excess);
+        if let Some(oldest) = ledger.history.first().map(|snapshot| snapshot.season) {
+            ledger.entries.retain(|entry| entry.season >= oldest);
+        }
+    }
+}
+
+impl Plugin for EconomyPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<EconomyConfig>();
+        app.register_type::<Treasury>();
+        app.register_type::<Ledger>();
+        app.init_resource::<EconomyConfig>();
+        app.init_game_resource::<Treasury>();
+        app.init_game_resource::<Ledger>();
+        app.add_systems(
+            FixedUpdate,
+            settle_season.after(CalendarSet).in_set(SimulationSet),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_economy_plugin.rs"]
+mod test_economy_plugin;
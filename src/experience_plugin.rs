@@ -0,0 +1,211 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    calendar_plugin::{Calendar, CalendarConfig, CalendarSet},
+    cat::{CatSheet, MagicSchool, PrimaryAttribute, SecondaryAttribute},
+    simulation_clock_plugin::SimulationSet,
+    timetable_plugin::{LessonSlot, Timetable},
+};
+
+/// Cats level up schools of magic and secondary attributes
+pub struct ExperiencePlugin;
+
+/// Experience needed for the first level, every next level needs one more portion of it
+pub const LEVEL_XP: u32 = 1000;
+/// Experience gained by every attendee of a class per tick, before learning bonuses
+pub const CLASS_XP_PER_TICK: u32 = 2;
+pub const PRACTICE_XP_PER_TICK: u32 = 1;
+/// Level cap is the governing attribute divided by this
+const LEVEL_CAP_DIVISOR: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum Skill {
+    School(MagicSchool),
+    Attribute(SecondaryAttribute),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct SkillProgress {
+    pub xp: u32,
+    pub level: u32,
+}
+
+/// Total experience needed to reach the level
+pub fn xp_for_level(level: u32) -> u32 {
+    LEVEL_XP * level * (level + 1) / 2
+}
+
+/// Highest level the cat can reach in the skill
+pub fn level_cap(sheet: &CatSheet, skill: Skill) -> u32 {
+    let governing = match skill {
+        Skill::School(school) => {
+            let base = school.base_attribute();
+            sheet.school_aptitude(school) + sheet.traits.modifier(base)
+        }
+        Skill::Attribute(attr) => {
+            let ceiling = attr.ceiling();
+            sheet.primary.get(ceiling) + sheet.traits.modifier(ceiling)
+        }
+    };
+    (governing / LEVEL_CAP_DIVISOR).max(0) as u32
+}
+
+/// Smart cats learn faster: every point of intelligence adds a tenth of the amount,
+/// so an average cat gets about twice as much
+pub fn learning_gain(sheet: &CatSheet, amount: u32) -> u32 {
+    let intelligence = PrimaryAttribute::Intelligence;
+    let intelligence = sheet.primary.get(intelligence) + sheet.traits.modifier(intelligence);
+    amount * (10 + intelligence.max(0) as u32) / 10
+}
+
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Experience {
+    schools: [SkillProgress; MagicSchool::ALL.len()],
+    attributes: [SkillProgress; SecondaryAttribute::ALL.len()],
+}
+
+impl Experience {
+    pub fn get(&self, skill: Skill) -> SkillProgress {
+        match skill {
+            Skill::School(school) => self.schools[school as usize],
+            Skill::Attribute(attr) => self.attributes[attr as usize],
+        }
+    }
+
+    fn get_mut(&mut self, skill: Skill) -> &mut SkillProgress {
+        match skill {
+            Skill::School(school) => &mut self.schools[school as usize],
+            Skill::Attribute(attr) => &mut self.attributes[attr as usize],
+        }
+    }
+
+    /// Add experience, return number of levels gained.
+    /// Growth stops at the level cap of the cat
+    pub fn gain(&mut self, sheet: &CatSheet, skill: Skill, amount: u32) -> u32 {
+        let cap = level_cap(sheet, skill);
+        let progress = self.get_mut(skill);
+        if progress.level >= cap {
+            return 0;
+        }
+        progress.xp += learning_gain(sheet, amount);
+        let mut levels = 0;
+        while progress.level < cap && progress.xp >= xp_for_level(progress.level + 1) {
+            progress.level += 1;
+            levels += 1;
+        }
+        if progress.level >= cap {
+            progress.xp = progress.xp.min(xp_for_level(cap));
+        }
+        levels
+    }
+}
+
+/// Skill the cat trains on its own
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Practising(pub Skill);
+
+/// Experience for the cat, before learning bonuses
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GainExperience {
+    pub cat: Entity,
+    pub skill: Skill,
+    pub amount: u32,
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelUp {
+    pub cat: Entity,
+    pub skill: Skill,
+    pub level: u32,
+}
+
+/// Systems which award experience should run before it
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct ExperienceSet;
+
+fn add_experience(ev: Trigger<OnAdd, CatSheet>, mut cmds: Commands) {
+    cmds.entity(ev.target())
+        .insert_if_new(Experience::default());
+}
+
+/// Teacher and students of the class going on learn its school
+fn attend_classes(
+    calendar: Res<Calendar>,
+    config: Res<CalendarConfig>,
+    timetable: Res<Timetable>,
+    mut gains: EventWriter<GainExperience>,
+) {
+    let slot = LessonSlot::at(&calendar, &config);
+    for class in timetable.classes_at(slot) {
+        for cat in class.students.iter().chain([&class.teacher]) {
+            gains.write(GainExperience {
+                cat: *cat,
+                skill: Skill::School(class.school),
+                amount: CLASS_XP_PER_TICK,
+            });
+        }
+    }
+}
+
+fn practise(practising: Query<(Entity, &Practising)>, mut gains: EventWriter<GainExperience>) {
+    for (cat, practising) in practising {
+        gains.write(GainExperience {
+            cat,
+            skill: practising.0,
+            amount: PRACTICE_XP_PER_TICK,
+        });
+    }
+}
+
+fn apply_experience(
+    mut gains: EventReader<GainExperience>,
+    mut cats: Query<(&mut Experience, &mut CatSheet)>,
+    mut level_ups: EventWriter<LevelUp>,
+) {
+    for gain in gains.read() {
+        let Ok((mut experience, mut sheet)) = cats.get_mut(gain.cat) else {
+            continue;
+        };
+        let levels = experience.gain(&sheet, gain.skill, gain.amount);
+        if levels == 0 {
+            continue;
+        }
+        // Secondary attributes grow with levels
+        if let Skill::Attribute(attr) = gain.skill {
+            *sheet.secondary.get_mut(attr) += levels as i32;
+        }
+        level_ups.write(LevelUp {
+            cat: gain.cat,
+            skill: gain.skill,
+            level: experience.get(gain.skill).level,
+        });
+    }
+}
+
+impl Plugin for ExperiencePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Experience>();
+        app.register_type::<Practising>();
+        app.add_event::<GainExperience>();
+        app.add_event::<LevelUp>();
+        app.add_observer(add_experience);
+        app.configure_sets(
+            FixedUpdate,
+            ExperienceSet.in_set(SimulationSet).after(CalendarSet),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (
+                (attend_classes, practise).in_set(ExperienceSet),
+                apply_experience.after(ExperienceSet).in_set(SimulationSet),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_experience_plugin.rs"]
+mod test_experience_plugin;
@@ -1,6 +1,13 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::game_state_plugin::{GameObject, GameState};
+use crate::{
+    game_state_plugin::{GameObject, GameResourceAppExt, GameState},
+    main_menu_plugin::NewGameSettings,
+    save_plugin::PendingLoad,
+    selection_plugin::Selectable,
+    student_plugin::Teacher,
+};
 
 pub struct GameMapPlugin;
 
@@ -8,9 +15,17 @@ pub struct GameMapPlugin;
 #[derive(Event)]
 pub struct ShiftActiveLayerEvent(pub isize);
 
+/// Observable event to replace the floor of the cell (column, row) of the layer
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetCellFloorEvent {
+    pub layer: usize,
+    pub cell: UVec2,
+    pub floor: GameMapCellFloor,
+}
+
 /// A single cell on a game map.
 /// TODO: use Entity?
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum GameMapCellFloor {
     None,
     Ground,
@@ -37,13 +52,25 @@ impl GameMapCell {
             floor_entity: Entity::PLACEHOLDER,
         }
     }
+    pub fn floor(&self) -> GameMapCellFloor {
+        self.floor
+    }
 }
 
+/// Renderer of the layer with the given index
 #[derive(Component)]
 pub struct GameMapLayerRenderer(usize);
 
+/// Map object on the layer with the given index, spawned at the height of its layer.
+/// It moves along with the layer renderers when the active layer shifts
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayerObject(pub usize);
+
 pub type GameMapLayer = Vec<Vec<GameMapCell>>;
 
+/// Floors of the cells by layer, row and column
+pub type GameMapFloors = Vec<Vec<Vec<GameMapCellFloor>>>;
+
 #[derive(Debug)]
 pub struct GameMap {
     // Vector of layers [0..layers]
@@ -72,14 +99,13 @@ impl GameMap {
     }
 
     pub fn new_ground_layer(rows: usize, cols: usize) -> GameMapLayer {
-        let rows = (0..rows)
+        (0..rows)
             .map(|row_idx| {
                 (0..cols)
                     .map(|col_idx| Self::make_ground_layer_floor(row_idx, col_idx))
                     .collect()
             })
-            .collect();
-        rows
+            .collect()
     }
 
     pub fn new_empty_layer(rows: usize, cols: usize) -> GameMapLayer {
@@ -102,10 +128,46 @@ impl GameMap {
             layers,
         }
     }
+
+    pub fn floors(&self) -> GameMapFloors {
+        self.cells
+            .iter()
+            .map(|layer| {
+                layer
+                    .iter()
+                    .map(|row| row.iter().map(GameMapCell::floor).collect())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Map made of the floors, see `floors`
+    pub fn from_floors(floors: &GameMapFloors) -> Self {
+        let cells: Vec<GameMapLayer> = floors
+            .iter()
+            .map(|layer| {
+                layer
+                    .iter()
+                    .map(|row| row.iter().copied().map(GameMapCell::from_floor).collect())
+                    .collect()
+            })
+            .collect();
+        let height = cells.first().map_or(0, |layer| layer.len());
+        let width = cells
+            .first()
+            .and_then(|layer| layer.first())
+            .map_or(0, |row| row.len());
+        Self {
+            layers: cells.len(),
+            cells,
+            width,
+            height,
+        }
+    }
 }
 
 #[derive(Resource)]
-struct GameMapData {
+pub struct GameMapData {
     grass: Vec<Handle<StandardMaterial>>,
     ground: Vec<Handle<StandardMaterial>>,
     stone: Vec<Handle<StandardMaterial>>,
@@ -115,6 +177,44 @@ struct GameMapData {
 }
 
 impl GameMapData {
+    /// Index of the layer the player is looking at
+    pub fn current_layer(&self) -> usize {
+        self.current_layer
+    }
+
+    pub fn map(&self) -> &GameMap {
+        &self.map
+    }
+
+    /// Spawn the box of a floor cell, nothing for an empty one
+    fn spawn_floor(
+        &self,
+        commands: &mut Commands,
+        row_idx: usize,
+        col_idx: usize,
+        floor: GameMapCellFloor,
+    ) -> Option<Entity> {
+        let not_so_rng = row_idx * 17 + col_idx * 11;
+        let materials = match floor {
+            GameMapCellFloor::None => return None,
+            GameMapCellFloor::Ground => &self.ground,
+            GameMapCellFloor::Grass => &self.grass,
+            GameMapCellFloor::Stone => &self.stone,
+        };
+        let material = materials[not_so_rng % materials.len()].clone();
+        let xf = col_idx as f32;
+        let zf = row_idx as f32;
+        let floor_entity = commands
+            .spawn((
+                Name::new(format!("Floor#{row_idx}#{col_idx}")),
+                Mesh3d(self.r#box.clone()),
+                MeshMaterial3d(material),
+                Transform::from_xyz(xf, 0.0, zf),
+            ))
+            .id();
+        Some(floor_entity)
+    }
+
     fn init_grass_mat() -> Vec<StandardMaterial> {
         const NUM: usize = 16;
         (1..NUM)
@@ -207,34 +307,32 @@ impl FromWorld for GameMapData {
     }
 }
 
+/// Map of the loaded game, or of the size picked for the new game.
+/// 10x10 without the main menu
 fn spawn_map(
     mut commands: Commands,
     mut game_map_res: ResMut<GameMapData>,
     asset_server: Res<AssetServer>,
+    new_game: Option<Res<NewGameSettings>>,
+    pending_load: Option<Res<PendingLoad>>,
 ) {
-    let mut map = GameMap::new(3, 10, 10);
+    let size = new_game
+        .as_ref()
+        .map_or(UVec2::splat(10), |new_game| new_game.map_size);
+    let mut map = match &pending_load {
+        Some(load) => GameMap::from_floors(&load.0.map),
+        None => GameMap::new(3, size.y as usize, size.x as usize),
+    };
     let mut children = vec![];
 
     for row_idx in 0..(map.height) {
         for col_idx in 0..(map.width) {
-            let not_so_rng = row_idx * 17 + col_idx * 11;
-            let materials = match map.cells[0][row_idx][col_idx].floor {
-                GameMapCellFloor::None => continue,
-                GameMapCellFloor::Ground => &game_map_res.ground,
-                GameMapCellFloor::Grass => &game_map_res.grass,
-                GameMapCellFloor::Stone => &game_map_res.stone,
+            let floor = map.cells[0][row_idx][col_idx].floor;
+            let Some(floor_entity) =
+                game_map_res.spawn_floor(&mut commands, row_idx, col_idx, floor)
+            else {
+                continue;
             };
-            let material = materials[not_so_rng % materials.len()].clone();
-            let xf = col_idx as f32;
-            let zf = row_idx as f32;
-            let floor_entity = commands
-                .spawn((
-                    Name::new(format!("Floor#{row_idx}#{col_idx}")),
-                    Mesh3d(game_map_res.r#box.clone()),
-                    MeshMaterial3d(material),
-                    Transform::from_xyz(xf, 0.0, zf),
-                ))
-                .id();
             map.cells[0][row_idx][col_idx].floor_entity = floor_entity;
             children.push(floor_entity);
         }
@@ -252,16 +350,33 @@ fn spawn_map(
         ))
         .add_children(&children);
 
-    commands.spawn((
+    // The founder is one of the loaded cats
+    if pending_load.is_some() {
+        return;
+    }
+    let mut founder = commands.spawn((
+        GameObject,
         Name::new("cat"),
+        Selectable,
         SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/cat3.glb"))),
         Transform::from_xyz(5.0, 0.0, 5.0),
     ));
+    if let Some(new_game) = new_game {
+        founder.insert((
+            Name::new(new_game.founder_name.clone()),
+            new_game.founder.clone(),
+            Teacher {
+                school: new_game.founder.best_school(),
+            },
+        ));
+    }
 }
 
+type ShiftedByLayer = Or<(With<GameMapLayerRenderer>, With<LayerObject>)>;
+
 fn shift_active_layer(
     ev: Trigger<ShiftActiveLayerEvent>,
-    renderers: Query<&mut Transform, With<GameMapLayerRenderer>>,
+    renderers: Query<&mut Transform, ShiftedByLayer>,
     mut map_data: ResMut<GameMapData>,
 ) {
     let Some(next_current_layer) = map_data.current_layer.checked_add_signed(ev.0) else {
@@ -278,11 +393,58 @@ fn shift_active_layer(
     }
 }
 
+/// Layer objects spawned while an upper layer is active start shifted like the renderers
+fn offset_layer_object(
+    ev: Trigger<OnAdd, LayerObject>,
+    mut objects: Query<&mut Transform>,
+    map_data: Option<Res<GameMapData>>,
+) {
+    let current_layer = map_data.map_or(0, |map_data| map_data.current_layer);
+    if let Ok(mut tr) = objects.get_mut(ev.target()) {
+        tr.translation.y -= current_layer as f32;
+    }
+}
+
+fn set_cell_floor(
+    ev: Trigger<SetCellFloorEvent>,
+    renderers: Query<(Entity, &GameMapLayerRenderer)>,
+    mut map_data: ResMut<GameMapData>,
+    mut commands: Commands,
+) {
+    let (row_idx, col_idx) = (ev.cell.y as usize, ev.cell.x as usize);
+    let Some(cell) = map_data
+        .map
+        .cells
+        .get_mut(ev.layer)
+        .and_then(|rows| rows.get_mut(row_idx))
+        .and_then(|row| row.get_mut(col_idx))
+    else {
+        return;
+    };
+    cell.floor = ev.floor;
+    let old_entity = std::mem::replace(&mut cell.floor_entity, Entity::PLACEHOLDER);
+    if old_entity != Entity::PLACEHOLDER {
+        commands.entity(old_entity).despawn();
+    }
+
+    // Layers without a renderer only keep the data
+    let Some((renderer, _)) = renderers.iter().find(|(_, layer)| layer.0 == ev.layer) else {
+        return;
+    };
+    let Some(floor_entity) = map_data.spawn_floor(&mut commands, row_idx, col_idx, ev.floor) else {
+        return;
+    };
+    commands.entity(renderer).add_child(floor_entity);
+    map_data.map.cells[ev.layer][row_idx][col_idx].floor_entity = floor_entity;
+}
+
 impl Plugin for GameMapPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Update, spawn_map.run_if(in_state(GameState::Init)));
         app.add_observer(shift_active_layer);
-        app.init_resource::<GameMapData>();
+        app.add_observer(offset_layer_object);
+        app.add_observer(set_cell_floor);
+        app.init_game_resource::<GameMapData>();
     }
 }
 
@@ -1,4 +1,7 @@
-use bevy::prelude::*;
+use bevy::{
+    ecs::{entity::EntityHashSet, observer::Observer, system::SystemIdMarker},
+    prelude::*,
+};
 
 pub struct GameStatePlugin;
 
@@ -8,8 +11,15 @@ pub struct GameStatePlugin;
 #[derive(Component)]
 pub struct GameObject;
 
+/// Entity which outlives games, like cameras of menus.
+/// Anything else spawned in `Game` is expected to be a `GameObject` or a child of one
+#[derive(Component, Debug, Default)]
+pub struct Persistent;
+
 #[derive(Debug, States, Hash, PartialEq, Eq, Clone, Copy, Default)]
 pub enum GameState {
+    /// Choose to start, load or quit. No game objects exist
+    MainMenu,
     /// Initialize a game
     #[default]
     Init,
@@ -19,11 +29,79 @@ pub enum GameState {
     Uninit,
 }
 
+/// State entered once game objects are cleaned up in `Uninit`.
+/// Reset to `Init` afterwards, so leaving the game restarts it unless told otherwise
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AfterUninit(pub GameState);
+
+impl Default for AfterUninit {
+    fn default() -> Self {
+        Self(GameState::Init)
+    }
+}
+
+/// Resources of a single game, inserted on `Init` and removed after `Uninit`
+pub trait GameResourceAppExt {
+    /// Like `init_resource`, but every game starts with a fresh resource.
+    /// Nothing outside of `Init`, `Game` and `Uninit` may rely on it
+    fn init_game_resource<R: Resource + FromWorld>(&mut self) -> &mut Self;
+}
+
+impl GameResourceAppExt for App {
+    fn init_game_resource<R: Resource + FromWorld>(&mut self) -> &mut Self {
+        // Values inserted beforehand, by tests for example, are kept
+        self.add_systems(OnEnter(GameState::Init), |world: &mut World| {
+            world.init_resource::<R>();
+        });
+        // Game objects are already despawned by then
+        self.add_systems(OnExit(GameState::Uninit), |mut cmds: Commands| {
+            cmds.remove_resource::<R>();
+        })
+    }
+}
+
+/// Entities alive before the game started
+#[derive(Resource, Debug, Default)]
+struct EntitiesBeforeGame(EntityHashSet);
+
+fn remember_entities_before_game(entities: Query<Entity>, mut before: ResMut<EntitiesBeforeGame>) {
+    before.0 = entities.iter().collect();
+}
+
+/// Entities which are neither despawned in `Uninit` nor meant to outlive the game
+type Unmanaged = (
+    Without<GameObject>,
+    Without<Persistent>,
+    Without<ChildOf>,
+    Without<Observer>,
+    Without<SystemIdMarker>,
+);
+
+/// Entities spawned since the game started which `Uninit` leaves behind
+fn leaked_entities(
+    entities: Query<Entity, Unmanaged>,
+    before: Res<EntitiesBeforeGame>,
+) -> Vec<Entity> {
+    entities
+        .iter()
+        .filter(|entity| !before.0.contains(entity))
+        .collect()
+}
+
+fn warn_about_leaked_entities(In(leaked): In<Vec<Entity>>, names: Query<NameOrEntity>) {
+    for entity in names.iter_many(leaked) {
+        warn!("{entity} was spawned during the game without GameObject or Persistent");
+    }
+}
+
 fn switch_init_to_play(mut next_state: ResMut<NextState<GameState>>) {
     next_state.set(GameState::Game);
 }
-fn switch_uninit_to_init(mut next_state: ResMut<NextState<GameState>>) {
-    next_state.set(GameState::Init);
+fn switch_uninit_to_next(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut after: ResMut<AfterUninit>,
+) {
+    next_state.set(std::mem::take(&mut *after).0);
 }
 
 fn despawn_game_objects_on_uninit(q: Query<Entity, With<GameObject>>, mut cmds: Commands) {
@@ -35,17 +113,26 @@ fn despawn_game_objects_on_uninit(q: Query<Entity, With<GameObject>>, mut cmds:
 impl Plugin for GameStatePlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<GameState>();
+        app.init_resource::<AfterUninit>();
+        app.init_resource::<EntitiesBeforeGame>();
         app.add_systems(
             Update,
             (
                 switch_init_to_play.run_if(in_state(GameState::Init)),
-                switch_uninit_to_init.run_if(in_state(GameState::Uninit)),
+                switch_uninit_to_next.run_if(in_state(GameState::Uninit)),
             ),
         );
         app.add_systems(
             PostUpdate,
             despawn_game_objects_on_uninit.run_if(in_state(GameState::Uninit)),
         );
+        if cfg!(debug_assertions) {
+            app.add_systems(OnEnter(GameState::Game), remember_entities_before_game);
+            app.add_systems(
+                OnEnter(GameState::Uninit),
+                leaked_entities.pipe(warn_about_leaked_entities),
+            );
+        }
     }
 }
 
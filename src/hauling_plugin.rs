@@ -0,0 +1,449 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    alchemy_plugin::{RecipeBook, Workstation},
+    cat::CatSheet,
+    cat_ai_plugin::CatAiSet,
+    designation_plugin::{DesignationKind, JobDone, JobId, JobQueue, JobState},
+    game_map_plugin::LayerObject,
+    game_state_plugin::{GameObject, GameResourceAppExt},
+    item::{Inventory, ItemKind, ItemQuality, ItemStack, carry_capacity},
+    pathfinding::cell_center,
+    rng_plugin::GameRng,
+    simulation_clock_plugin::SimulationSet,
+};
+
+/// Cats carry items between stockpiles and workstations, and bring what they find at work to the
+/// stockpiles. Picking up and delivering are jobs of `DesignationPlugin`
+pub struct HaulingPlugin;
+
+/// Map cell where items are stored in its `Inventory`
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Stockpile {
+    pub layer: usize,
+    /// (column, row)
+    pub cell: UVec2,
+}
+
+/// Trigger to designate the cell as a stockpile. Every cell holds one stockpile at most
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlaceStockpile {
+    pub layer: usize,
+    pub cell: UVec2,
+}
+
+/// Trigger to carry items from a stockpile or workstation to another one on the same layer
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestHaul {
+    pub from: Entity,
+    pub to: Entity,
+    pub kind: ItemKind,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum HaulState {
+    /// Waiting for the items at the source or for its cell to be free of jobs
+    Waiting,
+    PickingUp(JobId),
+    /// Waiting for the destination cell to be free of jobs
+    Carrying {
+        cat: Entity,
+        carried: u32,
+    },
+    Delivering {
+        cat: Entity,
+        carried: u32,
+        job: JobId,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct Haul {
+    /// Stockpile or workstation, or the cat who found the items
+    pub from: Entity,
+    pub to: Entity,
+    pub layer: usize,
+    pub from_cell: UVec2,
+    pub to_cell: UVec2,
+    pub kind: ItemKind,
+    /// Items still to deliver
+    pub count: u32,
+    pub state: HaulState,
+}
+
+/// Requested hauls in the order they were requested. Cats carry as much as their strength
+/// allows, so a haul may take several trips
+#[derive(Resource, Debug, Default, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct Hauls(pub Vec<Haul>);
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hauled {
+    pub cat: Entity,
+    pub from: Entity,
+    pub to: Entity,
+    pub kind: ItemKind,
+    pub count: u32,
+}
+
+/// Stockpile with its items. Headless tests have no render assets
+pub fn spawn_stockpile(
+    cmds: &mut Commands,
+    stockpile: Stockpile,
+    inventory: Inventory,
+    meshes: Option<&mut Assets<Mesh>>,
+    materials: Option<&mut Assets<StandardMaterial>>,
+) -> Entity {
+    let mut entity = cmds.spawn((
+        Name::new("Stockpile"),
+        GameObject,
+        LayerObject(stockpile.layer),
+        stockpile,
+        inventory,
+        Transform::from_translation(cell_center(stockpile.cell, stockpile.layer as f32 + 0.52)),
+    ));
+    if let (Some(meshes), Some(materials)) = (meshes, materials) {
+        entity.insert((
+            Mesh3d(meshes.add(Cuboid::new(0.9, 0.04, 0.9))),
+            MeshMaterial3d(materials.add(Color::srgb(0.45, 0.3, 0.15))),
+        ));
+    }
+    entity.id()
+}
+
+fn place_stockpile(
+    ev: Trigger<PlaceStockpile>,
+    stockpiles: Query<&Stockpile>,
+    mut meshes: Option<ResMut<Assets<Mesh>>>,
+    mut materials: Option<ResMut<Assets<StandardMaterial>>>,
+    mut cmds: Commands,
+) {
+    if stockpiles
+        .iter()
+        .any(|stockpile| stockpile.layer == ev.layer && stockpile.cell == ev.cell)
+    {
+        return;
+    }
+    spawn_stockpile(
+        &mut cmds,
+        Stockpile {
+            layer: ev.layer,
+            cell: ev.cell,
+        },
+        Inventory::default(),
+        meshes.as_deref_mut(),
+        materials.as_deref_mut(),
+    );
+}
+
+fn request_haul(
+    ev: Trigger<RequestHaul>,
+    sites: Query<(Option<&Stockpile>, Option<&Workstation>), With<Inventory>>,
+    mut hauls: ResMut<Hauls>,
+) {
+    let site = |entity: Entity| match sites.get(entity) {
+        Ok((Some(stockpile), _)) => Some((stockpile.layer, stockpile.cell)),
+        Ok((None, Some(station))) => Some((station.layer, station.cell)),
+        _ => None,
+    };
+    let (Some((layer, from_cell)), Some((to_layer, to_cell))) = (site(ev.from), site(ev.to)) else {
+        warn!("Items can only be hauled between stockpiles and workstations");
+        return;
+    };
+    if layer != to_layer || ev.from == ev.to || ev.count == 0 {
+        warn!("Can't haul from {:?} to {:?}", ev.from, ev.to);
+        return;
+    }
+    hauls.0.push(Haul {
+        from: ev.from,
+        to: ev.to,
+        layer,
+        from_cell,
+        to_cell,
+        kind: ev.kind,
+        count: ev.count,
+        state: HaulState::Waiting,
+    });
+}
+
+fn distance(a: UVec2, b: UVec2) -> i32 {
+    (a.as_ivec2() - b.as_ivec2()).length_squared()
+}
+
+fn closest_stockpile<'a>(
+    stockpiles: impl Iterator<Item = (Entity, &'a Stockpile)>,
+    layer: usize,
+    cell: UVec2,
+) -> Option<(Entity, &'a Stockpile)> {
+    stockpiles
+        .filter(|(_, stockpile)| stockpile.layer == layer)
+        .min_by_key(|(entity, stockpile)| (distance(stockpile.cell, cell), *entity))
+}
+
+/// Finished digging and clearing jobs leave an item with the worker, who takes it to the closest
+/// stockpile of the layer. Without stockpiles the worker keeps it
+fn gather(
+    mut done: EventReader<JobDone>,
+    mut rng: ResMut<GameRng>,
+    mut inventories: Query<&mut Inventory>,
+    stockpiles: Query<(Entity, &Stockpile)>,
+    mut hauls: ResMut<Hauls>,
+    mut cmds: Commands,
+) {
+    for job in done.read() {
+        let yields = job.kind.yields();
+        if yields.is_empty() {
+            continue;
+        }
+        let kind = yields[rng.0.gen_range(0..yields.len())];
+        match inventories.get_mut(job.cat) {
+            Ok(mut inventory) => inventory.add(kind, ItemQuality::Common, 1),
+            Err(_) => {
+                cmds.entity(job.cat).insert(Inventory(vec![ItemStack {
+                    kind,
+                    quality: ItemQuality::Common,
+                    count: 1,
+                }]));
+            }
+        }
+        let Some((to, stockpile)) = closest_stockpile(stockpiles.iter(), job.layer, job.cell)
+        else {
+            continue;
+        };
+        hauls.0.push(Haul {
+            from: job.cat,
+            to,
+            layer: job.layer,
+            from_cell: job.cell,
+            to_cell: stockpile.cell,
+            kind,
+            count: 1,
+            state: HaulState::Carrying {
+                cat: job.cat,
+                carried: 1,
+            },
+        });
+    }
+}
+
+/// Missing ingredients of the next order of every workstation are hauled from the closest
+/// stockpiles of its layer which have them
+fn supply_workstations(
+    book: Option<Res<RecipeBook>>,
+    stations: Query<(Entity, &Workstation, &Inventory)>,
+    stockpiles: Query<(Entity, &Stockpile, &Inventory)>,
+    hauls: Res<Hauls>,
+    mut cmds: Commands,
+) {
+    let Some(book) = book else {
+        return;
+    };
+    // Items already on their way, carried ones are out of the source
+    let mut requested: Vec<(Entity, ItemKind, u32)> = hauls
+        .0
+        .iter()
+        .map(|haul| {
+            let carried = match haul.state {
+                HaulState::Carrying { carried, .. } | HaulState::Delivering { carried, .. } => {
+                    carried
+                }
+                HaulState::Waiting | HaulState::PickingUp(_) => 0,
+            };
+            (haul.from, haul.kind, haul.count.saturating_sub(carried))
+        })
+        .collect();
+    let mut stations: Vec<_> = stations.iter().collect();
+    stations.sort_by_key(|(entity, ..)| *entity);
+    for (to, station, inventory) in stations {
+        let Some(recipe) = station.orders.first().and_then(|name| book.get(name)) else {
+            continue;
+        };
+        for (kind, count) in &recipe.ingredients {
+            let incoming: u32 = hauls
+                .0
+                .iter()
+                .filter(|haul| haul.to == to && haul.kind == *kind)
+                .map(|haul| haul.count)
+                .sum();
+            let mut missing = count.saturating_sub(inventory.count(*kind) + incoming);
+            let mut sources: Vec<_> = stockpiles
+                .iter()
+                .filter(|(_, stockpile, _)| stockpile.layer == station.layer)
+                .collect();
+            sources.sort_by_key(|(entity, stockpile, _)| {
+                (distance(stockpile.cell, station.cell), *entity)
+            });
+            for (from, _, stock) in sources {
+                if missing == 0 {
+                    break;
+                }
+                let reserved: u32 = requested
+                    .iter()
+                    .filter(|(source, requested, _)| *source == from && requested == kind)
+                    .map(|(.., count)| count)
+                    .sum();
+                let count = stock.count(*kind).saturating_sub(reserved).min(missing);
+                if count == 0 {
+                    continue;
+                }
+                cmds.trigger(RequestHaul {
+                    from,
+                    to,
+                    kind: *kind,
+                    count,
+                });
+                requested.push((from, *kind, count));
+                missing -= count;
+            }
+        }
+    }
+}
+
+/// Finished jobs move items into and out of the haulers, cancelled jobs drop their hauls.
+/// Waiting hauls designate the next pick up or delivery
+fn haul(
+    mut hauls: ResMut<Hauls>,
+    mut queue: ResMut<JobQueue>,
+    mut done: EventReader<JobDone>,
+    mut inventories: Query<&mut Inventory>,
+    cats: Query<&CatSheet>,
+    mut hauled: EventWriter<Hauled>,
+    mut cmds: Commands,
+) {
+    for job in done.read().filter(|job| job.kind == DesignationKind::Haul) {
+        let at = |haul: &Haul, cell: UVec2| haul.layer == job.layer && cell == job.cell;
+        for haul in hauls.0.iter_mut() {
+            match haul.state {
+                HaulState::PickingUp(_) if at(haul, haul.from_cell) => {
+                    let load = inventories.get(job.cat).map_or(0, |inv| inv.weight());
+                    let capacity = cats.get(job.cat).map_or(0, carry_capacity);
+                    // Anyone can carry a single item, slowly
+                    let room = (capacity.saturating_sub(load) / haul.kind.weight()).max(1);
+                    let taken = inventories
+                        .get_mut(haul.from)
+                        .map(|mut inv| inv.take(haul.kind, haul.count.min(room)))
+                        .unwrap_or_default();
+                    let carried = taken.iter().map(|stack| stack.count).sum();
+                    match inventories.get_mut(job.cat) {
+                        Ok(mut inventory) => {
+                            for stack in taken {
+                                inventory.add(stack.kind, stack.quality, stack.count);
+                            }
+                        }
+                        Err(_) => {
+                            cmds.entity(job.cat).insert(Inventory(taken));
+                        }
+                    }
+                    haul.state = match carried {
+                        0 => HaulState::Waiting,
+                        _ => HaulState::Carrying {
+                            cat: job.cat,
+                            carried,
+                        },
+                    };
+                }
+                HaulState::Delivering { cat, carried, .. }
+                    if cat == job.cat && at(haul, haul.to_cell) =>
+                {
+                    let taken = inventories
+                        .get_mut(cat)
+                        .map(|mut inv| inv.take(haul.kind, carried))
+                        .unwrap_or_default();
+                    let mut count = 0;
+                    if let Ok(mut inventory) = inventories.get_mut(haul.to) {
+                        for stack in taken {
+                            inventory.add(stack.kind, stack.quality, stack.count);
+                            count += stack.count;
+                        }
+                    }
+                    haul.count = haul.count.saturating_sub(carried);
+                    haul.state = HaulState::Waiting;
+                    hauled.write(Hauled {
+                        cat,
+                        from: haul.from,
+                        to: haul.to,
+                        kind: haul.kind,
+                        count,
+                    });
+                }
+                _ => continue,
+            }
+            break;
+        }
+    }
+
+    hauls.0.retain(|haul| {
+        let kept = haul.count > 0
+            && inventories.contains(haul.from)
+            && inventories.contains(haul.to)
+            && match haul.state {
+                HaulState::Waiting => true,
+                // Cancelled by the player, carried items stay with the cat
+                HaulState::PickingUp(job) | HaulState::Delivering { job, .. } => {
+                    queue.get(job).is_some()
+                }
+                HaulState::Carrying { cat, .. } => cats.contains(cat),
+            };
+        if !kept {
+            info!("Haul of {:?} to {:?} is dropped", haul.kind, haul.to);
+        }
+        kept
+    });
+
+    for haul in hauls.0.iter_mut() {
+        match haul.state {
+            HaulState::Waiting => {
+                let available = inventories
+                    .get(haul.from)
+                    .is_ok_and(|inv| inv.count(haul.kind) > 0);
+                if available && queue.at(haul.layer, haul.from_cell).is_none() {
+                    let job = queue.designate(DesignationKind::Haul, haul.layer, haul.from_cell);
+                    haul.state = HaulState::PickingUp(job);
+                }
+            }
+            // The hauler delivers once it is free of other jobs
+            HaulState::Carrying { cat, carried } => {
+                if queue.at(haul.layer, haul.to_cell).is_some() || queue.assigned_to(cat).is_some()
+                {
+                    continue;
+                }
+                let id = queue.designate(DesignationKind::Haul, haul.layer, haul.to_cell);
+                if let Some(job) = queue.jobs.iter_mut().find(|job| job.id == id) {
+                    job.state = JobState::Assigned(cat);
+                }
+                haul.state = HaulState::Delivering {
+                    cat,
+                    carried,
+                    job: id,
+                };
+            }
+            HaulState::PickingUp(_) | HaulState::Delivering { .. } => {}
+        }
+    }
+}
+
+impl Plugin for HaulingPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Stockpile>();
+        app.register_type::<Hauls>();
+        app.init_game_resource::<Hauls>();
+        app.add_event::<Hauled>();
+        app.add_observer(place_stockpile);
+        app.add_observer(request_haul);
+        app.add_systems(
+            FixedUpdate,
+            (gather, supply_workstations, haul)
+                .chain()
+                .before(CatAiSet)
+                .in_set(SimulationSet),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_hauling_plugin.rs"]
+mod test_hauling_plugin;
@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPlugin, EguiPrimaryContextPass, egui};
+
+use crate::{
+    calendar_plugin::Calendar,
+    cat::{CatSheet, PrimaryAttribute, SecondaryAttribute},
+    cat_ai_plugin::Task,
+    combat_plugin::{Fighting, Health},
+    economy_plugin::{Commodity, Treasury, holdings},
+    game_map_plugin::GameMapData,
+    game_state_plugin::GameState,
+    hauling_plugin::Stockpile,
+    item::{Inventory, carry_capacity},
+    needs_plugin::{NEED_MAX, Need, NeedLevel, Needs},
+    save_plugin::GameSaved,
+    selection_plugin::Selected,
+    simulation_clock_plugin::SimulationClock,
+    spell_plugin::Mana,
+};
+
+/// Resource bar, date, speed and the panel of the selected cat
+pub struct HudPlugin;
+
+/// Seconds a save notice stays on the screen
+const SAVE_NOTICE_SECS: f64 = 3.0;
+
+pub fn commodity_label(commodity: Commodity) -> &'static str {
+    match commodity {
+        Commodity::Food => "Food",
+        Commodity::Herbs => "Herbs",
+        Commodity::Gold => "Gold",
+        Commodity::Books => "Books",
+        Commodity::ManaCrystals => "Mana crystals",
+    }
+}
+
+/// Days are shown counted from 1
+pub fn date_label(calendar: &Calendar) -> String {
+    format!(
+        "{:?}, day {}, year {}",
+        calendar.season,
+        calendar.day + 1,
+        calendar.year
+    )
+}
+
+pub fn speed_label(clock: &SimulationClock) -> String {
+    match clock.paused {
+        true => "Paused".to_string(),
+        false => format!("Speed x{}", clock.speed.multiplier()),
+    }
+}
+
+/// Primary attribute with its trait modifier, e.g. "Strength 12 (+3)"
+pub fn attribute_label(sheet: &CatSheet, attr: PrimaryAttribute) -> String {
+    let value = sheet.primary.get(attr);
+    match sheet.traits.modifier(attr) {
+        0 => format!("{attr:?} {value}"),
+        modifier => format!("{attr:?} {value} ({modifier:+})"),
+    }
+}
+
+fn resource_bar(
+    mut contexts: EguiContexts,
+    treasury: Option<Res<Treasury>>,
+    stockpiles: Query<&Inventory, With<Stockpile>>,
+    calendar: Res<Calendar>,
+    clock: Res<SimulationClock>,
+    map_data: Option<Res<GameMapData>>,
+) -> Result {
+    egui::TopBottomPanel::top("resource_bar").show(contexts.ctx_mut()?, |ui| {
+        ui.horizontal(|ui| {
+            // Treasury is missing without the economy
+            if let Some(treasury) = treasury {
+                let amounts = holdings(&treasury, stockpiles.iter());
+                for commodity in Commodity::ALL {
+                    let amount = amounts[commodity as usize];
+                    ui.label(format!("{} {amount}", commodity_label(commodity)));
+                    ui.separator();
+                }
+            }
+            ui.label(date_label(&calendar));
+            ui.separator();
+            ui.label(speed_label(&clock));
+            if let Some(map_data) = map_data {
+                ui.separator();
+                ui.label(format!("Layer {}", map_data.current_layer()));
+            }
+        });
+    });
+    Ok(())
+}
+
+/// Last save and the real time until which it is shown
+#[derive(Resource, Debug, Default)]
+struct SaveNotice {
+    text: String,
+    until: f64,
+}
+
+fn remember_saves(
+    mut evs: EventReader<GameSaved>,
+    time: Res<Time<Real>>,
+    mut notice: ResMut<SaveNotice>,
+) {
+    for GameSaved(path) in evs.read() {
+        let file = path.file_name().unwrap_or_default().to_string_lossy();
+        *notice = SaveNotice {
+            text: format!("Game saved to {file}"),
+            until: time.elapsed_secs_f64() + SAVE_NOTICE_SECS,
+        };
+    }
+}
+
+fn save_notice(
+    mut contexts: EguiContexts,
+    notice: Res<SaveNotice>,
+    time: Res<Time<Real>>,
+) -> Result {
+    if time.elapsed_secs_f64() >= notice.until {
+        return Ok(());
+    }
+    egui::Area::new(egui::Id::new("save_notice"))
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -16.0])
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.label(&notice.text);
+        });
+    Ok(())
+}
+
+type SelectedCat<'a> = (
+    Option<&'a Name>,
+    &'a Needs,
+    Option<&'a CatSheet>,
+    Option<&'a Task>,
+    Option<&'a Health>,
+    Option<&'a Fighting>,
+    Option<&'a Mana>,
+    Option<&'a Inventory>,
+);
+
+/// Attributes, traits, needs, task, health, mana and load of the selected cat
+fn selected_cat_panel(
+    mut contexts: EguiContexts,
+    selected: Query<SelectedCat, With<Selected>>,
+) -> Result {
+    let Ok((name, needs, sheet, task, health, fighting, mana, inventory)) = selected.single()
+    else {
+        return Ok(());
+    };
+    let title = name.map_or("Selected cat".to_string(), |name| name.to_string());
+    egui::Window::new("Selected cat")
+        .title_bar(false)
+        .anchor(egui::Align2::RIGHT_TOP, [-8.0, 32.0])
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.heading(title);
+            if let Some(task) = task {
+                ui.label(format!("Doing: {:?}", task.action));
+            }
+            if let Some(fighting) = fighting {
+                let effects: Vec<String> = fighting
+                    .effects
+                    .iter()
+                    .map(|(effect, _)| format!("{effect:?}"))
+                    .collect();
+                ui.label(format!("Fighting {}", effects.join(", ")));
+            }
+            if let Some(health) = health {
+                ui.add(
+                    egui::ProgressBar::new(health.current as f32 / health.max as f32)
+                        .fill(egui::Color32::DARK_RED)
+                        .text(format!("Health {}/{}", health.current, health.max)),
+                );
+            }
+            if let Some(mana) = mana {
+                ui.add(
+                    egui::ProgressBar::new(mana.current as f32 / mana.max.max(1) as f32)
+                        .fill(egui::Color32::DARK_BLUE)
+                        .text(format!("Mana {}/{}", mana.current, mana.max)),
+                );
+            }
+            if let Some(sheet) = sheet {
+                if let Some(inventory) = inventory {
+                    let (weight, capacity) = (inventory.weight(), carry_capacity(sheet));
+                    let overloaded = if weight > capacity {
+                        ", overloaded"
+                    } else {
+                        ""
+                    };
+                    ui.label(format!("Carrying {weight}/{capacity}{overloaded}"));
+                }
+                let traits: Vec<String> = sheet
+                    .traits
+                    .0
+                    .iter()
+                    .map(|cat_trait| format!("{cat_trait:?}"))
+                    .collect();
+                if !traits.is_empty() {
+                    ui.label(format!("Traits: {}", traits.join(", ")));
+                }
+                ui.label(format!("Best at {:?}", sheet.best_school()));
+                ui.collapsing("Attributes", |ui| {
+                    egui::Grid::new("attributes").show(ui, |ui| {
+                        for (primary, secondary) in PrimaryAttribute::ALL
+                            .map(Some)
+                            .into_iter()
+                            .chain([None])
+                            .zip(SecondaryAttribute::ALL)
+                        {
+                            match primary {
+                                Some(attr) => ui.label(attribute_label(sheet, attr)),
+                                None => ui.label(""),
+                            };
+                            let value = sheet.secondary.get(secondary);
+                            ui.label(format!("{secondary:?} {value}"));
+                            ui.end_row();
+                        }
+                    });
+                });
+            }
+            for need in Need::ALL {
+                let value = needs.get(need);
+                let color = match needs.level(need) {
+                    NeedLevel::Critical => egui::Color32::RED,
+                    NeedLevel::Low => egui::Color32::YELLOW,
+                    NeedLevel::Satisfied => egui::Color32::DARK_GREEN,
+                };
+                ui.add(
+                    egui::ProgressBar::new(value / NEED_MAX)
+                        .fill(color)
+                        .text(format!("{need:?} {value:.0}")),
+                );
+            }
+        });
+    Ok(())
+}
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        // Shared with the inspector
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin::default());
+        }
+        app.add_event::<GameSaved>();
+        app.init_resource::<SaveNotice>();
+        app.add_systems(Update, remember_saves);
+        app.add_systems(
+            EguiPrimaryContextPass,
+            (resource_bar, selected_cat_panel, save_notice)
+                .chain()
+                .run_if(in_state(GameState::Game)),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_hud_plugin.rs"]
+mod test_hud_plugin;
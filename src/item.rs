@@ -0,0 +1,241 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cat::{CatSheet, PrimaryAttribute, SecondaryAttribute},
+    needs_plugin::Need,
+};
+
+/// Weight a cat carries per point of strength
+pub const CARRY_PER_STRENGTH: u32 = 2;
+/// Overloaded cats walk at least this fast
+const MIN_OVERLOAD_SPEED: f32 = 0.25;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect, Serialize, Deserialize,
+)]
+pub enum ItemKind {
+    Herb,
+    Mushroom,
+    Crystal,
+    Catnip,
+    Feather,
+    StaminaPotion,
+    HeartyBroth,
+    CatnipTonic,
+    WillpowerDraught,
+    SwiftnessElixir,
+    EnchantedToy,
+}
+
+/// What using the item does to a cat
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemEffect {
+    RestoreNeed(Need, f32),
+    /// Permanent gain of the attribute
+    RaiseAttribute(SecondaryAttribute, i32),
+}
+
+impl ItemKind {
+    /// Effect of a common item, `None` for raw ingredients
+    pub fn effect(self) -> Option<ItemEffect> {
+        use ItemEffect::*;
+        match self {
+            Self::Herb | Self::Mushroom | Self::Crystal | Self::Catnip | Self::Feather => None,
+            Self::StaminaPotion => Some(RestoreNeed(Need::Energy, 40.0)),
+            Self::HeartyBroth => Some(RestoreNeed(Need::Hunger, 50.0)),
+            Self::CatnipTonic => Some(RestoreNeed(Need::Fun, 40.0)),
+            Self::EnchantedToy => Some(RestoreNeed(Need::Fun, 25.0)),
+            Self::WillpowerDraught => Some(RaiseAttribute(SecondaryAttribute::Willpower, 1)),
+            Self::SwiftnessElixir => Some(RaiseAttribute(SecondaryAttribute::Speed, 1)),
+        }
+    }
+
+    /// Need restored by using the item
+    pub fn restores(self) -> Option<Need> {
+        match self.effect() {
+            Some(ItemEffect::RestoreNeed(need, _)) => Some(need),
+            _ => None,
+        }
+    }
+
+    pub fn weight(self) -> u32 {
+        match self {
+            Self::Herb | Self::Catnip | Self::Feather => 1,
+            Self::Mushroom | Self::StaminaPotion | Self::CatnipTonic => 2,
+            Self::WillpowerDraught | Self::SwiftnessElixir | Self::EnchantedToy => 2,
+            Self::HeartyBroth => 3,
+            Self::Crystal => 5,
+        }
+    }
+}
+
+/// Crafted items are as good as the roll of the crafter, gathered ones are common
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Default,
+    Reflect,
+    Serialize,
+    Deserialize,
+)]
+pub enum ItemQuality {
+    Crude,
+    #[default]
+    Common,
+    Fine,
+    Superb,
+}
+
+impl ItemQuality {
+    /// Quality of a crafted item, by how much the crafting roll beat the difficulty
+    pub fn from_margin(margin: i32) -> Self {
+        match margin {
+            ..4 => Self::Crude,
+            4..8 => Self::Common,
+            8..12 => Self::Fine,
+            12.. => Self::Superb,
+        }
+    }
+
+    /// Effects of better items are stronger
+    pub fn scale(self, effect: ItemEffect) -> ItemEffect {
+        match effect {
+            ItemEffect::RestoreNeed(need, amount) => {
+                let multiplier = match self {
+                    Self::Crude => 0.5,
+                    Self::Common => 1.0,
+                    Self::Fine => 1.5,
+                    Self::Superb => 2.0,
+                };
+                ItemEffect::RestoreNeed(need, amount * multiplier)
+            }
+            ItemEffect::RaiseAttribute(attr, amount) => {
+                let bonus = match self {
+                    Self::Crude | Self::Common => 0,
+                    Self::Fine => 1,
+                    Self::Superb => 2,
+                };
+                ItemEffect::RaiseAttribute(attr, amount + bonus)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub kind: ItemKind,
+    pub quality: ItemQuality,
+    pub count: u32,
+}
+
+/// Items carried by a cat or stored in a workstation.
+/// Stacks are kept sorted by kind and quality
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Inventory(pub Vec<ItemStack>);
+
+impl Inventory {
+    pub fn add(&mut self, kind: ItemKind, quality: ItemQuality, count: u32) {
+        if count == 0 {
+            return;
+        }
+        match self
+            .0
+            .binary_search_by_key(&(kind, quality), |stack| (stack.kind, stack.quality))
+        {
+            Ok(idx) => self.0[idx].count += count,
+            Err(idx) => self.0.insert(
+                idx,
+                ItemStack {
+                    kind,
+                    quality,
+                    count,
+                },
+            ),
+        }
+    }
+
+    /// Number of items of the kind of any quality
+    pub fn count(&self, kind: ItemKind) -> u32 {
+        self.0
+            .iter()
+            .filter(|stack| stack.kind == kind)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    /// Take items of the kind, the worst ones first. Nothing is taken if there are not enough
+    pub fn remove(&mut self, kind: ItemKind, count: u32) -> bool {
+        if self.count(kind) < count {
+            return false;
+        }
+        let mut left = count;
+        for stack in self.0.iter_mut().filter(|stack| stack.kind == kind) {
+            let taken = stack.count.min(left);
+            stack.count -= taken;
+            left -= taken;
+        }
+        self.0.retain(|stack| stack.count > 0);
+        true
+    }
+
+    /// Take up to `count` items of the kind, the worst ones first
+    pub fn take(&mut self, kind: ItemKind, count: u32) -> Vec<ItemStack> {
+        let mut taken = vec![];
+        let mut left = count;
+        for stack in self.0.iter_mut().filter(|stack| stack.kind == kind) {
+            let count = stack.count.min(left);
+            if count > 0 {
+                taken.push(ItemStack { count, ..*stack });
+            }
+            stack.count -= count;
+            left -= count;
+        }
+        self.0.retain(|stack| stack.count > 0);
+        taken
+    }
+
+    pub fn weight(&self) -> u32 {
+        self.0
+            .iter()
+            .map(|stack| stack.kind.weight() * stack.count)
+            .sum()
+    }
+
+    /// Take a single item of the kind, the best one
+    pub fn take_best(&mut self, kind: ItemKind) -> Option<ItemQuality> {
+        let idx = self.0.iter().rposition(|stack| stack.kind == kind)?;
+        let quality = self.0[idx].quality;
+        self.0[idx].count -= 1;
+        if self.0[idx].count == 0 {
+            self.0.remove(idx);
+        }
+        Some(quality)
+    }
+}
+
+/// Weight the cat carries without slowing down
+pub fn carry_capacity(sheet: &CatSheet) -> u32 {
+    let strength = PrimaryAttribute::Strength;
+    (sheet.primary.get(strength) + sheet.traits.modifier(strength)).max(0) as u32
+        * CARRY_PER_STRENGTH
+}
+
+/// Walking speed multiplier of a cat carrying `weight`, 1 up to the capacity
+pub fn overload_speed(weight: u32, capacity: u32) -> f32 {
+    if weight <= capacity {
+        return 1.0;
+    }
+    (capacity as f32 / weight as f32).max(MIN_OVERLOAD_SPEED)
+}
+
+#[cfg(test)]
+#[path = "./tests/test_item.rs"]
+mod test_item;
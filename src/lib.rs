@@ -0,0 +1,37 @@
+pub mod alchemy_plugin;
+pub mod autosave_plugin;
+pub mod calendar_plugin;
+pub mod camera_bookmarks_plugin;
+pub mod cat;
+pub mod cat_ai_plugin;
+pub mod combat_plugin;
+pub mod designation_plugin;
+pub mod economy_plugin;
+pub mod experience_plugin;
+pub mod game_map_plugin;
+pub mod game_state_plugin;
+pub mod hauling_plugin;
+pub mod hud_plugin;
+pub mod inspector_plugin;
+pub mod item;
+pub mod light_plugin;
+pub mod main_menu_plugin;
+pub mod needs_plugin;
+pub mod orbit_camera_plugin;
+pub mod pathfinding;
+pub mod player_control_plugin;
+pub mod player_input_stage;
+pub mod rng_plugin;
+pub mod roll;
+pub mod ron_tree;
+pub mod save_plugin;
+pub mod selection_plugin;
+pub mod settings_plugin;
+pub mod simulation_clock_plugin;
+pub mod spell_plugin;
+pub mod student_plugin;
+pub mod timetable_plugin;
+
+#[cfg(test)]
+#[path = "./tests/test_utils.rs"]
+mod test_utils;
@@ -1,31 +1,67 @@
-mod inspector_plugin;
-mod light_plugin;
-mod player_control_plugin;
-
 use bevy::prelude::*;
-use game_map_plugin::GameMapPlugin;
-use game_state_plugin::GameStatePlugin;
-use inspector_plugin::InspectorPlugin;
-use light_plugin::LightPlugin;
-use player_control_plugin::PlayerControlPlugin;
-use player_input_stage::PlayerInputStagesPlugin;
-mod game_map_plugin;
-mod game_state_plugin;
-mod player_input_stage;
-
-#[path = "./tests/test_utils.rs"]
-mod test_utils;
+use macatemy::alchemy_plugin::AlchemyPlugin;
+use macatemy::autosave_plugin::AutosavePlugin;
+use macatemy::calendar_plugin::CalendarPlugin;
+use macatemy::camera_bookmarks_plugin::CameraBookmarksPlugin;
+use macatemy::cat_ai_plugin::CatAiPlugin;
+use macatemy::combat_plugin::CombatPlugin;
+use macatemy::designation_plugin::DesignationPlugin;
+use macatemy::economy_plugin::EconomyPlugin;
+use macatemy::experience_plugin::ExperiencePlugin;
+use macatemy::game_map_plugin::GameMapPlugin;
+use macatemy::game_state_plugin::GameStatePlugin;
+use macatemy::hauling_plugin::HaulingPlugin;
+use macatemy::hud_plugin::HudPlugin;
+use macatemy::inspector_plugin::InspectorPlugin;
+use macatemy::light_plugin::LightPlugin;
+use macatemy::main_menu_plugin::MainMenuPlugin;
+use macatemy::needs_plugin::NeedsPlugin;
+use macatemy::orbit_camera_plugin::OrbitCameraPlugin;
+use macatemy::player_control_plugin::PlayerControlPlugin;
+use macatemy::player_input_stage::PlayerInputStagesPlugin;
+use macatemy::rng_plugin::RngPlugin;
+use macatemy::save_plugin::SavePlugin;
+use macatemy::selection_plugin::SelectionPlugin;
+use macatemy::settings_plugin::SettingsPlugin;
+use macatemy::simulation_clock_plugin::SimulationClockPlugin;
+use macatemy::spell_plugin::SpellPlugin;
+use macatemy::student_plugin::StudentPlugin;
+use macatemy::timetable_plugin::TimetablePlugin;
 
 fn main() {
     let mut app = App::new();
     app.add_plugins((
         DefaultPlugins,
         GameStatePlugin,
+        SettingsPlugin::default(),
+        RngPlugin::default(),
         PlayerInputStagesPlugin,
         LightPlugin,
         GameMapPlugin,
         PlayerControlPlugin,
+        OrbitCameraPlugin,
+        SelectionPlugin,
+        CameraBookmarksPlugin,
         InspectorPlugin,
+        HudPlugin,
+        MainMenuPlugin,
+    ));
+    app.add_plugins((
+        SimulationClockPlugin,
+        CalendarPlugin,
+        StudentPlugin,
+        TimetablePlugin,
+        ExperiencePlugin,
+        NeedsPlugin,
+        CatAiPlugin,
+        DesignationPlugin,
+        CombatPlugin,
+        SpellPlugin,
+        AlchemyPlugin,
+        HaulingPlugin,
+        EconomyPlugin,
+        SavePlugin::default(),
+        AutosavePlugin,
     ));
     app.run();
 }
@@ -0,0 +1,257 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPlugin, EguiPrimaryContextPass, egui};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    autosave_plugin::{AutosaveConfig, newest_save},
+    cat::{CatSheet, PrimaryAttribute, generate_cat_name},
+    game_state_plugin::GameState,
+    hud_plugin::attribute_label,
+    rng_plugin::{GameRng, GameSeed},
+    save_plugin::{LoadGame, SavePath},
+    settings_plugin::Settings,
+};
+
+/// The game starts in the main menu, where a new game is set up
+pub struct MainMenuPlugin;
+
+/// Amenities and classrooms are laid out on the first 10x10 cells
+pub const MIN_MAP_SIZE: u32 = 10;
+pub const MAX_MAP_SIZE: u32 = 40;
+
+/// Choices of the new game screen, used when the map is spawned.
+/// Saved with the game, so restarting a loaded game sets it up the same way
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NewGameSettings {
+    /// Seed of the game RNG
+    pub seed: u64,
+    /// (columns, rows)
+    pub map_size: UVec2,
+    pub founder_name: String,
+    /// Founding mother of the academy, its first teacher
+    pub founder: CatSheet,
+    rerolls: u64,
+}
+
+impl NewGameSettings {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Self::founder_rng(seed, 0);
+        Self {
+            seed,
+            map_size: UVec2::splat(10),
+            founder: CatSheet::generate(&mut rng),
+            founder_name: generate_cat_name(&mut rng),
+            rerolls: 0,
+        }
+    }
+
+    /// Founders of a seed are reproducible, every reroll takes its own stream
+    fn founder_rng(seed: u64, rerolls: u64) -> ChaCha8Rng {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(rerolls);
+        rng
+    }
+
+    /// New seed keeps the map size
+    pub fn set_seed(&mut self, seed: u64) {
+        *self = Self {
+            map_size: self.map_size,
+            ..Self::new(seed)
+        };
+    }
+
+    pub fn reroll_founder(&mut self) {
+        self.rerolls += 1;
+        let mut rng = Self::founder_rng(self.seed, self.rerolls);
+        self.founder = CatSheet::generate(&mut rng);
+        self.founder_name = generate_cat_name(&mut rng);
+    }
+}
+
+impl Default for NewGameSettings {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+/// Trigger to start the game set up by `NewGameSettings`
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartNewGame;
+
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum MenuScreen {
+    #[default]
+    Main,
+    NewGame,
+    Settings,
+}
+
+/// Egui draws the menu through it, the game spawns its own camera
+#[derive(Component)]
+struct MenuCamera;
+
+fn spawn_menu_camera(mut cmds: Commands) {
+    cmds.spawn((Name::new("Menu Camera"), MenuCamera, Camera2d));
+}
+
+fn despawn_menu_camera(cameras: Query<Entity, With<MenuCamera>>, mut cmds: Commands) {
+    for camera in &cameras {
+        cmds.entity(camera).despawn();
+    }
+}
+
+fn start_new_game(
+    _: Trigger<StartNewGame>,
+    settings: Res<NewGameSettings>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut cmds: Commands,
+) {
+    cmds.insert_resource(GameSeed(settings.seed));
+    cmds.insert_resource(GameRng::from_seed(settings.seed));
+    next_state.set(GameState::Init);
+}
+
+fn reset_menu_screen(mut screen: ResMut<MenuScreen>) {
+    *screen = MenuScreen::Main;
+}
+
+fn new_game_screen(ui: &mut egui::Ui, settings: &mut NewGameSettings) {
+    ui.horizontal(|ui| {
+        ui.label("World seed");
+        let mut seed = settings.seed;
+        if ui.add(egui::DragValue::new(&mut seed)).changed() {
+            settings.set_seed(seed);
+        }
+        if ui.button("Random").clicked() {
+            settings.set_seed(rand::random());
+        }
+    });
+    let sizes = MIN_MAP_SIZE..=MAX_MAP_SIZE;
+    ui.add(egui::Slider::new(&mut settings.map_size.x, sizes.clone()).text("Map width"));
+    ui.add(egui::Slider::new(&mut settings.map_size.y, sizes).text("Map height"));
+    ui.separator();
+
+    let founder = &settings.founder;
+    ui.label(format!("Founding mother: {}", settings.founder_name));
+    for attr in PrimaryAttribute::ALL {
+        ui.label(attribute_label(founder, attr));
+    }
+    let traits: Vec<String> = founder
+        .traits
+        .0
+        .iter()
+        .map(|cat_trait| format!("{cat_trait:?}"))
+        .collect();
+    if !traits.is_empty() {
+        ui.label(format!("Traits: {}", traits.join(", ")));
+    }
+    ui.label(format!("Best at {:?}", founder.best_school()));
+    if ui.button("Reroll").clicked() {
+        settings.reroll_founder();
+    }
+}
+
+fn settings_screen(ui: &mut egui::Ui, settings: &mut Mut<Settings>) {
+    // Settings are saved on change, keep quiet while nothing changes
+    let mouse = &mut settings.bypass_change_detection().mouse;
+    let mut changed = ui
+        .add(
+            egui::Slider::new(&mut mouse.sensitivity, 0.0005..=0.01)
+                .logarithmic(true)
+                .text("Mouse sensitivity"),
+        )
+        .changed();
+    changed |= ui.checkbox(&mut mouse.invert_y, "Invert mouse Y").changed();
+    if changed {
+        settings.set_changed();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn main_menu(
+    mut contexts: EguiContexts,
+    mut screen: ResMut<MenuScreen>,
+    mut new_game: ResMut<NewGameSettings>,
+    mut settings: ResMut<Settings>,
+    save_path: Option<Res<SavePath>>,
+    autosave: Option<Res<AutosaveConfig>>,
+    mut exit: EventWriter<AppExit>,
+    mut cmds: Commands,
+) -> Result {
+    egui::CentralPanel::default().show(contexts.ctx_mut()?, |ui| {
+        ui.vertical_centered(|ui| {
+            ui.heading("Magical cats academy");
+            ui.separator();
+            match *screen {
+                MenuScreen::Main => {
+                    if ui.button("New Game").clicked() {
+                        *screen = MenuScreen::NewGame;
+                    }
+                    // Autosaves count, the game may have crashed since the last save
+                    let slots = autosave.as_ref().map_or(0, |config| config.slots);
+                    let save = save_path
+                        .as_ref()
+                        .and_then(|path| newest_save(&path.0, slots));
+                    let clicked = ui
+                        .add_enabled(save.is_some(), egui::Button::new("Load"))
+                        .on_disabled_hover_text("No saved games")
+                        .clicked();
+                    if let Some(save) = save.filter(|_| clicked) {
+                        cmds.trigger(LoadGame(save));
+                    }
+                    if ui.button("Settings").clicked() {
+                        *screen = MenuScreen::Settings;
+                    }
+                    if ui.button("Quit").clicked() {
+                        exit.write(AppExit::Success);
+                    }
+                }
+                MenuScreen::NewGame => {
+                    new_game_screen(ui, &mut new_game);
+                    ui.separator();
+                    if ui.button("Start").clicked() {
+                        cmds.trigger(StartNewGame);
+                    }
+                    if ui.button("Back").clicked() {
+                        *screen = MenuScreen::Main;
+                    }
+                }
+                MenuScreen::Settings => {
+                    settings_screen(ui, &mut settings.reborrow());
+                    if ui.button("Back").clicked() {
+                        *screen = MenuScreen::Main;
+                    }
+                }
+            }
+        });
+    });
+    Ok(())
+}
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        // Shared with the inspector and the HUD
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin::default());
+        }
+        app.insert_state(GameState::MainMenu);
+        app.init_resource::<NewGameSettings>();
+        app.init_resource::<MenuScreen>();
+        app.add_observer(start_new_game);
+        app.add_systems(
+            OnEnter(GameState::MainMenu),
+            (reset_menu_screen, spawn_menu_camera),
+        );
+        app.add_systems(OnExit(GameState::MainMenu), despawn_menu_camera);
+        app.add_systems(
+            EguiPrimaryContextPass,
+            main_menu.run_if(in_state(GameState::MainMenu)),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_main_menu_plugin.rs"]
+mod test_main_menu_plugin;
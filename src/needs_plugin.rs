@@ -0,0 +1,274 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cat::CatSheet,
+    game_state_plugin::{GameObject, GameState},
+    simulation_clock_plugin::SimulationSet,
+};
+
+/// Needs of cats decay over time and are satisfied by map objects
+pub struct NeedsPlugin;
+
+pub const NEED_MAX: f32 = 100.0;
+pub const NEED_LOW: f32 = 30.0;
+pub const NEED_CRITICAL: f32 = 10.0;
+/// Attribute value which keeps base decay rates
+const AVERAGE_ATTRIBUTE: f32 = 13.0;
+/// Cats interact with objects and each other within this distance
+pub const INTERACTION_DISTANCE: f32 = 1.5;
+/// Social need restored per tick by every cat nearby
+pub const SOCIAL_PER_TICK: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum Need {
+    Hunger,
+    Energy,
+    Fun,
+    Hygiene,
+    Social,
+}
+
+impl Need {
+    pub const ALL: [Need; 5] = [
+        Self::Hunger,
+        Self::Energy,
+        Self::Fun,
+        Self::Hygiene,
+        Self::Social,
+    ];
+
+    /// Decay per simulation tick of an average cat
+    fn base_decay(self) -> f32 {
+        match self {
+            Self::Hunger => 0.05,
+            Self::Energy => 0.04,
+            Self::Fun => 0.06,
+            Self::Hygiene => 0.03,
+            Self::Social => 0.04,
+        }
+    }
+
+    /// Decay per simulation tick. Hardy cats tire slower, charming cats long for company faster
+    pub fn decay(self, sheet: Option<&CatSheet>) -> f32 {
+        let Some(sheet) = sheet else {
+            return self.base_decay();
+        };
+        let factor = match self {
+            Self::Energy => AVERAGE_ATTRIBUTE / sheet.secondary.constitution.max(1) as f32,
+            Self::Social => sheet.primary.charm as f32 / AVERAGE_ATTRIBUTE,
+            _ => 1.0,
+        };
+        self.base_decay() * factor.clamp(0.5, 2.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect)]
+pub enum NeedLevel {
+    Critical,
+    Low,
+    Satisfied,
+}
+
+impl NeedLevel {
+    pub fn of(value: f32) -> Self {
+        if value < NEED_CRITICAL {
+            Self::Critical
+        } else if value < NEED_LOW {
+            Self::Low
+        } else {
+            Self::Satisfied
+        }
+    }
+}
+
+/// Needs from 0 (desperate) to `NEED_MAX` (fully satisfied)
+#[derive(Component, Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Needs {
+    pub hunger: f32,
+    pub energy: f32,
+    pub fun: f32,
+    pub hygiene: f32,
+    pub social: f32,
+}
+
+impl Default for Needs {
+    fn default() -> Self {
+        Self {
+            hunger: NEED_MAX,
+            energy: NEED_MAX,
+            fun: NEED_MAX,
+            hygiene: NEED_MAX,
+            social: NEED_MAX,
+        }
+    }
+}
+
+impl Needs {
+    pub fn get(&self, need: Need) -> f32 {
+        match need {
+            Need::Hunger => self.hunger,
+            Need::Energy => self.energy,
+            Need::Fun => self.fun,
+            Need::Hygiene => self.hygiene,
+            Need::Social => self.social,
+        }
+    }
+
+    pub fn get_mut(&mut self, need: Need) -> &mut f32 {
+        match need {
+            Need::Hunger => &mut self.hunger,
+            Need::Energy => &mut self.energy,
+            Need::Fun => &mut self.fun,
+            Need::Hygiene => &mut self.hygiene,
+            Need::Social => &mut self.social,
+        }
+    }
+
+    pub fn level(&self, need: Need) -> NeedLevel {
+        NeedLevel::of(self.get(need))
+    }
+}
+
+/// Sent when a need crosses a threshold in either direction
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NeedLevelChanged {
+    pub cat: Entity,
+    pub need: Need,
+    pub level: NeedLevel,
+}
+
+/// Map object which satisfies the need of cats using it
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct NeedSource {
+    pub need: Need,
+    pub per_tick: f32,
+}
+
+/// Cat is interacting with the map object. Removed once the need is fully satisfied
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Using(pub Entity);
+
+/// Name, need, restored per tick, cell (column, row)
+const AMENITIES: [(&str, Need, f32, (usize, usize)); 4] = [
+    ("Food bowl", Need::Hunger, 1.0, (1, 7)),
+    ("Basket", Need::Energy, 0.5, (8, 8)),
+    ("Yarn ball", Need::Fun, 0.8, (4, 8)),
+    ("Puddle", Need::Hygiene, 1.0, (6, 6)),
+];
+
+fn add_needs(ev: Trigger<OnAdd, CatSheet>, mut cmds: Commands) {
+    cmds.entity(ev.target()).insert_if_new(Needs::default());
+}
+
+fn spawn_amenities(
+    mut cmds: Commands,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<StandardMaterial>>>,
+) {
+    // Headless tests have no render assets
+    let mut render = meshes.zip(materials);
+    for (name, need, per_tick, (col, row)) in AMENITIES {
+        let mut amenity = cmds.spawn((
+            GameObject,
+            Name::new(name),
+            NeedSource { need, per_tick },
+            Transform::from_xyz(col as f32, 0.7, row as f32),
+        ));
+        if let Some((meshes, materials)) = render.as_mut() {
+            let color = match need {
+                Need::Hunger => Color::srgb(0.9, 0.5, 0.1),
+                Need::Energy => Color::srgb(0.4, 0.3, 0.8),
+                Need::Fun => Color::srgb(0.9, 0.2, 0.6),
+                Need::Hygiene => Color::srgb(0.2, 0.6, 0.9),
+                Need::Social => Color::srgb(0.9, 0.9, 0.2),
+            };
+            amenity.insert((
+                Mesh3d(meshes.add(Cuboid::from_length(0.4))),
+                MeshMaterial3d(materials.add(color)),
+            ));
+        }
+    }
+}
+
+fn within_reach(a: &Transform, b: &Transform) -> bool {
+    a.translation.xz().distance(b.translation.xz()) <= INTERACTION_DISTANCE
+}
+
+type NeedsQueryItem<'a> = (
+    Entity,
+    &'a mut Needs,
+    &'a Transform,
+    Option<&'a CatSheet>,
+    Option<&'a Using>,
+);
+
+fn update_needs(
+    mut cats: Query<NeedsQueryItem>,
+    sources: Query<(&NeedSource, &Transform)>,
+    mut changed: EventWriter<NeedLevelChanged>,
+    mut cmds: Commands,
+) {
+    let positions: Vec<(Entity, Transform)> = cats
+        .iter()
+        .map(|(entity, _, tr, ..)| (entity, *tr))
+        .collect();
+    for (cat, mut needs, tr, sheet, using) in &mut cats {
+        let mut restored = [0.0; Need::ALL.len()];
+        let source = using
+            .and_then(|using| sources.get(using.0).ok())
+            .filter(|(_, source_tr)| within_reach(tr, source_tr))
+            .map(|(source, _)| source);
+        match source {
+            Some(source) => restored[source.need as usize] += source.per_tick,
+            // Too far or gone
+            None if using.is_some() => {
+                cmds.entity(cat).remove::<Using>();
+            }
+            None => {}
+        }
+        let company = positions
+            .iter()
+            .filter(|(other, other_tr)| *other != cat && within_reach(tr, other_tr))
+            .count();
+        restored[Need::Social as usize] += company as f32 * SOCIAL_PER_TICK;
+
+        for need in Need::ALL {
+            let before = needs.level(need);
+            let value = needs.get_mut(need);
+            *value = (*value - need.decay(sheet) + restored[need as usize]).clamp(0.0, NEED_MAX);
+            let after = needs.level(need);
+            if before != after {
+                changed.write(NeedLevelChanged {
+                    cat,
+                    need,
+                    level: after,
+                });
+            }
+        }
+        if let Some(source) = source
+            && needs.get(source.need) >= NEED_MAX
+        {
+            cmds.entity(cat).remove::<Using>();
+        }
+    }
+}
+
+impl Plugin for NeedsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Needs>();
+        app.register_type::<NeedSource>();
+        app.register_type::<Using>();
+        app.add_event::<NeedLevelChanged>();
+        app.add_observer(add_needs);
+        app.add_systems(OnEnter(GameState::Game), spawn_amenities);
+        app.add_systems(FixedUpdate, update_needs.in_set(SimulationSet));
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_needs_plugin.rs"]
+mod test_needs_plugin;
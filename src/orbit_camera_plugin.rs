@@ -0,0 +1,228 @@
+use crate::{
+    game_state_plugin::GameState,
+    player_control_plugin::{CameraMode, MoveCameraXZ, Player, PlayerCommand},
+    player_input_stage::{PlayerInputPostUpdate, PlayerInputPreUpdate},
+    settings_plugin::Settings,
+};
+use bevy::{input::mouse::AccumulatedMouseMotion, prelude::*, window::PrimaryWindow};
+use std::f32::consts::FRAC_PI_2;
+
+pub struct OrbitCameraPlugin;
+
+/// Height of the surface the camera orbits around.
+/// Layer renderers are shifted so that the active layer is always at y=0,
+/// so the focus is placed on top of its floor cubes
+pub const ORBIT_FOCUS_Y: f32 = 0.5;
+pub const ORBIT_MIN_DISTANCE: f32 = 2.0;
+pub const ORBIT_MAX_DISTANCE: f32 = 30.0;
+pub const ORBIT_MIN_PITCH: f32 = -FRAC_PI_2 + 0.05;
+pub const ORBIT_MAX_PITCH: f32 = -0.1;
+/// Distance used if the camera doesn't look at the ground when switching to the orbit mode
+const ORBIT_FALLBACK_DISTANCE: f32 = 5.0;
+/// Pan speed per unit of distance to the focus, so panning feels the same at any zoom level
+const ORBIT_PAN_SPEED: f32 = 1.0;
+/// Part of the distance removed per one unit of `MoveCameraInOut`
+const ORBIT_ZOOM_FACTOR: f32 = 0.01;
+/// How fast current pose catches up with the target pose, higher is snappier
+const ORBIT_SMOOTHNESS: f32 = 10.0;
+/// Distance in logical pixels from the window border which triggers edge scrolling
+pub const EDGE_SCROLL_MARGIN: f32 = 16.0;
+
+/// Orbiting camera position described relatively to the point it looks at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitPose {
+    pub focus: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+}
+
+impl OrbitPose {
+    /// Find a pose which matches the transform: the focus is where camera looks at the active layer
+    pub fn from_transform(tr: &Transform) -> Self {
+        let (yaw, pitch, _) = tr.rotation.to_euler(EulerRot::YXZ);
+        let forward = tr.forward();
+        let height = tr.translation.y - ORBIT_FOCUS_Y;
+        let distance = if forward.y < -f32::EPSILON && height > 0.0 {
+            height / -forward.y
+        } else {
+            ORBIT_FALLBACK_DISTANCE
+        };
+        let focus = (tr.translation + forward * distance).with_y(ORBIT_FOCUS_Y);
+        Self {
+            focus,
+            yaw,
+            pitch,
+            distance,
+        }
+    }
+
+    pub fn rotation(&self) -> Quat {
+        Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0)
+    }
+
+    pub fn to_transform(self) -> Transform {
+        let rotation = self.rotation();
+        let translation = self.focus + rotation * Vec3::Z * self.distance;
+        Transform::from_translation(translation).with_rotation(rotation)
+    }
+
+    pub fn clamped(self) -> Self {
+        Self {
+            pitch: self.pitch.clamp(ORBIT_MIN_PITCH, ORBIT_MAX_PITCH),
+            distance: self.distance.clamp(ORBIT_MIN_DISTANCE, ORBIT_MAX_DISTANCE),
+            ..self
+        }
+    }
+
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            focus: self.focus.lerp(other.focus, t),
+            yaw: self.yaw.lerp(other.yaw, t),
+            pitch: self.pitch.lerp(other.pitch, t),
+            distance: self.distance.lerp(other.distance, t),
+        }
+    }
+}
+
+/// State of the camera in the orbit mode.
+/// Commands change `target`, `current` eases towards it and is applied to the transform
+#[derive(Component, Debug)]
+pub struct OrbitCameraRig {
+    pub current: OrbitPose,
+    pub target: OrbitPose,
+}
+
+impl OrbitCameraRig {
+    pub fn from_transform(tr: &Transform) -> Self {
+        let current = OrbitPose::from_transform(tr);
+        Self {
+            current,
+            target: current.clamped(),
+        }
+    }
+}
+
+type PlayerWithoutRig = (With<Player>, Without<OrbitCameraRig>);
+
+fn attach_orbit_rig(players: Query<(Entity, &Transform), PlayerWithoutRig>, mut cmds: Commands) {
+    for (entity, tr) in players {
+        cmds.entity(entity)
+            .insert(OrbitCameraRig::from_transform(tr));
+    }
+}
+
+fn detach_orbit_rig(players: Query<Entity, With<OrbitCameraRig>>, mut cmds: Commands) {
+    for entity in players {
+        cmds.entity(entity).remove::<OrbitCameraRig>();
+    }
+}
+
+fn orbit_look(
+    mut rig: Single<&mut OrbitCameraRig>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    settings: Res<Settings>,
+    window: Single<&Window, With<PrimaryWindow>>,
+) {
+    if !window.focused || !mouse.pressed(MouseButton::Middle) {
+        return;
+    }
+    let look = settings.mouse.look_delta(mouse_motion.delta);
+    let target = &mut rig.target;
+    target.yaw += look.x;
+    target.pitch += look.y;
+    *target = target.clamped();
+}
+
+fn orbit_edge_scroll(
+    mut ev: EventWriter<PlayerCommand>,
+    window: Single<&Window, With<PrimaryWindow>>,
+) {
+    if !window.focused {
+        return;
+    }
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    let right = if cursor.x < EDGE_SCROLL_MARGIN {
+        -1.0
+    } else if cursor.x > window.width() - EDGE_SCROLL_MARGIN {
+        1.0
+    } else {
+        0.0
+    };
+    // Window Y axis goes down
+    let forward = if cursor.y < EDGE_SCROLL_MARGIN {
+        1.0
+    } else if cursor.y > window.height() - EDGE_SCROLL_MARGIN {
+        -1.0
+    } else {
+        0.0
+    };
+    if (forward, right) != (0.0, 0.0) {
+        ev.write(PlayerCommand::MoveCameraXZ(MoveCameraXZ::new(
+            forward, right,
+        )));
+    }
+}
+
+fn orbit_cmd_move_camera(
+    mut evs: EventReader<PlayerCommand>,
+    mut rig: Single<&mut OrbitCameraRig>,
+    time: Res<Time<Real>>,
+) {
+    for ev in evs.read() {
+        let target = &mut rig.target;
+        match ev {
+            PlayerCommand::MoveCameraXZ(move_camera) => {
+                // Pan along the ground: camera's pitch doesn't matter, only yaw does
+                let yaw = Quat::from_rotation_y(target.yaw);
+                let forward = yaw * Vec3::NEG_Z;
+                let right = yaw * Vec3::X;
+                let direction = (move_camera.forward() * forward + move_camera.right() * right)
+                    .normalize_or_zero();
+                target.focus += direction * ORBIT_PAN_SPEED * target.distance * time.delta_secs();
+            }
+            PlayerCommand::MoveCameraInOut(n) => {
+                target.distance *= (1.0 - *n * ORBIT_ZOOM_FACTOR).max(0.0);
+            }
+            _ => continue,
+        }
+        *target = target.clamped();
+    }
+}
+
+fn orbit_ease(
+    player: Single<(&mut Transform, &mut OrbitCameraRig), With<Player>>,
+    time: Res<Time<Real>>,
+) {
+    let (mut tr, mut rig) = player.into_inner();
+    let t = 1.0 - (-ORBIT_SMOOTHNESS * time.delta_secs()).exp();
+    rig.current = rig.current.lerp(&rig.target, t);
+    *tr = rig.current.to_transform();
+}
+
+impl Plugin for OrbitCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(CameraMode::Orbit), detach_orbit_rig);
+        app.add_systems(
+            PlayerInputPreUpdate,
+            (attach_orbit_rig, orbit_look, orbit_edge_scroll)
+                .chain()
+                .run_if(in_state(GameState::Game))
+                .run_if(in_state(CameraMode::Orbit)),
+        );
+        app.add_systems(
+            PlayerInputPostUpdate,
+            (orbit_cmd_move_camera, orbit_ease)
+                .chain()
+                .run_if(in_state(GameState::Game))
+                .run_if(in_state(CameraMode::Orbit)),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_orbit_camera_plugin.rs"]
+mod test_orbit_camera_plugin;
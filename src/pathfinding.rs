@@ -0,0 +1,88 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use bevy::prelude::*;
+
+use crate::game_map_plugin::{GameMap, GameMapCellFloor};
+
+/// Cell (column, row) under the world position. Cell centers are at integer x and z
+pub fn cell_of(translation: Vec3) -> UVec2 {
+    UVec2::new(
+        translation.x.round().max(0.0) as u32,
+        translation.z.round().max(0.0) as u32,
+    )
+}
+
+/// World position of the cell center at the given height
+pub fn cell_center(cell: UVec2, y: f32) -> Vec3 {
+    Vec3::new(cell.x as f32, y, cell.y as f32)
+}
+
+/// Cats walk on cells which have a floor
+pub fn is_walkable(map: &GameMap, layer: usize, cell: UVec2) -> bool {
+    map.cells
+        .get(layer)
+        .and_then(|rows| rows.get(cell.y as usize))
+        .and_then(|row| row.get(cell.x as usize))
+        .is_some_and(|cell| cell.floor() != GameMapCellFloor::None)
+}
+
+/// 4-connected cells around the cell
+pub fn neighbours(cell: UVec2) -> impl Iterator<Item = UVec2> {
+    // Fixed order keeps paths reproducible
+    [IVec2::NEG_Y, IVec2::X, IVec2::Y, IVec2::NEG_X]
+        .into_iter()
+        .map(move |dir| cell.as_ivec2() + dir)
+        .filter(|cell| cell.x >= 0 && cell.y >= 0)
+        .map(|cell| cell.as_uvec2())
+}
+
+fn manhattan(a: UVec2, b: UVec2) -> u32 {
+    a.x.abs_diff(b.x) + a.y.abs_diff(b.y)
+}
+
+/// A* over the 4-connected cells of the layer.
+/// Returns cells to walk through, without `from` and with `to`, or `None` if `to` can't be reached.
+/// Equal cost paths are resolved by the cell order, so the same map always gives the same path
+pub fn find_path(map: &GameMap, layer: usize, from: UVec2, to: UVec2) -> Option<Vec<UVec2>> {
+    if !is_walkable(map, layer, to) {
+        return None;
+    }
+    // (estimated total cost, estimated rest, row, column)
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<UVec2, UVec2> = HashMap::new();
+    let mut cost: HashMap<UVec2, u32> = HashMap::from([(from, 0)]);
+    let rest = manhattan(from, to);
+    open.push(Reverse((rest, rest, from.y, from.x)));
+
+    while let Some(Reverse((_, _, y, x))) = open.pop() {
+        let cell = UVec2::new(x, y);
+        if cell == to {
+            let mut path = vec![];
+            let mut cell = cell;
+            while cell != from {
+                path.push(cell);
+                cell = came_from[&cell];
+            }
+            path.reverse();
+            return Some(path);
+        }
+        let next_cost = cost[&cell] + 1;
+        for next in neighbours(cell).filter(|next| is_walkable(map, layer, *next)) {
+            if cost.get(&next).is_some_and(|known| *known <= next_cost) {
+                continue;
+            }
+            cost.insert(next, next_cost);
+            came_from.insert(next, cell);
+            let rest = manhattan(next, to);
+            open.push(Reverse((next_cost + rest, rest, next.y, next.x)));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+#[path = "./tests/test_pathfinding.rs"]
+mod test_pathfinding;
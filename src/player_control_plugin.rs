@@ -1,13 +1,17 @@
 use crate::{
+    camera_bookmarks_plugin::CAMERA_BOOKMARKS,
     game_map_plugin::ShiftActiveLayerEvent,
-    game_state_plugin::{GameObject, GameState},
+    game_state_plugin::{AfterUninit, GameObject, GameState},
+    pathfinding::cell_of,
     player_input_stage::{PlayerInputPostUpdate, PlayerInputPreUpdate},
+    settings_plugin::Settings,
 };
 use bevy::{
     input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit},
     prelude::*,
     window::PrimaryWindow,
 };
+use bevy_egui::{EguiContext, PrimaryEguiContext};
 use std::f32::consts::FRAC_PI_2;
 
 pub struct PlayerControlPlugin;
@@ -30,14 +34,55 @@ impl MoveCameraXZ {
 #[derive(Event, Debug)]
 pub enum PlayerCommand {
     QuitApp,
+    /// Leave the game through `GameState::Uninit` to the main menu
+    ReturnToMenu,
+    /// Start the game over through `GameState::Uninit` with the same seed
+    RestartGame,
+    /// Write the game to the save file
+    SaveGame,
+    /// Replace the game with the one in the save file
+    LoadGame,
     MoveCameraXZ(MoveCameraXZ),
     MoveCameraInOut(f32),
     ShiftActiveLayer(isize),
+    ToggleCameraMode,
+    /// Remember camera position and active layer in the slot [0..CAMERA_BOOKMARKS)
+    StoreCameraBookmark(usize),
+    /// Fly back to camera position stored in the slot [0..CAMERA_BOOKMARKS)
+    RecallCameraBookmark(usize),
+    SelectNext,
+    JumpToSelected,
+    /// Start or stop following the selected entity with the camera
+    FollowSelected,
+    TogglePause,
+    /// Pause the simulation and advance it by one tick
+    StepSimulation,
+    /// Make simulation faster (positive) or slower (negative)
+    ChangeSimulationSpeed(isize),
+    /// Switch to the next designation tool
+    CycleDesignationTool,
+    /// Queue the next recipe of the recipe book at the selected workstation
+    OrderNextRecipe,
+    /// Apply the designation tool to the rectangle of cells (column, row) of the active layer
+    DesignateArea {
+        from: UVec2,
+        to: UVec2,
+    },
 }
 
 #[derive(Component)]
 pub struct Player;
 
+/// Controller used to move the player camera around
+#[derive(Debug, States, Hash, PartialEq, Eq, Clone, Copy, Default)]
+pub enum CameraMode {
+    /// FPS-like camera: moves along its forward/right vector, looks around with the mouse
+    #[default]
+    FreeFly,
+    /// RTS-like camera: orbits around a focus point on the active layer
+    Orbit,
+}
+
 fn spawn_camera(mut commands: Commands) {
     let looking_at = Vec3::new(5.0, 0.0, 5.0);
     let tr = Transform::from_xyz(0.0, 2.0, 4.0).looking_at(looking_at, Vec3::Y);
@@ -60,18 +105,17 @@ fn player_look(
     mut player: Single<&mut Transform, With<Camera3d>>,
     mouse: Res<ButtonInput<MouseButton>>,
     mouse_motion: Res<AccumulatedMouseMotion>,
-    time: Res<Time>,
+    settings: Res<Settings>,
     window: Single<&Window, With<PrimaryWindow>>,
 ) {
     if !window.focused || !mouse.pressed(MouseButton::Middle) {
         return;
     }
-    let dt = time.delta_secs();
-    let sensetivity = 100. / window.width().min(window.height());
     let (mut yaw, mut pitch, _) = player.rotation.to_euler(EulerRot::YXZ);
 
-    pitch -= mouse_motion.delta.y * dt * sensetivity;
-    yaw -= mouse_motion.delta.x * dt * sensetivity;
+    let look = settings.mouse.look_delta(mouse_motion.delta);
+    yaw += look.x;
+    pitch += look.y;
     pitch = pitch.clamp(-FRAC_PI_2, FRAC_PI_2);
     player.rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0);
 }
@@ -88,11 +132,27 @@ fn directional_keys(input: &ButtonInput<KeyCode>, pos: KeyCode, neg: KeyCode) ->
 
 fn player_keyboard_input(mut ev: EventWriter<PlayerCommand>, input: Res<ButtonInput<KeyCode>>) {
     let shift = input.pressed(KeyCode::ShiftLeft) || input.pressed(KeyCode::ShiftRight);
+    let ctrl = input.pressed(KeyCode::ControlLeft) || input.pressed(KeyCode::ControlRight);
 
     // Alt+Q
     if input.pressed(KeyCode::AltLeft) && input.just_pressed(KeyCode::KeyQ) {
         ev.write(PlayerCommand::QuitApp);
     }
+    // Alt+R
+    if input.pressed(KeyCode::AltLeft) && input.just_pressed(KeyCode::KeyR) {
+        ev.write(PlayerCommand::RestartGame);
+    }
+    // F10
+    if input.just_pressed(KeyCode::F10) {
+        ev.write(PlayerCommand::ReturnToMenu);
+    }
+    // F5, F9
+    if input.just_pressed(KeyCode::F5) {
+        ev.write(PlayerCommand::SaveGame);
+    }
+    if input.just_pressed(KeyCode::F9) {
+        ev.write(PlayerCommand::LoadGame);
+    }
 
     // W, A, S, D
     let move_fwd = directional_keys(&input, KeyCode::KeyW, KeyCode::KeyS);
@@ -111,6 +171,104 @@ fn player_keyboard_input(mut ev: EventWriter<PlayerCommand>, input: Res<ButtonIn
     if shift && input.just_pressed(KeyCode::Period) {
         ev.write(PlayerCommand::ShiftActiveLayer(-1));
     }
+
+    // C
+    if input.just_pressed(KeyCode::KeyC) {
+        ev.write(PlayerCommand::ToggleCameraMode);
+    }
+
+    // Ctrl+1..9, 1..9
+    const BOOKMARK_KEYS: [KeyCode; CAMERA_BOOKMARKS] = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    for (slot, key) in BOOKMARK_KEYS.into_iter().enumerate() {
+        if input.just_pressed(key) {
+            ev.write(if ctrl {
+                PlayerCommand::StoreCameraBookmark(slot)
+            } else {
+                PlayerCommand::RecallCameraBookmark(slot)
+            });
+        }
+    }
+
+    // Tab, J, F
+    if input.just_pressed(KeyCode::Tab) {
+        ev.write(PlayerCommand::SelectNext);
+    }
+    if input.just_pressed(KeyCode::KeyJ) {
+        ev.write(PlayerCommand::JumpToSelected);
+    }
+    if input.just_pressed(KeyCode::KeyF) {
+        ev.write(PlayerCommand::FollowSelected);
+    }
+
+    // Space, N, -, =
+    if input.just_pressed(KeyCode::Space) {
+        ev.write(PlayerCommand::TogglePause);
+    }
+    if input.just_pressed(KeyCode::KeyN) {
+        ev.write(PlayerCommand::StepSimulation);
+    }
+    if input.just_pressed(KeyCode::Equal) {
+        ev.write(PlayerCommand::ChangeSimulationSpeed(1));
+    }
+    if input.just_pressed(KeyCode::Minus) {
+        ev.write(PlayerCommand::ChangeSimulationSpeed(-1));
+    }
+
+    // Z, O
+    if input.just_pressed(KeyCode::KeyZ) {
+        ev.write(PlayerCommand::CycleDesignationTool);
+    }
+    if input.just_pressed(KeyCode::KeyO) {
+        ev.write(PlayerCommand::OrderNextRecipe);
+    }
+}
+
+/// Drag with the left mouse button to select a rectangle of cells
+fn player_mouse_designate(
+    mut ev: EventWriter<PlayerCommand>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform), With<Player>>,
+    mut egui: Query<&mut EguiContext, With<PrimaryEguiContext>>,
+    mut drag_start: Local<Option<UVec2>>,
+) {
+    // Top of the floor boxes of the active layer
+    const FLOOR_TOP: f32 = 0.5;
+    if !(mouse.just_pressed(MouseButton::Left) || mouse.just_released(MouseButton::Left)) {
+        return;
+    }
+    // Clicks on the HUD are not meant for the map. Tests run without egui
+    if egui
+        .iter_mut()
+        .any(|mut ctx| ctx.get_mut().wants_pointer_input())
+    {
+        *drag_start = None;
+        return;
+    }
+    let (camera, camera_tr) = *camera;
+    let cell = window
+        .cursor_position()
+        .and_then(|pos| camera.viewport_to_world(camera_tr, pos).ok())
+        .and_then(|ray| {
+            let distance =
+                ray.intersect_plane(Vec3::Y * FLOOR_TOP, InfinitePlane3d::new(Vec3::Y))?;
+            Some(cell_of(ray.get_point(distance)))
+        });
+    if mouse.just_pressed(MouseButton::Left) {
+        *drag_start = cell;
+    } else if let (Some(from), Some(to)) = (drag_start.take(), cell) {
+        ev.write(PlayerCommand::DesignateArea { from, to });
+    }
 }
 
 fn player_move_with_mouse_wheel(
@@ -136,6 +294,23 @@ fn player_cmd_quit(mut evs: EventReader<PlayerCommand>, mut exit: EventWriter<Ap
     }
 }
 
+/// Game objects are cleaned up in `Uninit` on the way to the menu or a new game
+fn player_cmd_leave_game(
+    mut evs: EventReader<PlayerCommand>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut after: ResMut<AfterUninit>,
+) {
+    for ev in evs.read() {
+        let next = match ev {
+            PlayerCommand::ReturnToMenu => GameState::MainMenu,
+            PlayerCommand::RestartGame => GameState::Init,
+            _ => continue,
+        };
+        *after = AfterUninit(next);
+        next_state.set(GameState::Uninit);
+    }
+}
+
 fn player_cmd_move_camera(
     mut evs: EventReader<PlayerCommand>,
     mut player: Single<&mut Transform, With<Player>>,
@@ -167,6 +342,22 @@ fn player_cmd_shift_active_layer(mut evs: EventReader<PlayerCommand>, mut cmds:
     }
 }
 
+fn player_cmd_toggle_camera_mode(
+    mut evs: EventReader<PlayerCommand>,
+    mode: Res<State<CameraMode>>,
+    mut next_mode: ResMut<NextState<CameraMode>>,
+) {
+    for _ in evs
+        .read()
+        .filter(|x| matches!(x, PlayerCommand::ToggleCameraMode))
+    {
+        next_mode.set(match mode.get() {
+            CameraMode::FreeFly => CameraMode::Orbit,
+            CameraMode::Orbit => CameraMode::FreeFly,
+        });
+    }
+}
+
 fn ensure_grabbed_cursor(window: &mut Window) {
     use bevy::window::CursorGrabMode;
     if window.cursor_options.grab_mode == CursorGrabMode::None {
@@ -211,14 +402,17 @@ fn grab_focused_window(
 impl Plugin for PlayerControlPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<PlayerCommand>();
+        app.init_state::<CameraMode>();
+        app.init_resource::<Settings>();
         app.add_systems(PreUpdate, grab_focused_window);
         app.add_systems(Update, spawn_camera.run_if(in_state(GameState::Init)));
         app.add_systems(
             PlayerInputPreUpdate,
             (
-                player_look,
+                player_look.run_if(in_state(CameraMode::FreeFly)),
                 player_keyboard_input,
                 player_move_with_mouse_wheel,
+                player_mouse_designate,
             )
                 .run_if(in_state(GameState::Game)),
         );
@@ -226,8 +420,10 @@ impl Plugin for PlayerControlPlugin {
             PlayerInputPostUpdate,
             (
                 player_cmd_quit,
+                player_cmd_leave_game,
                 player_cmd_shift_active_layer,
-                player_cmd_move_camera,
+                player_cmd_toggle_camera_mode,
+                player_cmd_move_camera.run_if(in_state(CameraMode::FreeFly)),
             )
                 .run_if(in_state(GameState::Game)),
         );
@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::game_state_plugin::GameState;
+
+/// Provides the random number generator of the game world.
+/// With a seed the game is reproducible, which tests rely on
+#[derive(Default)]
+pub struct RngPlugin {
+    pub seed: Option<u64>,
+}
+
+/// The only source of randomness for the simulation.
+/// Systems that use it must not run in parallel with each other, which `ResMut` guarantees
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng(pub ChaCha8Rng);
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+/// Seed of the current game, the RNG starts over from it when the game is cleaned up
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameSeed(pub u64);
+
+fn reset_rng(seed: Res<GameSeed>, mut cmds: Commands) {
+    cmds.insert_resource(GameRng::from_seed(seed.0));
+}
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        let seed = self.seed.unwrap_or_else(rand::random);
+        app.insert_resource(GameSeed(seed));
+        app.insert_resource(GameRng::from_seed(seed));
+        app.add_systems(OnEnter(GameState::Uninit), reset_rng);
+    }
+}
@@ -0,0 +1,347 @@
+use std::{error::Error, fmt, ops::Range, str::FromStr};
+
+use bevy::log::debug;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Dice roll as described in the design document, e.g. `8d10(drop 4 high)` or `1d{luck}+3`.
+/// Serialized in the same notation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Roll {
+    pub dice: u32,
+    pub sides: u32,
+    /// Number of highest dice discarded from the result
+    pub drop_high: u32,
+    /// Number of lowest dice discarded from the result
+    pub drop_low: u32,
+    pub bonus: i32,
+}
+
+/// Largest number of dice accepted by the notation, `distribution` allocates their square
+pub const MAX_DICE: u32 = 20;
+/// Largest number of sides accepted by the notation
+pub const MAX_SIDES: u32 = 100;
+
+impl Roll {
+    /// `8d10(drop 4 high)`: goes from 4 to 40 with average around 13
+    pub const DEFAULT: Roll = Roll {
+        dice: 8,
+        sides: 10,
+        drop_high: 4,
+        drop_low: 0,
+        bonus: 0,
+    };
+
+    /// Roll representing chaos and despair
+    pub const CHAOS: Roll = Roll::DEFAULT;
+
+    pub const fn new(dice: u32, sides: u32) -> Self {
+        Self {
+            dice,
+            sides,
+            drop_high: 0,
+            drop_low: 0,
+            bonus: 0,
+        }
+    }
+
+    /// `1d{value}+{bonus}`. Non-positive attributes make a die which always rolls 0
+    pub fn attribute(value: i32, bonus: i32) -> Self {
+        Self {
+            bonus,
+            ..Self::new(1, value.max(0) as u32)
+        }
+    }
+
+    pub fn roll(&self, rng: &mut impl Rng) -> i32 {
+        self.roll_detailed(rng).total
+    }
+
+    /// Roll keeping every die, for debugging
+    pub fn roll_detailed(&self, rng: &mut impl Rng) -> RollDetail {
+        let mut dice: Vec<i32> = (0..self.dice)
+            .map(|_| match self.sides {
+                0 => 0,
+                sides => rng.gen_range(1..=sides) as i32,
+            })
+            .collect();
+        dice.sort_unstable();
+        let kept = self.kept(dice.len());
+        let total = dice[kept.clone()].iter().sum::<i32>() + self.bonus;
+        RollDetail { dice, kept, total }
+    }
+
+    /// Positions of the kept dice among `dice` dice sorted from the lowest
+    fn kept(&self, dice: usize) -> Range<usize> {
+        let end = dice.saturating_sub(self.drop_high as usize);
+        let start = (self.drop_low as usize).min(end);
+        start..end
+    }
+
+    /// Exact probability of every total, for balancing.
+    /// Dice are assigned faces from the highest one down, so it is known which of them are dropped
+    pub fn distribution(&self) -> Distribution {
+        let dice = self.dice as usize;
+        let faces: Vec<(i32, f64)> = match self.sides {
+            0 => vec![(0, 1.0)],
+            sides => (1..=sides as i32)
+                .rev()
+                .map(|face| (face, 1.0 / sides as f64))
+                .collect(),
+        };
+        // Positions counted from the highest die
+        let kept = self.kept(dice);
+        let kept = dice - kept.end..dice - kept.start;
+        let max_sum = dice * self.sides as usize;
+
+        // [assigned dice][sum of kept dice]
+        let mut dp = vec![vec![0.0; max_sum + 1]; dice + 1];
+        dp[0][0] = 1.0;
+        for (face, p) in faces {
+            let mut next = vec![vec![0.0; max_sum + 1]; dice + 1];
+            for assigned in 0..=dice {
+                for sum in 0..=max_sum {
+                    let weight = dp[assigned][sum];
+                    if weight == 0.0 {
+                        continue;
+                    }
+                    for count in 0..=dice - assigned {
+                        let positions = assigned..assigned + count;
+                        let kept_count = positions
+                            .end
+                            .min(kept.end)
+                            .saturating_sub(positions.start.max(kept.start));
+                        let ways = binomial(dice - assigned, count) * p.powi(count as i32);
+                        next[assigned + count][sum + kept_count * face as usize] += weight * ways;
+                    }
+                }
+            }
+            dp = next;
+        }
+        Distribution {
+            min: self.bonus,
+            probabilities: dp.swap_remove(dice),
+        }
+    }
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseRollError(pub String);
+
+impl fmt::Display for ParseRollError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid roll: {}", self.0)
+    }
+}
+
+impl Error for ParseRollError {}
+
+fn parse_number<T: FromStr>(value: &str, what: &str) -> Result<T, ParseRollError> {
+    value
+        .parse()
+        .map_err(|_| ParseRollError(format!("bad {what} `{value}`")))
+}
+
+impl FromStr for Roll {
+    type Err = ParseRollError;
+
+    /// Notation of `Display`, e.g. `8d10(drop 4 high)` or `1d13+3`. Number of dice defaults to 1
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        let (head, drops) = s.split_at(s.find('(').unwrap_or(s.len()));
+        let Some((dice, rest)) = head.split_once('d') else {
+            return Err(ParseRollError(format!("no `d` in `{s}`")));
+        };
+        let mut roll = Roll::new(1, 0);
+        if !dice.is_empty() {
+            roll.dice = parse_number(dice, "number of dice")?;
+        }
+        let (sides, bonus) = rest.split_at(rest.find(['+', '-']).unwrap_or(rest.len()));
+        roll.sides = parse_number(sides, "number of sides")?;
+        if roll.dice > MAX_DICE || roll.sides > MAX_SIDES {
+            return Err(ParseRollError(format!(
+                "more than {MAX_DICE} dice or {MAX_SIDES} sides in `{s}`"
+            )));
+        }
+        if !bonus.is_empty() {
+            roll.bonus = parse_number(bonus.trim_start_matches('+'), "bonus")?;
+        }
+        for drop in drops.split_terminator(')') {
+            let drop = drop
+                .strip_prefix("(drop")
+                .ok_or_else(|| ParseRollError(format!("expected `(drop` in `{s}`")))?;
+            if let Some(count) = drop.strip_suffix("high") {
+                roll.drop_high = parse_number(count, "number of dropped dice")?;
+            } else if let Some(count) = drop.strip_suffix("low") {
+                roll.drop_low = parse_number(count, "number of dropped dice")?;
+            } else {
+                return Err(ParseRollError(format!("expected `high` or `low` in `{s}`")));
+            }
+        }
+        Ok(roll)
+    }
+}
+
+impl TryFrom<String> for Roll {
+    type Error = ParseRollError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Roll> for String {
+    fn from(value: Roll) -> Self {
+        value.to_string()
+    }
+}
+
+/// Single roll with every die
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RollDetail {
+    /// Rolled dice from the lowest
+    pub dice: Vec<i32>,
+    /// Positions of the dice which were not dropped
+    pub kept: Range<usize>,
+    pub total: i32,
+}
+
+impl fmt::Display for RollDetail {
+    /// Dropped dice are in parentheses: `(1) 3 5 (9) = 8`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, die) in self.dice.iter().enumerate() {
+            match self.kept.contains(&idx) {
+                true => write!(f, "{die} ")?,
+                false => write!(f, "({die}) ")?,
+            }
+        }
+        write!(f, "= {}", self.total)
+    }
+}
+
+/// Probability of every total of a roll
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    /// Total of the first probability
+    pub min: i32,
+    pub probabilities: Vec<f64>,
+}
+
+impl Distribution {
+    /// Possible totals with their probabilities
+    pub fn totals(&self) -> impl Iterator<Item = (i32, f64)> + '_ {
+        self.probabilities
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| **p > 0.0)
+            .map(|(idx, p)| (self.min + idx as i32, *p))
+    }
+
+    pub fn lowest(&self) -> i32 {
+        self.totals().next().map_or(self.min, |(total, _)| total)
+    }
+
+    pub fn highest(&self) -> i32 {
+        self.totals().last().map_or(self.min, |(total, _)| total)
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.totals().map(|(total, p)| total as f64 * p).sum()
+    }
+
+    /// Lowest total which is rolled or beaten with the probability `1 - fraction`
+    pub fn percentile(&self, fraction: f64) -> i32 {
+        let mut cumulative = 0.0;
+        for (total, p) in self.totals() {
+            cumulative += p;
+            // Float sums fall a bit short of exact fractions
+            if cumulative >= fraction - 1e-12 {
+                return total;
+            }
+        }
+        self.highest()
+    }
+}
+
+/// Roll A wins against roll B if strictly greater, ties are no result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContestOutcome {
+    Win,
+    Lose,
+    Tie,
+}
+
+impl ContestOutcome {
+    /// Outcome for A of already rolled totals
+    pub fn of(a: i32, b: i32) -> Self {
+        match a.cmp(&b) {
+            std::cmp::Ordering::Greater => Self::Win,
+            std::cmp::Ordering::Less => Self::Lose,
+            std::cmp::Ordering::Equal => Self::Tie,
+        }
+    }
+}
+
+/// Roll A, then B. Bonuses of the rolls, e.g. from traits, are applied
+pub fn contest(a: &Roll, b: &Roll, rng: &mut impl Rng) -> ContestOutcome {
+    let a_detail = a.roll_detailed(rng);
+    let b_detail = b.roll_detailed(rng);
+    let outcome = ContestOutcome::of(a_detail.total, b_detail.total);
+    debug!("Contest {a}: {a_detail} vs {b}: {b_detail}: {outcome:?}");
+    outcome
+}
+
+/// Exact chances of A in a contest against B, for balancing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContestOdds {
+    pub win: f64,
+    pub lose: f64,
+    pub tie: f64,
+}
+
+impl ContestOdds {
+    pub fn of(a: &Roll, b: &Roll) -> Self {
+        let (a, b) = (a.distribution(), b.distribution());
+        let mut odds = Self {
+            win: 0.0,
+            lose: 0.0,
+            tie: 0.0,
+        };
+        for (a_total, a_p) in a.totals() {
+            for (b_total, b_p) in b.totals() {
+                let p = a_p * b_p;
+                match ContestOutcome::of(a_total, b_total) {
+                    ContestOutcome::Win => odds.win += p,
+                    ContestOutcome::Lose => odds.lose += p,
+                    ContestOutcome::Tie => odds.tie += p,
+                }
+            }
+        }
+        odds
+    }
+}
+
+impl fmt::Display for Roll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}d{}", self.dice, self.sides)?;
+        if self.bonus != 0 {
+            write!(f, "{:+}", self.bonus)?;
+        }
+        if self.drop_high != 0 {
+            write!(f, "(drop {} high)", self.drop_high)?;
+        }
+        if self.drop_low != 0 {
+            write!(f, "(drop {} low)", self.drop_low)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_roll.rs"]
+mod test_roll;
@@ -0,0 +1,303 @@
+//! Untyped RON document which keeps every token, enum variants included.
+//! `ron::Value` drops the names of structs and variants, so it can't be written back
+
+/// Parsed value. Numbers, strings and chars are kept as they were written
+#[derive(Debug, Clone, PartialEq)]
+pub enum RonTree {
+    /// Number, string or char
+    Literal(String),
+    /// Unit variant, `None`, `true` or `false`
+    Ident(String),
+    /// `Name(field: value, ..)`, the name is optional
+    Struct(Option<String>, Vec<(String, RonTree)>),
+    /// `Name(value, ..)` like `Some(1)` or a tuple variant, the name is optional
+    Tuple(Option<String>, Vec<RonTree>),
+    List(Vec<RonTree>),
+    Map(Vec<(RonTree, RonTree)>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RonTreeError {
+    /// Byte offset in the text
+    pub position: usize,
+    pub message: &'static str,
+}
+
+impl std::fmt::Display for RonTreeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.position)
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &'static str) -> RonTreeError {
+        RonTreeError {
+            position: self.pos,
+            message,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    /// Whitespace and comments
+    fn skip(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if trimmed.starts_with("/*") {
+                self.pos += trimmed.find("*/").map_or(trimmed.len(), |end| end + 2);
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip();
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char, message: &'static str) -> Result<(), RonTreeError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    /// Token of identifier or number characters
+    fn word(&mut self) -> &'a str {
+        self.skip();
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_alphanumeric() || "_+-.".contains(c)))
+            .unwrap_or(self.rest().len());
+        self.pos += len;
+        &self.text[start..self.pos]
+    }
+
+    /// String or char literal with its quotes and escapes
+    fn quoted(&mut self, quote: char) -> Result<&'a str, RonTreeError> {
+        let start = self.pos;
+        let mut escaped = false;
+        for (idx, c) in self.rest().char_indices().skip(1) {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if c == quote => {
+                    self.pos += idx + 1;
+                    return Ok(&self.text[start..self.pos]);
+                }
+                _ => {}
+            }
+        }
+        Err(self.error("unterminated literal"))
+    }
+
+    /// Items until the closing bracket, trailing comma allowed
+    fn items<T>(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<T, RonTreeError>,
+    ) -> Result<Vec<T>, RonTreeError> {
+        let mut items = vec![];
+        while !self.eat(close) {
+            items.push(item(self)?);
+            if !self.eat(',') {
+                self.expect(close, "expected a comma or a closing bracket")?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    /// Contents of parentheses, fields if the first item is `name:`
+    fn group(&mut self, name: Option<String>) -> Result<RonTree, RonTreeError> {
+        self.skip();
+        let start = self.pos;
+        let first = self.word().to_string();
+        let is_struct = !first.is_empty()
+            && self.eat(':')
+            && !self.rest().starts_with(':')
+            && !first.starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c));
+        self.pos = start;
+        if !is_struct {
+            return Ok(RonTree::Tuple(name, self.items(')', Self::value)?));
+        }
+        let fields = self.items(')', |parser| {
+            let field = parser.word().to_string();
+            parser.expect(':', "expected a colon after the field name")?;
+            Ok((field, parser.value()?))
+        })?;
+        Ok(RonTree::Struct(name, fields))
+    }
+
+    fn value(&mut self) -> Result<RonTree, RonTreeError> {
+        match self.peek() {
+            None => Err(self.error("unexpected end")),
+            Some('(') => {
+                self.pos += 1;
+                self.group(None)
+            }
+            Some('[') => {
+                self.pos += 1;
+                Ok(RonTree::List(self.items(']', Self::value)?))
+            }
+            Some('{') => {
+                self.pos += 1;
+                let entries = self.items('}', |parser| {
+                    let key = parser.value()?;
+                    parser.expect(':', "expected a colon after the map key")?;
+                    Ok((key, parser.value()?))
+                })?;
+                Ok(RonTree::Map(entries))
+            }
+            Some(quote @ ('"' | '\'')) => Ok(RonTree::Literal(self.quoted(quote)?.to_string())),
+            Some(c) if c.is_ascii_digit() || "+-.".contains(c) => {
+                Ok(RonTree::Literal(self.word().to_string()))
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let name = self.word().to_string();
+                // Named struct, tuple variant or `Some`
+                if self.rest().starts_with('(') {
+                    self.pos += 1;
+                    return self.group(Some(name));
+                }
+                Ok(RonTree::Ident(name))
+            }
+            Some(_) => Err(self.error("unexpected character")),
+        }
+    }
+}
+
+impl RonTree {
+    pub fn parse(text: &str) -> Result<Self, RonTreeError> {
+        let mut parser = Parser { text, pos: 0 };
+        let value = parser.value()?;
+        match parser.peek() {
+            None => Ok(value),
+            Some(_) => Err(parser.error("trailing characters")),
+        }
+    }
+
+    /// Value of the field of a struct
+    pub fn field(&self, name: &str) -> Option<&RonTree> {
+        match self {
+            Self::Struct(_, fields) => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut RonTree> {
+        match self {
+            Self::Struct(_, fields) => fields
+                .iter_mut()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Replace the value of the field, or add the field to the struct
+    pub fn set_field(&mut self, name: &str, value: RonTree) {
+        if let Some(old) = self.field_mut(name) {
+            *old = value;
+        } else if let Self::Struct(_, fields) = self {
+            fields.push((name.to_string(), value));
+        }
+    }
+
+    pub fn remove_field(&mut self, name: &str) -> Option<RonTree> {
+        let Self::Struct(_, fields) = self else {
+            return None;
+        };
+        let idx = fields.iter().position(|(field, _)| field == name)?;
+        Some(fields.remove(idx).1)
+    }
+
+    pub fn rename_field(&mut self, from: &str, to: &str) {
+        if let Self::Struct(_, fields) = self {
+            for (field, _) in fields.iter_mut().filter(|(field, _)| field == from) {
+                *field = to.to_string();
+            }
+        }
+    }
+
+    /// Unsigned number of a literal
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            Self::Literal(literal) => literal.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+fn write_list<T>(
+    f: &mut std::fmt::Formatter<'_>,
+    items: &[T],
+    mut item: impl FnMut(&mut std::fmt::Formatter<'_>, &T) -> std::fmt::Result,
+) -> std::fmt::Result {
+    for (idx, value) in items.iter().enumerate() {
+        if idx > 0 {
+            write!(f, ", ")?;
+        }
+        item(f, value)?;
+    }
+    Ok(())
+}
+
+/// Compact RON which `ron::from_str` reads back
+impl std::fmt::Display for RonTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Literal(text) | Self::Ident(text) => write!(f, "{text}"),
+            Self::Struct(name, fields) => {
+                write!(f, "{}(", name.as_deref().unwrap_or_default())?;
+                write_list(f, fields, |f, (field, value)| write!(f, "{field}: {value}"))?;
+                write!(f, ")")
+            }
+            Self::Tuple(name, items) => {
+                write!(f, "{}(", name.as_deref().unwrap_or_default())?;
+                write_list(f, items, |f, value| write!(f, "{value}"))?;
+                write!(f, ")")
+            }
+            Self::List(items) => {
+                write!(f, "[")?;
+                write_list(f, items, |f, value| write!(f, "{value}"))?;
+                write!(f, "]")
+            }
+            Self::Map(entries) => {
+                write!(f, "{{")?;
+                write_list(f, entries, |f, (key, value)| write!(f, "{key}: {value}"))?;
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_ron_tree.rs"]
+mod test_ron_tree;
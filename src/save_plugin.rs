@@ -0,0 +1,653 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    alchemy_plugin::{Workstation, spawn_workstation},
+    calendar_plugin::Calendar,
+    cat::{CatSheet, MagicSchool},
+    cat_ai_plugin::{Action, Task, WalkPath},
+    combat_plugin::Health,
+    designation_plugin::{DesignationKind, Job, JobId, JobQueue, JobState},
+    economy_plugin::{Ledger, Treasury},
+    experience_plugin::Experience,
+    game_map_plugin::{GameMapData, GameMapFloors},
+    game_state_plugin::{AfterUninit, GameObject, GameState},
+    hauling_plugin::{Stockpile, spawn_stockpile},
+    item::Inventory,
+    main_menu_plugin::NewGameSettings,
+    needs_plugin::Needs,
+    player_control_plugin::PlayerCommand,
+    player_input_stage::PlayerInputPostUpdate,
+    rng_plugin::{GameRng, GameSeed},
+    ron_tree::{RonTree, RonTreeError},
+    selection_plugin::Selectable,
+    simulation_clock_plugin::SimulationClock,
+    spell_plugin::Mana,
+    student_plugin::{Alumni, AlumniRecord, Enrollment, Teacher},
+    timetable_plugin::{Class, Classroom, Classrooms, LessonSlot, Timetable},
+};
+
+/// Writes the game to a file and loads it back through `GameState::Uninit` and `Init`
+pub struct SavePlugin {
+    pub path: PathBuf,
+}
+
+impl Default for SavePlugin {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("savegame.ron"),
+        }
+    }
+}
+
+/// Version of the save format written by this build
+pub const SAVE_VERSION: u32 = 2;
+
+/// Upgrades of older saves, the one at index `i` brings version `i + 1` to `i + 2`.
+/// Add one with every change of the format and bump `SAVE_VERSION`.
+/// They work on the untyped document, which is read as `SavedGame` after the last one
+const MIGRATIONS: [fn(&mut RonTree); SAVE_VERSION as usize - 1] = [add_school_records];
+
+/// Version 2 keeps the alumni, the classrooms and the timetable. Older games get the usual
+/// classrooms and plan their classes anew
+fn add_school_records(save: &mut RonTree) {
+    let classrooms = ron::to_string(&Classrooms::default().0).expect("classrooms are plain data");
+    let classrooms = RonTree::parse(&classrooms).expect("ron writes valid documents");
+    save.set_field("alumni", RonTree::List(vec![]));
+    save.set_field("classrooms", classrooms);
+    save.set_field("timetable", RonTree::List(vec![]));
+}
+
+/// Position of the game RNG, it goes on with exactly the same numbers
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SavedRng {
+    pub seed: [u8; 32],
+    pub stream: u64,
+    /// (high, low) halves, RON has no 128-bit integers
+    pub word_pos: (u64, u64),
+}
+
+impl SavedRng {
+    pub fn new(rng: &ChaCha8Rng) -> Self {
+        let word_pos = rng.get_word_pos();
+        Self {
+            seed: rng.get_seed(),
+            stream: rng.get_stream(),
+            word_pos: ((word_pos >> 64) as u64, word_pos as u64),
+        }
+    }
+
+    pub fn restore(&self) -> ChaCha8Rng {
+        let mut rng = ChaCha8Rng::from_seed(self.seed);
+        rng.set_stream(self.stream);
+        rng.set_word_pos(((self.word_pos.0 as u128) << 64) | self.word_pos.1 as u128);
+        rng
+    }
+}
+
+/// Task which doesn't depend on other entities.
+/// Cats busy with map objects or jobs choose their tasks anew after loading
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SavedTask {
+    pub action: Action,
+    pub destination: UVec2,
+    pub lesson: Option<LessonSlot>,
+    pub started: u64,
+    pub path: Vec<UVec2>,
+}
+
+/// Components missing from the save are added by their plugins as for a new cat
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedCat {
+    pub name: String,
+    pub translation: Vec3,
+    pub sheet: CatSheet,
+    pub needs: Option<Needs>,
+    pub inventory: Option<Inventory>,
+    pub experience: Option<Experience>,
+    pub mana: Option<Mana>,
+    pub health: Option<Health>,
+    pub enrollment: Option<Enrollment>,
+    pub teacher: Option<Teacher>,
+    pub task: Option<SavedTask>,
+}
+
+/// Class with its cats as indices into `SavedGame::cats`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SavedClass {
+    pub school: MagicSchool,
+    pub teacher: usize,
+    pub students: Vec<usize>,
+    pub classroom: usize,
+    pub slot: LessonSlot,
+}
+
+/// Stockpile or workstation with the items kept there
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedSite<T> {
+    pub site: T,
+    pub inventory: Inventory,
+}
+
+/// Job without its cat, it is assigned anew after loading
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SavedJob {
+    pub id: JobId,
+    pub kind: DesignationKind,
+    pub layer: usize,
+    pub cell: UVec2,
+    pub progress: f32,
+}
+
+/// Left out on purpose:
+/// - hauls and their jobs, workstations ask for their ingredients again
+/// - summoned creatures and running encounters, the cats keep their health
+/// - the layer the player looks at, loaded games show the ground layer
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedGame {
+    pub version: u32,
+    pub map: GameMapFloors,
+    pub cats: Vec<SavedCat>,
+    pub calendar: Calendar,
+    /// Simulation tick, see `SimulationClock::tick`
+    pub tick: u64,
+    pub treasury: Treasury,
+    pub ledger: Ledger,
+    /// Food, herbs and crystals of the academy are kept in the stockpiles
+    pub stockpiles: Vec<SavedSite<Stockpile>>,
+    pub workstations: Vec<SavedSite<Workstation>>,
+    pub jobs: Vec<SavedJob>,
+    pub alumni: Vec<AlumniRecord>,
+    pub classrooms: Vec<Classroom>,
+    pub timetable: Vec<SavedClass>,
+    pub rng: SavedRng,
+    /// The game starts over from it on restart, see `GameSeed`
+    pub seed: u64,
+    /// Settings the game was started with, none without the main menu
+    pub new_game: Option<NewGameSettings>,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    /// Not a RON document
+    Syntax(RonTreeError),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    /// Save of a newer build, or not a save at all
+    Version(u32),
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {e}"),
+            Self::Syntax(e) => write!(f, "syntax error: {e}"),
+            Self::Parse(e) => write!(f, "parse error: {e}"),
+            Self::Serialize(e) => write!(f, "serialize error: {e}"),
+            Self::Version(version) => write!(f, "unsupported save version {version}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<RonTreeError> for SaveError {
+    fn from(value: RonTreeError) -> Self {
+        Self::Syntax(value)
+    }
+}
+
+impl From<ron::error::SpannedError> for SaveError {
+    fn from(value: ron::error::SpannedError) -> Self {
+        Self::Parse(value)
+    }
+}
+
+impl From<ron::Error> for SaveError {
+    fn from(value: ron::Error) -> Self {
+        Self::Serialize(value)
+    }
+}
+
+/// `version` of the save, 0 if there is none
+fn save_version(save: &RonTree) -> u32 {
+    save.field("version")
+        .and_then(RonTree::as_u32)
+        .unwrap_or_default()
+}
+
+/// Run the migrations from the version of the save up to the last one
+fn migrate(save: &mut RonTree, migrations: &[fn(&mut RonTree)]) {
+    let version = save_version(save);
+    let start = (version as usize).saturating_sub(1);
+    for (migration, version) in migrations.iter().skip(start).zip(version + 1..) {
+        migration(save);
+        save.set_field("version", RonTree::Literal(version.to_string()));
+    }
+}
+
+impl SavedGame {
+    /// Older saves are migrated to the current version
+    pub fn from_ron(text: &str) -> Result<Self, SaveError> {
+        let mut tree = RonTree::parse(text)?;
+        let version = save_version(&tree);
+        if !(1..=SAVE_VERSION).contains(&version) {
+            return Err(SaveError::Version(version));
+        }
+        // Errors point into the file when there is nothing to migrate
+        if version == SAVE_VERSION {
+            return Ok(ron::from_str(text)?);
+        }
+        migrate(&mut tree, &MIGRATIONS);
+        Ok(ron::from_str(&tree.to_string())?)
+    }
+
+    pub fn to_ron(&self) -> Result<String, SaveError> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SaveError> {
+        Self::from_ron(&std::fs::read_to_string(path)?)
+    }
+
+    /// Written to a temporary file which then replaces the save,
+    /// so a crash halfway through leaves the old save intact
+    pub fn save(&self, path: &Path) -> Result<(), SaveError> {
+        let tmp = path.with_extension("tmp");
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(self.to_ron()?.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// File the player saves the game to
+#[derive(Resource, Debug, Clone)]
+pub struct SavePath(pub PathBuf);
+
+/// Game being loaded. Present from leaving the old game until the first frame of the loaded one
+#[derive(Resource, Debug, Clone)]
+pub struct PendingLoad(pub SavedGame);
+
+/// Observable event to write the game to the file
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct SaveGame(pub PathBuf);
+
+/// Observable event to replace the game with the one in the file
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct LoadGame(pub PathBuf);
+
+/// Sent once the game is written to the file
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct GameSaved(pub PathBuf);
+
+type CatToSave<'a> = (
+    Entity,
+    Option<&'a Name>,
+    &'a Transform,
+    &'a CatSheet,
+    Option<&'a Needs>,
+    Option<&'a Inventory>,
+    Option<&'a Experience>,
+    Option<&'a Mana>,
+    Option<&'a Health>,
+    Option<&'a Enrollment>,
+    Option<&'a Teacher>,
+    Option<&'a Task>,
+    Option<&'a WalkPath>,
+);
+
+/// Sites sorted by entity, so saving twice gives the same file
+fn saved_sites<T: Component + Clone>(sites: &Query<(Entity, &T, &Inventory)>) -> Vec<SavedSite<T>> {
+    let mut sites: Vec<_> = sites.iter().collect();
+    sites.sort_by_key(|(entity, ..)| *entity);
+    sites
+        .into_iter()
+        .map(|(_, site, inventory)| SavedSite {
+            site: site.clone(),
+            inventory: inventory.clone(),
+        })
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn save_game(
+    ev: Trigger<SaveGame>,
+    cats: Query<CatToSave>,
+    stockpiles: Query<(Entity, &Stockpile, &Inventory)>,
+    workstations: Query<(Entity, &Workstation, &Inventory)>,
+    map_data: Option<Res<GameMapData>>,
+    calendar: Res<Calendar>,
+    clock: Res<SimulationClock>,
+    treasury: Res<Treasury>,
+    ledger: Res<Ledger>,
+    jobs: Option<Res<JobQueue>>,
+    alumni: Option<Res<Alumni>>,
+    classrooms: Option<Res<Classrooms>>,
+    timetable: Option<Res<Timetable>>,
+    rng: Res<GameRng>,
+    seed: Res<GameSeed>,
+    new_game: Option<Res<NewGameSettings>>,
+    mut saved: EventWriter<GameSaved>,
+) {
+    let mut cats: Vec<_> = cats.iter().collect();
+    cats.sort_by_key(|(cat, ..)| *cat);
+    let entities: Vec<Entity> = cats.iter().map(|(cat, ..)| *cat).collect();
+    let index = |cat: &Entity| entities.iter().position(|entity| entity == cat);
+    // Cats who left are left out of their classes
+    let timetable = timetable.map_or(vec![], |timetable| {
+        timetable
+            .classes
+            .iter()
+            .filter_map(|class| {
+                Some(SavedClass {
+                    school: class.school,
+                    teacher: index(&class.teacher)?,
+                    students: class.students.iter().filter_map(index).collect(),
+                    classroom: class.classroom,
+                    slot: class.slot,
+                })
+            })
+            .collect()
+    });
+    let cats = cats
+        .into_iter()
+        .map(
+            |(
+                _,
+                name,
+                tr,
+                sheet,
+                needs,
+                inventory,
+                xp,
+                mana,
+                health,
+                enrollment,
+                teacher,
+                task,
+                path,
+            )| {
+                SavedCat {
+                    name: name.map_or(String::new(), |name| name.to_string()),
+                    translation: tr.translation,
+                    sheet: sheet.clone(),
+                    needs: needs.cloned(),
+                    inventory: inventory.cloned(),
+                    experience: xp.cloned(),
+                    mana: mana.copied(),
+                    health: health.copied(),
+                    enrollment: enrollment.cloned(),
+                    teacher: teacher.cloned(),
+                    task: task
+                        .filter(|task| task.target.is_none() && task.job.is_none())
+                        .map(|task| SavedTask {
+                            action: task.action,
+                            destination: task.destination,
+                            lesson: task.lesson,
+                            started: task.started,
+                            path: path.map_or(vec![], |path| path.0.clone()),
+                        }),
+                }
+            },
+        )
+        .collect();
+    let save = SavedGame {
+        version: SAVE_VERSION,
+        map: map_data.map_or(vec![], |data| data.map().floors()),
+        cats,
+        calendar: *calendar,
+        tick: clock.tick,
+        treasury: *treasury,
+        ledger: ledger.clone(),
+        stockpiles: saved_sites(&stockpiles),
+        workstations: saved_sites(&workstations),
+        jobs: jobs.map_or(vec![], |jobs| {
+            jobs.jobs
+                .iter()
+                .filter(|job| job.kind != DesignationKind::Haul)
+                .map(|job| SavedJob {
+                    id: job.id,
+                    kind: job.kind,
+                    layer: job.layer,
+                    cell: job.cell,
+                    progress: job.progress,
+                })
+                .collect()
+        }),
+        alumni: alumni.map_or(vec![], |alumni| alumni.0.clone()),
+        classrooms: classrooms.map_or(vec![], |classrooms| classrooms.0.clone()),
+        timetable,
+        rng: SavedRng::new(&rng.0),
+        seed: seed.0,
+        new_game: new_game.map(|settings| settings.clone()),
+    };
+    let path = &ev.event().0;
+    match save.save(path) {
+        Ok(()) => {
+            info!("Game saved to {path:?}");
+            saved.write(GameSaved(path.clone()));
+        }
+        Err(e) => warn!("Failed to save the game to {path:?}: {e}"),
+    }
+}
+
+/// The old game is cleaned up in `Uninit`, the saved one is restored in `Init`
+fn load_game(
+    ev: Trigger<LoadGame>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut after: ResMut<AfterUninit>,
+    mut cmds: Commands,
+) {
+    let path = &ev.event().0;
+    let save = match SavedGame::load(path) {
+        Ok(save) => save,
+        Err(e) => {
+            warn!("Failed to load the game from {path:?}: {e}");
+            return;
+        }
+    };
+    cmds.insert_resource(PendingLoad(save));
+    *after = AfterUninit(GameState::Init);
+    next_state.set(GameState::Uninit);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn restore_game(
+    load: Res<PendingLoad>,
+    mut calendar: ResMut<Calendar>,
+    mut clock: ResMut<SimulationClock>,
+    mut treasury: ResMut<Treasury>,
+    mut ledger: ResMut<Ledger>,
+    jobs: Option<ResMut<JobQueue>>,
+    alumni: Option<ResMut<Alumni>>,
+    classrooms: Option<ResMut<Classrooms>>,
+    timetable: Option<ResMut<Timetable>>,
+    asset_server: Option<Res<AssetServer>>,
+    mut meshes: Option<ResMut<Assets<Mesh>>>,
+    mut materials: Option<ResMut<Assets<StandardMaterial>>>,
+    mut cmds: Commands,
+) {
+    let save = &load.0;
+    *calendar = save.calendar;
+    clock.tick = save.tick;
+    *treasury = save.treasury;
+    *ledger = save.ledger.clone();
+    cmds.insert_resource(GameRng(save.rng.restore()));
+    cmds.insert_resource(GameSeed(save.seed));
+    if let Some(new_game) = &save.new_game {
+        cmds.insert_resource(new_game.clone());
+    }
+    if let Some(mut jobs) = jobs {
+        *jobs = JobQueue::from_jobs(
+            save.jobs
+                .iter()
+                .map(|job| Job {
+                    id: job.id,
+                    kind: job.kind,
+                    layer: job.layer,
+                    cell: job.cell,
+                    state: JobState::Pending,
+                    progress: job.progress,
+                    retry_at: 0,
+                })
+                .collect(),
+        );
+    }
+    for stockpile in &save.stockpiles {
+        spawn_stockpile(
+            &mut cmds,
+            stockpile.site,
+            stockpile.inventory.clone(),
+            meshes.as_deref_mut(),
+            materials.as_deref_mut(),
+        );
+    }
+    for station in &save.workstations {
+        spawn_workstation(
+            &mut cmds,
+            station.site.clone(),
+            station.inventory.clone(),
+            meshes.as_deref_mut(),
+            materials.as_deref_mut(),
+        );
+    }
+
+    if let Some(mut alumni) = alumni {
+        alumni.0 = save.alumni.clone();
+    }
+    if let Some(mut classrooms) = classrooms {
+        classrooms.0 = save.classrooms.clone();
+    }
+
+    let mut cats = vec![];
+    for cat in &save.cats {
+        let mut entity = cmds.spawn((
+            GameObject,
+            Name::new(cat.name.clone()),
+            Selectable,
+            Transform::from_translation(cat.translation),
+            cat.sheet.clone(),
+        ));
+        if let Some(needs) = &cat.needs {
+            entity.insert(needs.clone());
+        }
+        if let Some(inventory) = &cat.inventory {
+            entity.insert(inventory.clone());
+        }
+        if let Some(experience) = &cat.experience {
+            entity.insert(experience.clone());
+        }
+        if let Some(mana) = cat.mana {
+            entity.insert(mana);
+        }
+        if let Some(health) = cat.health {
+            entity.insert(health);
+        }
+        if let Some(enrollment) = &cat.enrollment {
+            entity.insert(enrollment.clone());
+        }
+        if let Some(teacher) = &cat.teacher {
+            entity.insert(teacher.clone());
+        }
+        // Interactions begin again once the cat arrives
+        if let Some(task) = &cat.task {
+            entity.insert((
+                Task {
+                    action: task.action,
+                    target: None,
+                    destination: task.destination,
+                    lesson: task.lesson,
+                    job: None,
+                    started: task.started,
+                    arrived: false,
+                },
+                WalkPath(task.path.clone()),
+            ));
+        }
+        // Headless tests have no assets
+        if let Some(asset_server) = &asset_server {
+            entity.insert(SceneRoot(
+                asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/cat3.glb")),
+            ));
+        }
+        cats.push(entity.id());
+    }
+    if let Some(mut timetable) = timetable {
+        timetable.classes = save
+            .timetable
+            .iter()
+            .filter_map(|class| {
+                Some(Class {
+                    school: class.school,
+                    teacher: *cats.get(class.teacher)?,
+                    students: class
+                        .students
+                        .iter()
+                        .filter_map(|student| cats.get(*student).copied())
+                        .collect(),
+                    classroom: class.classroom,
+                    slot: class.slot,
+                })
+            })
+            .collect();
+    }
+}
+
+fn finish_loading(mut cmds: Commands) {
+    cmds.remove_resource::<PendingLoad>();
+}
+
+fn player_cmd_save_load(
+    mut evs: EventReader<PlayerCommand>,
+    path: Res<SavePath>,
+    mut cmds: Commands,
+) {
+    for ev in evs.read() {
+        match ev {
+            PlayerCommand::SaveGame => cmds.trigger(SaveGame(path.0.clone())),
+            PlayerCommand::LoadGame => cmds.trigger(LoadGame(path.0.clone())),
+            _ => {}
+        }
+    }
+}
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SavePath(self.path.clone()));
+        app.add_event::<GameSaved>();
+        app.add_observer(save_game);
+        app.add_observer(load_game);
+        app.add_systems(
+            Update,
+            (
+                restore_game.run_if(in_state(GameState::Init)),
+                finish_loading.run_if(in_state(GameState::Game)),
+            )
+                .run_if(resource_exists::<PendingLoad>),
+        );
+        app.add_systems(
+            PlayerInputPostUpdate,
+            player_cmd_save_load.run_if(in_state(GameState::Game)),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_save_plugin.rs"]
+mod test_save_plugin;
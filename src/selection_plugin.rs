@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+
+use crate::{
+    game_state_plugin::GameState, player_control_plugin::PlayerCommand,
+    player_input_stage::PlayerInputPostUpdate,
+};
+
+pub struct SelectionPlugin;
+
+/// Entity which the player can select, e.g. a cat
+#[derive(Component)]
+pub struct Selectable;
+
+/// Entity currently selected by the player. At most one entity is selected at a time
+#[derive(Component)]
+pub struct Selected;
+
+fn player_cmd_select_next(
+    mut evs: EventReader<PlayerCommand>,
+    selectables: Query<(Entity, Has<Selected>), With<Selectable>>,
+    mut cmds: Commands,
+) {
+    let steps = evs
+        .read()
+        .filter(|x| matches!(x, PlayerCommand::SelectNext))
+        .count();
+    // Sort to have stable order regardless of query iteration order
+    let mut candidates: Vec<_> = selectables.iter().collect();
+    if steps == 0 || candidates.is_empty() {
+        return;
+    }
+    candidates.sort_by_key(|(entity, _)| *entity);
+
+    let next_idx = match candidates.iter().position(|(_, selected)| *selected) {
+        Some(idx) => (idx + steps) % candidates.len(),
+        None => (steps - 1) % candidates.len(),
+    };
+    for (entity, selected) in candidates.iter() {
+        if *selected {
+            cmds.entity(*entity).remove::<Selected>();
+        }
+    }
+    cmds.entity(candidates[next_idx].0).insert(Selected);
+}
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PlayerInputPostUpdate,
+            player_cmd_select_next.run_if(in_state(GameState::Game)),
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Loads user settings on startup and writes them back every time they change.
+pub struct SettingsPlugin {
+    pub path: PathBuf,
+}
+
+impl Default for SettingsPlugin {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("settings.ron"),
+        }
+    }
+}
+
+/// User preferences that persist between runs.
+/// Missing fields are filled with defaults, so older settings files keep working
+#[derive(Resource, Reflect, Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct Settings {
+    pub mouse: MouseSettings,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct MouseSettings {
+    /// Radians of camera rotation per unit of raw mouse motion
+    pub sensitivity: f32,
+    /// Moving the mouse up makes camera look down
+    pub invert_y: bool,
+}
+
+impl Default for MouseSettings {
+    fn default() -> Self {
+        Self {
+            sensitivity: 0.002,
+            invert_y: false,
+        }
+    }
+}
+
+impl MouseSettings {
+    /// Converts raw mouse motion into (yaw, pitch) change.
+    /// Motion is not scaled by frame time: the same physical movement gives
+    /// the same rotation regardless of how many frames it was split across
+    pub fn look_delta(&self, motion: Vec2) -> Vec2 {
+        let pitch_sign = if self.invert_y { 1.0 } else { -1.0 };
+        Vec2::new(
+            -motion.x * self.sensitivity,
+            pitch_sign * motion.y * self.sensitivity,
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+}
+
+impl std::fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {e}"),
+            Self::Parse(e) => write!(f, "parse error: {e}"),
+            Self::Serialize(e) => write!(f, "serialize error: {e}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for SettingsError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<ron::error::SpannedError> for SettingsError {
+    fn from(value: ron::error::SpannedError) -> Self {
+        Self::Parse(value)
+    }
+}
+
+impl From<ron::Error> for SettingsError {
+    fn from(value: ron::Error) -> Self {
+        Self::Serialize(value)
+    }
+}
+
+impl Settings {
+    pub fn from_ron(text: &str) -> Result<Self, SettingsError> {
+        Ok(ron::from_str(text)?)
+    }
+
+    pub fn to_ron(&self) -> Result<String, SettingsError> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+
+    /// Load settings from the file. Missing file is not an error: defaults are used
+    pub fn load(path: &Path) -> Result<Self, SettingsError> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::from_ron(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SettingsError> {
+        Ok(std::fs::write(path, self.to_ron()?)?)
+    }
+}
+
+#[derive(Resource)]
+struct SettingsPath(PathBuf);
+
+fn save_settings(settings: Res<Settings>, path: Res<SettingsPath>) {
+    if let Err(e) = settings.save(&path.0) {
+        warn!("Failed to save settings to {:?}: {e}", path.0);
+    }
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = Settings::load(&self.path).unwrap_or_else(|e| {
+            warn!("Failed to load settings from {:?}: {e}", self.path);
+            Settings::default()
+        });
+        app.register_type::<Settings>();
+        app.insert_resource(settings);
+        app.insert_resource(SettingsPath(self.path.clone()));
+        app.add_systems(
+            Last,
+            save_settings.run_if(resource_changed::<Settings>.and(not(resource_added::<Settings>))),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_settings_plugin.rs"]
+mod test_settings_plugin;
@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+
+use crate::{
+    game_state_plugin::{GameResourceAppExt, GameState},
+    player_control_plugin::PlayerCommand,
+    player_input_stage::PlayerInputPostUpdate,
+};
+
+/// Runs the game world in `FixedUpdate` with pause, speed control and single stepping.
+/// Camera and UI use `Time<Real>` and keep running regardless of the simulation clock
+pub struct SimulationClockPlugin;
+
+pub const SIMULATION_TICKS_PER_SECOND: f64 = 10.0;
+
+/// How fast the simulation runs compared to the real time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum SimulationSpeed {
+    #[default]
+    Normal,
+    Fast,
+    Fastest,
+}
+
+impl SimulationSpeed {
+    const ALL: [SimulationSpeed; 3] = [Self::Normal, Self::Fast, Self::Fastest];
+
+    pub fn multiplier(self) -> f32 {
+        match self {
+            Self::Normal => 1.0,
+            Self::Fast => 2.0,
+            Self::Fastest => 5.0,
+        }
+    }
+
+    /// Speed `steps` positions faster (or slower if negative), saturating at the ends
+    pub fn shifted(self, steps: isize) -> Self {
+        let idx = Self::ALL.iter().position(|x| *x == self).unwrap_or(0);
+        let idx = idx.saturating_add_signed(steps).min(Self::ALL.len() - 1);
+        Self::ALL[idx]
+    }
+}
+
+/// Every game starts unpaused at the normal speed
+#[derive(Resource, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct SimulationClock {
+    pub paused: bool,
+    pub speed: SimulationSpeed,
+    /// Number of simulation ticks since the start of the game
+    pub tick: u64,
+    /// Ticks requested to run while paused
+    pending_steps: u32,
+    /// Whether simulation systems run during the current fixed step
+    ticking: bool,
+}
+
+impl SimulationClock {
+    /// Pause the simulation, if needed, and run exactly one tick
+    pub fn step(&mut self) {
+        self.paused = true;
+        self.pending_steps += 1;
+    }
+}
+
+/// Systems which advance the game world. They run in `FixedUpdate`
+/// once per simulation tick and don't run while the simulation is paused
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct SimulationSet;
+
+/// There is no clock outside of the game
+pub fn simulation_is_ticking(clock: Option<Res<SimulationClock>>) -> bool {
+    clock.is_some_and(|clock| clock.ticking)
+}
+
+fn start_simulation_tick(mut clock: ResMut<SimulationClock>) {
+    clock.ticking = if !clock.paused {
+        true
+    } else if clock.pending_steps > 0 {
+        clock.pending_steps -= 1;
+        true
+    } else {
+        false
+    };
+    if clock.ticking {
+        clock.tick += 1;
+    }
+}
+
+fn stop_simulation_tick(mut clock: ResMut<SimulationClock>) {
+    clock.ticking = false;
+}
+
+fn apply_simulation_speed(clock: Res<SimulationClock>, mut time: ResMut<Time<Virtual>>) {
+    let speed = clock.speed.multiplier();
+    if time.relative_speed() != speed {
+        time.set_relative_speed(speed);
+    }
+}
+
+fn player_cmd_simulation_clock(
+    mut evs: EventReader<PlayerCommand>,
+    mut clock: ResMut<SimulationClock>,
+) {
+    for ev in evs.read() {
+        match ev {
+            PlayerCommand::TogglePause => clock.paused = !clock.paused,
+            PlayerCommand::StepSimulation => clock.step(),
+            PlayerCommand::ChangeSimulationSpeed(steps) => {
+                clock.speed = clock.speed.shifted(*steps);
+            }
+            _ => continue,
+        }
+    }
+}
+
+impl Plugin for SimulationClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SimulationClock>();
+        app.init_game_resource::<SimulationClock>();
+        app.insert_resource(Time::<Fixed>::from_hz(SIMULATION_TICKS_PER_SECOND));
+        app.configure_sets(
+            FixedUpdate,
+            SimulationSet
+                .run_if(in_state(GameState::Game))
+                .run_if(simulation_is_ticking),
+        );
+        app.add_systems(
+            FixedFirst,
+            start_simulation_tick.run_if(in_state(GameState::Game)),
+        );
+        app.add_systems(
+            FixedLast,
+            stop_simulation_tick.run_if(in_state(GameState::Game)),
+        );
+        app.add_systems(
+            PlayerInputPostUpdate,
+            (player_cmd_simulation_clock, apply_simulation_speed)
+                .chain()
+                .run_if(in_state(GameState::Game)),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_simulation_clock_plugin.rs"]
+mod test_simulation_clock_plugin;
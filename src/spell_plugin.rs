@@ -0,0 +1,383 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cat::{CatSheet, MagicSchool, PrimaryAttribute},
+    combat_plugin::{Creature, CreatureKind, EFFECT_TURNS, Fighting, Health, StatusEffect},
+    experience_plugin::{Experience, Skill},
+    game_map_plugin::{GameMapCellFloor, GameMapData, LayerObject, SetCellFloorEvent},
+    game_state_plugin::GameObject,
+    needs_plugin::{NEED_MAX, Need, Needs},
+    pathfinding::cell_center,
+    rng_plugin::GameRng,
+    roll::{ContestOutcome, Roll},
+    simulation_clock_plugin::{SimulationClock, SimulationSet},
+};
+
+/// Spells of the five schools of magic, cast with mana against their difficulty
+pub struct SpellPlugin;
+
+/// Spells known in the academy
+const SPELLS: &str = include_str!("../assets/spells.ron");
+/// Every point of magic gives this much mana
+pub const MANA_PER_MAGIC: i32 = 2;
+/// Cats restore a point of mana per this many ticks
+pub const MANA_REGEN_TICKS: u64 = 20;
+
+/// Branch of a school of magic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum Discipline {
+    Fire,
+    Water,
+    Wind,
+    Ground,
+    Healing,
+    Necromancy,
+    Transmutation,
+    PotionMaking,
+    Enchantment,
+    Summoning,
+    Illusion,
+    Prophecy,
+    DivineShielding,
+}
+
+impl Discipline {
+    pub fn school(self) -> MagicSchool {
+        match self {
+            Self::Fire | Self::Water | Self::Wind | Self::Ground => MagicSchool::Elemental,
+            Self::Healing | Self::Necromancy | Self::Transmutation => MagicSchool::Medical,
+            Self::PotionMaking | Self::Enchantment => MagicSchool::Alchemy,
+            Self::Summoning | Self::Illusion => MagicSchool::Evocation,
+            Self::Prophecy | Self::DivineShielding => MagicSchool::Divination,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SpellEffect {
+    /// Takes health
+    Damage(i32),
+    /// Restores health up to the maximum
+    Heal(i32),
+    /// Adds health, even above the maximum
+    Ward(i32),
+    /// Status effect on a fighter, mental ones are resisted by willpower
+    Inflict(StatusEffect),
+    RestoreNeed(Need, f32),
+    /// Creature appears at the cell
+    Summon(CreatureKind),
+    /// Floor of the cell changes
+    SetFloor(GameMapCellFloor),
+}
+
+impl SpellEffect {
+    /// Effects aimed at a map cell rather than at a cat or a creature
+    pub fn targets_cell(self) -> bool {
+        matches!(self, Self::Summon(_) | Self::SetFloor(_))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpellDef {
+    pub name: String,
+    pub school: MagicSchool,
+    pub discipline: Discipline,
+    /// Mana spent on every attempt
+    pub cost: i32,
+    /// Rolled against the school aptitude of the caster
+    pub difficulty: Roll,
+    pub effect: SpellEffect,
+}
+
+#[derive(Debug)]
+pub enum SpellError {
+    Parse(ron::error::SpannedError),
+    /// The discipline belongs to another school
+    WrongSchool(String),
+    Duplicate(String),
+}
+
+impl std::fmt::Display for SpellError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "parse error: {e}"),
+            Self::WrongSchool(name) => write!(f, "discipline of {name} is not of its school"),
+            Self::Duplicate(name) => write!(f, "{name} is defined twice"),
+        }
+    }
+}
+
+impl From<ron::error::SpannedError> for SpellError {
+    fn from(value: ron::error::SpannedError) -> Self {
+        Self::Parse(value)
+    }
+}
+
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct SpellBook(pub Vec<SpellDef>);
+
+impl SpellBook {
+    pub fn from_ron(text: &str) -> Result<Self, SpellError> {
+        let spells: Vec<SpellDef> = ron::from_str(text)?;
+        for (idx, spell) in spells.iter().enumerate() {
+            if spell.discipline.school() != spell.school {
+                return Err(SpellError::WrongSchool(spell.name.clone()));
+            }
+            if spells[..idx].iter().any(|other| other.name == spell.name) {
+                return Err(SpellError::Duplicate(spell.name.clone()));
+            }
+        }
+        Ok(Self(spells))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SpellDef> {
+        self.0.iter().find(|spell| spell.name == name)
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Mana {
+    pub current: i32,
+    pub max: i32,
+}
+
+pub fn max_mana(sheet: &CatSheet) -> i32 {
+    let magic = PrimaryAttribute::Magic;
+    ((sheet.primary.get(magic) + sheet.traits.modifier(magic)) * MANA_PER_MAGIC).max(0)
+}
+
+/// Roll of the caster against the difficulty of the spell.
+/// Aptitude and the level of the school add up, trait of the base attribute is the bonus
+pub fn casting_roll(
+    sheet: &CatSheet,
+    experience: Option<&Experience>,
+    school: MagicSchool,
+) -> Roll {
+    let level = experience.map_or(0, |xp| xp.get(Skill::School(school)).level as i32);
+    Roll::attribute(
+        sheet.school_aptitude(school) + level,
+        sheet.traits.modifier(school.base_attribute()),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpellTarget {
+    Entity(Entity),
+    Cell { layer: usize, cell: UVec2 },
+}
+
+/// Trigger to cast the spell of the spell book
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct CastSpell {
+    pub caster: Entity,
+    pub spell: String,
+    pub target: SpellTarget,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastOutcome {
+    Cast,
+    /// Lost the roll against the difficulty, mana is spent anyway
+    Fizzled,
+    /// The target shrugged off a mental effect
+    Resisted,
+    NoMana,
+    /// Not in the spell book, or the caster is not a cat
+    Unknown,
+    /// The effect can't be applied to the target
+    BadTarget,
+}
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct SpellCast {
+    pub caster: Entity,
+    pub spell: String,
+    pub target: SpellTarget,
+    pub outcome: CastOutcome,
+}
+
+fn add_mana(ev: Trigger<OnAdd, CatSheet>, sheets: Query<&CatSheet>, mut cmds: Commands) {
+    let Ok(sheet) = sheets.get(ev.target()) else {
+        return;
+    };
+    let max = max_mana(sheet);
+    cmds.entity(ev.target())
+        .insert_if_new(Mana { current: max, max });
+}
+
+type SpellCaster<'a> = (&'a CatSheet, &'a mut Mana, Option<&'a Experience>);
+
+type SpellSubject<'a> = (
+    Option<&'a mut Health>,
+    Option<&'a mut Needs>,
+    Option<&'a mut Fighting>,
+    Option<&'a CatSheet>,
+    Option<&'a Creature>,
+);
+
+/// Whether the target has what the effect changes
+fn is_valid_target(
+    effect: SpellEffect,
+    target: SpellTarget,
+    subjects: &Query<SpellSubject>,
+    map_data: Option<&GameMapData>,
+) -> bool {
+    match target {
+        SpellTarget::Entity(entity) => {
+            let Ok((health, needs, fighting, ..)) = subjects.get(entity) else {
+                return false;
+            };
+            match effect {
+                SpellEffect::Damage(_) | SpellEffect::Heal(_) | SpellEffect::Ward(_) => {
+                    health.is_some()
+                }
+                SpellEffect::Inflict(_) => fighting.is_some(),
+                SpellEffect::RestoreNeed(..) => needs.is_some(),
+                SpellEffect::Summon(_) | SpellEffect::SetFloor(_) => false,
+            }
+        }
+        SpellTarget::Cell { layer, cell } => {
+            effect.targets_cell()
+                && map_data.is_none_or(|data| {
+                    let map = data.map();
+                    layer < map.layers
+                        && (cell.x as usize) < map.width
+                        && (cell.y as usize) < map.height
+                })
+        }
+    }
+}
+
+/// Spend mana and roll against the difficulty, apply the effect on success
+#[allow(clippy::too_many_arguments)]
+fn cast_spell(
+    ev: Trigger<CastSpell>,
+    book: Res<SpellBook>,
+    mut casters: Query<SpellCaster>,
+    mut subjects: Query<SpellSubject>,
+    map_data: Option<Res<GameMapData>>,
+    mut rng: ResMut<GameRng>,
+    mut casts: EventWriter<SpellCast>,
+    mut cmds: Commands,
+) {
+    let mut report = |outcome| {
+        casts.write(SpellCast {
+            caster: ev.caster,
+            spell: ev.spell.clone(),
+            target: ev.target,
+            outcome,
+        });
+    };
+    let (Some(spell), Ok((sheet, mut mana, experience))) =
+        (book.get(&ev.spell), casters.get_mut(ev.caster))
+    else {
+        report(CastOutcome::Unknown);
+        return;
+    };
+    if !is_valid_target(spell.effect, ev.target, &subjects, map_data.as_deref()) {
+        report(CastOutcome::BadTarget);
+        return;
+    }
+    if mana.current < spell.cost {
+        report(CastOutcome::NoMana);
+        return;
+    }
+    mana.current -= spell.cost;
+    let casting = casting_roll(sheet, experience, spell.school);
+    let power = casting.roll(&mut rng.0);
+    let difficulty = spell.difficulty.roll(&mut rng.0);
+    if ContestOutcome::of(power, difficulty) != ContestOutcome::Win {
+        report(CastOutcome::Fizzled);
+        return;
+    }
+
+    let mut outcome = CastOutcome::Cast;
+    match (spell.effect, ev.target) {
+        // Creatures on the ground layer attack the cats nearby
+        (SpellEffect::Summon(kind), SpellTarget::Cell { layer, cell }) => {
+            cmds.spawn((
+                Name::new(format!("{kind:?}")),
+                GameObject,
+                LayerObject(layer),
+                Creature(kind),
+                Transform::from_translation(cell_center(cell, layer as f32)),
+            ));
+        }
+        (SpellEffect::SetFloor(floor), SpellTarget::Cell { layer, cell }) => {
+            cmds.trigger(SetCellFloorEvent { layer, cell, floor });
+        }
+        (effect, SpellTarget::Entity(target)) => {
+            let (health, needs, fighting, target_sheet, creature) =
+                subjects.get_mut(target).unwrap();
+            match effect {
+                SpellEffect::Damage(amount) => health.unwrap().current -= amount,
+                SpellEffect::Heal(amount) => {
+                    let mut health = health.unwrap();
+                    health.current = (health.current + amount).min(health.max.max(health.current));
+                }
+                SpellEffect::Ward(amount) => health.unwrap().current += amount,
+                SpellEffect::RestoreNeed(need, amount) => {
+                    let mut needs = needs.unwrap();
+                    let value = needs.get_mut(need);
+                    *value = (*value + amount).min(NEED_MAX);
+                }
+                SpellEffect::Inflict(effect) => {
+                    let resisted = effect.is_mental() && {
+                        let willpower = match (target_sheet, creature) {
+                            (Some(sheet), _) => sheet.secondary.willpower,
+                            (None, Some(creature)) => creature.0.attributes().willpower,
+                            (None, None) => 0,
+                        };
+                        let will = Roll::attribute(willpower, 0).roll(&mut rng.0);
+                        ContestOutcome::of(will, power) == ContestOutcome::Win
+                    };
+                    if resisted {
+                        outcome = CastOutcome::Resisted;
+                    } else {
+                        let mut fighting = fighting.unwrap();
+                        fighting.effects.retain(|(e, _)| *e != effect);
+                        fighting.effects.push((effect, EFFECT_TURNS));
+                    }
+                }
+                SpellEffect::Summon(_) | SpellEffect::SetFloor(_) => unreachable!(),
+            }
+        }
+        (_, SpellTarget::Cell { .. }) => unreachable!(),
+    }
+    debug!("{:?} casts {}: {outcome:?}", ev.caster, spell.name);
+    report(outcome);
+}
+
+/// Mana comes back over time. Maximum follows the magic attribute
+fn regenerate_mana(mut cats: Query<(&mut Mana, &CatSheet)>, clock: Res<SimulationClock>) {
+    if !clock.tick.is_multiple_of(MANA_REGEN_TICKS) {
+        return;
+    }
+    for (mut mana, sheet) in &mut cats {
+        mana.max = max_mana(sheet);
+        if mana.current < mana.max {
+            mana.current += 1;
+        }
+    }
+}
+
+impl Plugin for SpellPlugin {
+    fn build(&self, app: &mut App) {
+        let book = SpellBook::from_ron(SPELLS).unwrap_or_else(|e| {
+            error!("Failed to load spells: {e}");
+            SpellBook::default()
+        });
+        app.register_type::<Mana>();
+        app.insert_resource(book);
+        app.add_event::<SpellCast>();
+        app.add_observer(add_mana);
+        app.add_observer(cast_spell);
+        app.add_systems(FixedUpdate, regenerate_mana.in_set(SimulationSet));
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_spell_plugin.rs"]
+mod test_spell_plugin;
@@ -0,0 +1,257 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    calendar_plugin::{Calendar, CalendarSet, SeasonChanged},
+    cat::{CatSheet, MagicSchool, PrimaryAttribute, generate_cat_name},
+    game_state_plugin::{GameObject, GameResourceAppExt, GameState},
+    rng_plugin::GameRng,
+    roll::{ContestOutcome, Roll, contest},
+    save_plugin::PendingLoad,
+    selection_plugin::Selectable,
+    simulation_clock_plugin::SimulationSet,
+};
+
+/// Kittens arrive, study and leave the academy
+pub struct StudentPlugin;
+
+/// Kittens appear at the start of the road
+const ARRIVAL_POINT: Vec3 = Vec3::new(0.0, 0.0, 3.0);
+
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct StudentLifecycleConfig {
+    /// Kittens arriving when the game starts
+    pub initial_arrivals: u32,
+    /// Kittens arriving at the start of every season
+    pub arrivals_per_season: u32,
+    /// "General education" lasts no more than this
+    pub general_education_seasons: u32,
+    pub specific_education_seasons: u32,
+}
+
+impl Default for StudentLifecycleConfig {
+    fn default() -> Self {
+        Self {
+            initial_arrivals: 3,
+            arrivals_per_season: 2,
+            general_education_seasons: 4,
+            specific_education_seasons: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum Education {
+    General,
+    Specific,
+}
+
+/// Enrollment record of a kitten studying in the academy
+#[derive(Component, Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Enrollment {
+    /// Season index (see `Calendar::season_index`) of the arrival
+    pub enrolled_season: u32,
+    pub education: Education,
+    /// Season index when the current education has started
+    pub education_started: u32,
+}
+
+/// Cat teaching its school of magic
+#[derive(Component, Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Teacher {
+    pub school: MagicSchool,
+}
+
+/// What is left of a student after departure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlumniRecord {
+    pub name: String,
+    pub sheet: CatSheet,
+    pub enrollment: Enrollment,
+    pub departed_season: u32,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct Alumni(pub Vec<AlumniRecord>);
+
+/// Observable event to spawn a newly generated kitten
+#[derive(Event)]
+pub struct KittenArrival;
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct StudentGraduated {
+    pub entity: Entity,
+    pub education: Education,
+    /// Kitten stays for specific education, or as a teacher after it
+    pub stays: bool,
+}
+
+/// Observable event to let the student leave the academy
+#[derive(Event)]
+pub struct StudentDeparture(pub Entity);
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct StudentDeparted(pub AlumniRecord);
+
+fn spawn_arriving_kitten(
+    _: Trigger<KittenArrival>,
+    mut rng: ResMut<GameRng>,
+    calendar: Res<Calendar>,
+    asset_server: Option<Res<AssetServer>>,
+    students: Query<(), With<Enrollment>>,
+    mut cmds: Commands,
+) {
+    let sheet = CatSheet::generate(&mut rng.0);
+    let name = generate_cat_name(&mut rng.0);
+    // Line up along the road
+    let offset = students.iter().count() % 8;
+    let season = calendar.season_index();
+    let mut kitten = cmds.spawn((
+        GameObject,
+        Name::new(name),
+        Selectable,
+        sheet,
+        Enrollment {
+            enrolled_season: season,
+            education: Education::General,
+            education_started: season,
+        },
+        Transform::from_translation(ARRIVAL_POINT + Vec3::X * offset as f32),
+    ));
+    // Headless tests have no assets
+    if let Some(asset_server) = asset_server {
+        kitten.insert(SceneRoot(
+            asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/cat3.glb")),
+        ));
+    }
+}
+
+fn initial_arrivals(config: Res<StudentLifecycleConfig>, mut cmds: Commands) {
+    for _ in 0..config.initial_arrivals {
+        cmds.trigger(KittenArrival);
+    }
+}
+
+fn season_arrivals(
+    mut evs: EventReader<SeasonChanged>,
+    config: Res<StudentLifecycleConfig>,
+    mut cmds: Commands,
+) {
+    for _ in evs.read() {
+        for _ in 0..config.arrivals_per_season {
+            cmds.trigger(KittenArrival);
+        }
+    }
+}
+
+/// At the end of the season kittens who finished their education graduate.
+/// After general education smart kittens may stay for specific one,
+/// after specific education charming ones may stay as teachers. The rest leave
+fn graduate_students(
+    mut evs: EventReader<SeasonChanged>,
+    config: Res<StudentLifecycleConfig>,
+    mut rng: ResMut<GameRng>,
+    mut students: Query<(Entity, &CatSheet, &mut Enrollment)>,
+    mut graduated: EventWriter<StudentGraduated>,
+    mut cmds: Commands,
+) {
+    for ev in evs.read() {
+        let season = ev.calendar.season_index();
+        // Consume random numbers in a stable order
+        let mut order: Vec<Entity> = students.iter().map(|(entity, ..)| entity).collect();
+        order.sort();
+        for entity in order {
+            let Ok((entity, sheet, mut enrollment)) = students.get_mut(entity) else {
+                continue;
+            };
+            let studied = season.saturating_sub(enrollment.education_started);
+            let duration = match enrollment.education {
+                Education::General => config.general_education_seasons,
+                Education::Specific => config.specific_education_seasons,
+            };
+            if studied < duration {
+                continue;
+            }
+            let attr = match enrollment.education {
+                Education::General => PrimaryAttribute::Intelligence,
+                Education::Specific => PrimaryAttribute::Charm,
+            };
+            let stays = contest(&sheet.attribute_roll(attr), &Roll::CHAOS, &mut rng.0)
+                == ContestOutcome::Win;
+            graduated.write(StudentGraduated {
+                entity,
+                education: enrollment.education,
+                stays,
+            });
+            match (enrollment.education, stays) {
+                (_, false) => cmds.trigger(StudentDeparture(entity)),
+                (Education::General, true) => {
+                    enrollment.education = Education::Specific;
+                    enrollment.education_started = season;
+                }
+                (Education::Specific, true) => {
+                    cmds.entity(entity).remove::<Enrollment>().insert(Teacher {
+                        school: sheet.best_school(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Despawn the student, keeping the alumni record
+fn depart_student(
+    ev: Trigger<StudentDeparture>,
+    students: Query<(&Name, &CatSheet, &Enrollment)>,
+    calendar: Res<Calendar>,
+    mut alumni: ResMut<Alumni>,
+    mut departed: EventWriter<StudentDeparted>,
+    mut cmds: Commands,
+) {
+    let Ok((name, sheet, enrollment)) = students.get(ev.0) else {
+        return;
+    };
+    let record = AlumniRecord {
+        name: name.to_string(),
+        sheet: sheet.clone(),
+        enrollment: enrollment.clone(),
+        departed_season: calendar.season_index(),
+    };
+    alumni.0.push(record.clone());
+    departed.write(StudentDeparted(record));
+    cmds.entity(ev.0).despawn();
+}
+
+impl Plugin for StudentPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<CatSheet>();
+        app.register_type::<Enrollment>();
+        app.register_type::<Teacher>();
+        app.register_type::<StudentLifecycleConfig>();
+        app.init_resource::<StudentLifecycleConfig>();
+        app.init_game_resource::<Alumni>();
+        app.add_event::<StudentGraduated>();
+        app.add_event::<StudentDeparted>();
+        app.add_observer(spawn_arriving_kitten);
+        app.add_observer(depart_student);
+        // Loaded games already have their kittens
+        app.add_systems(
+            OnEnter(GameState::Game),
+            initial_arrivals.run_if(not(resource_exists::<PendingLoad>)),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (graduate_students, season_arrivals)
+                .chain()
+                .in_set(SimulationSet)
+                .after(CalendarSet),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_student_plugin.rs"]
+mod test_student_plugin;
@@ -0,0 +1,330 @@
+use bevy::prelude::*;
+
+use crate::{
+    calendar_plugin::CalendarPlugin,
+    cat::CatSheet,
+    cat_ai_plugin::CatAiPlugin,
+    designation_plugin::{
+        DesignationKind, DesignationPlugin, DesignationTool, JOB_WORK, JobQueue, Working,
+    },
+    item::{Inventory, ItemKind, ItemQuality},
+    needs_plugin::Needs,
+    player_control_plugin::PlayerCommand,
+    rng_plugin::RngPlugin,
+    roll::Roll,
+    selection_plugin::Selected,
+    test_utils::{sheet, simulation_app, start_game},
+    timetable_plugin::TimetablePlugin,
+};
+
+use super::{
+    AlchemyPlugin, Crafted, OrderCraft, PlaceWorkstation, RECIPES, RecipeBook, RecipeError,
+    StationKind, UseItem, Workstation,
+};
+
+#[test]
+fn builtin_recipes_parse() {
+    let book = RecipeBook::from_ron(RECIPES).unwrap();
+    let potion = book.get("Stamina Potion").unwrap();
+    assert_eq!(potion.station, StationKind::Cauldron);
+    assert_eq!(
+        potion.ingredients,
+        vec![(ItemKind::Herb, 2), (ItemKind::Mushroom, 1)]
+    );
+    assert_eq!(potion.difficulty, Roll::new(1, 10));
+    assert!(
+        book.0
+            .iter()
+            .any(|recipe| recipe.station == StationKind::AlchemyTable)
+    );
+
+    let recipe = r#"(name: "Toy", station: AlchemyTable, ingredients: [], product: EnchantedToy, difficulty: "1d4")"#;
+    assert!(matches!(
+        RecipeBook::from_ron(&format!("[{recipe}, {recipe}]")),
+        Err(RecipeError::Duplicate(_))
+    ));
+    assert!(matches!(
+        RecipeBook::from_ron(r#"[(name: "Toy", product: Rock)]"#),
+        Err(RecipeError::Parse(_))
+    ));
+}
+
+#[derive(Resource, Default)]
+struct CraftLog(Vec<Crafted>);
+
+fn log_crafts(mut evs: EventReader<Crafted>, mut log: ResMut<CraftLog>) {
+    log.0.extend(evs.read().cloned());
+}
+
+fn new_app() -> App {
+    let mut app = simulation_app();
+    app.add_plugins((
+        RngPlugin { seed: Some(1) },
+        CalendarPlugin,
+        TimetablePlugin,
+        CatAiPlugin,
+        DesignationPlugin,
+        AlchemyPlugin,
+    ));
+    app.init_resource::<CraftLog>();
+    app.add_systems(Update, log_crafts);
+    start_game(&mut app);
+    app
+}
+
+/// Cat who never botches a recipe
+fn alchemist() -> CatSheet {
+    let mut sheet = sheet();
+    sheet.primary.intelligence = 40;
+    sheet.secondary.willpower = 20;
+    sheet
+}
+
+fn place(app: &mut App, kind: StationKind, cell: UVec2) -> Option<Entity> {
+    app.world_mut().trigger(PlaceWorkstation {
+        kind,
+        layer: 0,
+        cell,
+    });
+    app.update();
+    let mut stations = app.world_mut().query::<(Entity, &Workstation)>();
+    stations
+        .iter(app.world())
+        .find(|(_, station)| station.cell == cell && station.kind == kind)
+        .map(|(entity, _)| entity)
+}
+
+fn order(app: &mut App, station: Entity, recipe: &str) {
+    app.world_mut().trigger(OrderCraft {
+        station,
+        recipe: recipe.to_string(),
+    });
+    app.update();
+}
+
+fn stock(app: &mut App, station: Entity, items: &[(ItemKind, u32)]) {
+    let mut inventory = app.world_mut().get_mut::<Inventory>(station).unwrap();
+    for (kind, count) in items {
+        inventory.add(*kind, ItemQuality::Common, *count);
+    }
+}
+
+fn count(app: &App, entity: Entity, kind: ItemKind) -> u32 {
+    app.world().get::<Inventory>(entity).unwrap().count(kind)
+}
+
+#[test]
+fn workstations_take_matching_orders() {
+    let mut app = new_app();
+    let cell = UVec2::new(2, 2);
+    let cauldron = place(&mut app, StationKind::Cauldron, cell).unwrap();
+    assert!(place(&mut app, StationKind::AlchemyTable, cell).is_none());
+
+    order(&mut app, cauldron, "Enchanted Toy");
+    order(&mut app, cauldron, "Unheard Of");
+    order(&mut app, cauldron, "Hearty Broth");
+    assert_eq!(
+        app.world().get::<Workstation>(cauldron).unwrap().orders,
+        vec!["Hearty Broth".to_string()]
+    );
+}
+
+#[test]
+fn cats_craft_ordered_recipes() {
+    let mut app = new_app();
+    let cell = UVec2::new(2, 2);
+    let cauldron = place(&mut app, StationKind::Cauldron, cell).unwrap();
+    order(&mut app, cauldron, "Stamina Potion");
+    order(&mut app, cauldron, "Stamina Potion");
+    app.update();
+    assert!(
+        app.world().resource::<JobQueue>().jobs.is_empty(),
+        "no ingredients yet"
+    );
+
+    stock(
+        &mut app,
+        cauldron,
+        &[(ItemKind::Herb, 3), (ItemKind::Mushroom, 2)],
+    );
+    app.update();
+    let station = app.world().get::<Workstation>(cauldron).unwrap();
+    let (_, id) = station.crafting.clone().unwrap();
+    assert_eq!(station.orders.len(), 1);
+    let job = *app.world().resource::<JobQueue>().get(id).unwrap();
+    assert_eq!(job.kind, DesignationKind::Craft);
+    assert_eq!(job.cell, cell);
+    assert_eq!(count(&app, cauldron, ItemKind::Herb), 1);
+    assert_eq!(count(&app, cauldron, ItemKind::Mushroom), 1);
+
+    let cat = app.world_mut().spawn(alchemist()).id();
+    app.update();
+    app.world_mut().entity_mut(cat).insert(Working(id));
+    for _ in 0..JOB_WORK as usize + 2 {
+        app.update();
+    }
+    let log = &app.world().resource::<CraftLog>().0;
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].cat, cat);
+    assert_eq!(log[0].station, cauldron);
+    assert!(log[0].quality.is_some());
+    assert_eq!(count(&app, cauldron, ItemKind::StaminaPotion), 1);
+    let station = app.world().get::<Workstation>(cauldron).unwrap();
+    assert!(station.crafting.is_none(), "second order lacks herbs");
+    assert_eq!(station.orders.len(), 1);
+}
+
+#[test]
+fn cancelled_crafting_refunds_ingredients() {
+    let mut app = new_app();
+    let cell = UVec2::new(1, 1);
+    let cauldron = place(&mut app, StationKind::Cauldron, cell).unwrap();
+    stock(&mut app, cauldron, &[(ItemKind::Catnip, 2)]);
+    order(&mut app, cauldron, "Catnip Tonic");
+    app.update();
+    assert_eq!(count(&app, cauldron, ItemKind::Catnip), 0);
+
+    app.world_mut().resource_mut::<JobQueue>().cancel(0, cell);
+    app.update();
+    assert_eq!(count(&app, cauldron, ItemKind::Catnip), 2);
+    let station = app.world().get::<Workstation>(cauldron).unwrap();
+    assert!(station.crafting.is_none());
+    assert!(station.orders.is_empty());
+}
+
+#[test]
+fn cats_use_items() {
+    let mut app = new_app();
+    let cat = app
+        .world_mut()
+        .spawn((
+            alchemist(),
+            Needs {
+                energy: 10.0,
+                ..default()
+            },
+        ))
+        .id();
+    app.update();
+    let willpower = app
+        .world()
+        .get::<CatSheet>(cat)
+        .unwrap()
+        .secondary
+        .willpower;
+    {
+        let mut inventory = app.world_mut().get_mut::<Inventory>(cat).unwrap();
+        inventory.add(ItemKind::StaminaPotion, ItemQuality::Crude, 1);
+        inventory.add(ItemKind::StaminaPotion, ItemQuality::Fine, 1);
+        inventory.add(ItemKind::WillpowerDraught, ItemQuality::Superb, 1);
+        inventory.add(ItemKind::Herb, ItemQuality::Common, 1);
+    }
+    for kind in [
+        ItemKind::StaminaPotion,
+        ItemKind::WillpowerDraught,
+        ItemKind::Herb,
+        ItemKind::HeartyBroth,
+    ] {
+        app.world_mut().trigger(UseItem { cat, kind });
+    }
+    app.update();
+    let energy = app.world().get::<Needs>(cat).unwrap().energy;
+    assert!(energy >= 10.0 + 60.0 - 1.0, "{energy}");
+    assert_eq!(
+        app.world()
+            .get::<CatSheet>(cat)
+            .unwrap()
+            .secondary
+            .willpower,
+        willpower + 3
+    );
+    let inventory = app.world().get::<Inventory>(cat).unwrap();
+    assert_eq!(inventory.count(ItemKind::StaminaPotion), 1);
+    assert!(
+        inventory
+            .0
+            .iter()
+            .any(|stack| stack.kind == ItemKind::StaminaPotion
+                && stack.quality == ItemQuality::Crude)
+    );
+    assert_eq!(inventory.count(ItemKind::Herb), 1, "herbs are not usable");
+}
+
+#[test]
+fn needy_cats_use_carried_and_stored_items() {
+    let mut app = new_app();
+    let cauldron = place(&mut app, StationKind::Cauldron, UVec2::new(3, 0)).unwrap();
+    stock(&mut app, cauldron, &[(ItemKind::HeartyBroth, 1)]);
+    let cat = app
+        .world_mut()
+        .spawn((
+            alchemist(),
+            Needs {
+                hunger: 5.0,
+                energy: 5.0,
+                ..default()
+            },
+            Transform::default(),
+        ))
+        .id();
+    app.update();
+    app.world_mut().get_mut::<Inventory>(cat).unwrap().add(
+        ItemKind::StaminaPotion,
+        ItemQuality::Common,
+        1,
+    );
+    for _ in 0..200 {
+        app.update();
+    }
+    let needs = app.world().get::<Needs>(cat).unwrap();
+    assert!(needs.hunger > 5.0 + 50.0 - 1.0, "{}", needs.hunger);
+    assert!(needs.energy > 5.0 + 40.0 - 1.0, "{}", needs.energy);
+    assert_eq!(count(&app, cauldron, ItemKind::HeartyBroth), 0);
+    assert!(app.world().get::<Inventory>(cat).unwrap().0.is_empty());
+}
+
+#[test]
+fn player_places_workstations_and_orders_recipes() {
+    let mut app = new_app();
+    *app.world_mut().resource_mut::<DesignationTool>() =
+        DesignationTool::Workstation(StationKind::Cauldron);
+    app.world_mut().send_event(PlayerCommand::DesignateArea {
+        from: UVec2::new(1, 1),
+        to: UVec2::new(2, 2),
+    });
+    app.update();
+    let mut stations = app.world_mut().query::<(Entity, &Workstation)>();
+    let placed: Vec<(Entity, UVec2)> = stations
+        .iter(app.world())
+        .map(|(entity, station)| (entity, station.cell))
+        .collect();
+    assert_eq!(placed.len(), 1, "one workstation per drag");
+    let (cauldron, cell) = placed[0];
+    assert_eq!(cell, UVec2::new(1, 1));
+
+    app.world_mut().send_event(PlayerCommand::OrderNextRecipe);
+    app.update();
+    assert!(
+        app.world()
+            .get::<Workstation>(cauldron)
+            .unwrap()
+            .orders
+            .is_empty(),
+        "nothing selected"
+    );
+
+    app.world_mut().entity_mut(cauldron).insert(Selected);
+    app.world_mut().send_event(PlayerCommand::OrderNextRecipe);
+    app.world_mut().send_event(PlayerCommand::OrderNextRecipe);
+    app.update();
+    app.world_mut().send_event(PlayerCommand::OrderNextRecipe);
+    app.update();
+    assert_eq!(
+        app.world().get::<Workstation>(cauldron).unwrap().orders,
+        vec![
+            "Stamina Potion".to_string(),
+            "Hearty Broth".to_string(),
+            "Catnip Tonic".to_string()
+        ]
+    );
+}
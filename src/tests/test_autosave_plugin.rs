@@ -0,0 +1,140 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    calendar_plugin::{CalendarConfig, CalendarPlugin},
+    economy_plugin::EconomyPlugin,
+    rng_plugin::RngPlugin,
+    save_plugin::{SavePlugin, SavedGame},
+    simulation_clock_plugin::{SIMULATION_TICKS_PER_SECOND, SimulationClock},
+    test_utils::{simulation_app, start_game},
+};
+
+use super::{AutosaveConfig, AutosavePlugin, autosave_path, newest_save, next_autosave_path};
+
+fn temp_save_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join(format!("macatemy-test-{}", std::process::id()))
+        .join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("save.ron")
+}
+
+fn new_app(path: PathBuf, config: AutosaveConfig, calendar: CalendarConfig) -> App {
+    let mut app = simulation_app();
+    app.add_plugins((
+        CalendarPlugin,
+        RngPlugin { seed: Some(1) },
+        EconomyPlugin,
+        SavePlugin { path },
+        AutosavePlugin,
+    ));
+    app.insert_resource(config);
+    app.insert_resource(calendar);
+    start_game(&mut app);
+    app
+}
+
+fn tick(app: &App) -> u64 {
+    app.world().resource::<SimulationClock>().tick
+}
+
+fn touch(path: &Path, secs: u64) {
+    std::fs::write(path, "").unwrap();
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+    std::fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(time)
+        .unwrap();
+}
+
+#[test]
+fn oldest_slot_is_reused() {
+    let save = temp_save_path("slots");
+    assert_eq!(
+        autosave_path(&save, 1),
+        save.with_file_name("save-autosave2.ron")
+    );
+    assert_eq!(next_autosave_path(&save, 3), autosave_path(&save, 0));
+    assert_eq!(newest_save(&save, 3), None);
+
+    touch(&autosave_path(&save, 0), 300);
+    touch(&autosave_path(&save, 1), 100);
+    assert_eq!(
+        next_autosave_path(&save, 3),
+        autosave_path(&save, 2),
+        "empty slots first"
+    );
+    touch(&autosave_path(&save, 2), 200);
+    assert_eq!(next_autosave_path(&save, 3), autosave_path(&save, 1));
+
+    assert_eq!(newest_save(&save, 3), Some(autosave_path(&save, 0)));
+    touch(&save, 400);
+    assert_eq!(newest_save(&save, 3), Some(save.clone()));
+    assert_eq!(newest_save(&save, 0), Some(save));
+}
+
+#[test]
+fn seasons_are_autosaved_in_turn() {
+    let save = temp_save_path("seasons");
+    let config = AutosaveConfig {
+        slots: 2,
+        interval_minutes: 0,
+        on_season_change: true,
+    };
+    // A season lasts two ticks
+    let calendar = CalendarConfig {
+        ticks_per_day: 1,
+        days_per_season: 2,
+    };
+    let mut app = new_app(save.clone(), config, calendar);
+    let season = |slot| {
+        SavedGame::load(&autosave_path(&save, slot))
+            .unwrap()
+            .calendar
+            .season_index()
+    };
+
+    while tick(&app) < 4 {
+        app.update();
+    }
+    assert_eq!((season(0), season(1)), (1, 2));
+    while tick(&app) < 6 {
+        app.update();
+    }
+    assert_eq!((season(0), season(1)), (3, 2), "first slot is overwritten");
+    assert!(!save.exists(), "the save of the player is left alone");
+}
+
+#[test]
+fn game_is_autosaved_every_few_minutes() {
+    let save = temp_save_path("interval");
+    let config = AutosaveConfig {
+        slots: 3,
+        interval_minutes: 1,
+        on_season_change: true,
+    };
+    let mut app = new_app(save.clone(), config, CalendarConfig::default());
+    let interval = (60.0 * SIMULATION_TICKS_PER_SECOND) as u64;
+    while !autosave_path(&save, 0).exists() {
+        app.update();
+        assert!(tick(&app) <= interval + 1, "first autosave is late");
+    }
+    let first = SavedGame::load(&autosave_path(&save, 0)).unwrap().tick;
+    while tick(&app) < first + interval - 1 {
+        app.update();
+    }
+    assert!(!autosave_path(&save, 1).exists());
+    app.update();
+    assert_eq!(
+        SavedGame::load(&autosave_path(&save, 1)).unwrap().tick,
+        first + interval
+    );
+}
@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+
+use crate::{player_control_plugin::PlayerCommand, test_utils::simulation_app};
+
+use super::{Calendar, CalendarConfig, CalendarPlugin, DayChanged, Season, SeasonChanged};
+
+#[derive(Resource, Default)]
+struct SeasonLog(Vec<SeasonChanged>);
+
+fn log_seasons(mut evs: EventReader<SeasonChanged>, mut log: ResMut<SeasonLog>) {
+    log.0.extend(evs.read().copied());
+}
+
+fn new_app(config: CalendarConfig) -> App {
+    let mut app = simulation_app();
+    app.add_plugins(CalendarPlugin);
+    app.insert_resource(config);
+    app.init_resource::<SeasonLog>();
+    app.add_systems(Update, log_seasons);
+    app
+}
+
+fn short_config() -> CalendarConfig {
+    CalendarConfig {
+        ticks_per_day: 2,
+        days_per_season: 3,
+    }
+}
+
+#[test]
+fn advance_rolls_over_days_seasons_and_years() {
+    let config = short_config();
+    let mut calendar = Calendar::default();
+    assert_eq!(calendar.season_index(), 0);
+
+    let rollover = calendar.advance(&config);
+    assert!(!rollover.day);
+    let rollover = calendar.advance(&config);
+    assert!(rollover.day && !rollover.season);
+    assert_eq!((calendar.day, calendar.tick_of_day), (1, 0));
+
+    // Rest of the spring
+    for _ in 0..4 {
+        calendar.advance(&config);
+    }
+    assert_eq!(calendar.season, Season::Summer);
+    assert_eq!(calendar.day, 0);
+    assert_eq!(calendar.season_index(), 1);
+
+    // Three more seasons to the next year
+    let mut years = 0;
+    for _ in 0..18 {
+        years += calendar.advance(&config).year as u32;
+    }
+    assert_eq!(years, 1);
+    assert_eq!(calendar.season, Season::Spring);
+    assert_eq!(calendar.year, 2);
+    assert_eq!(calendar.season_index(), 4);
+}
+
+#[test]
+fn calendar_follows_simulation_ticks() {
+    let mut app = new_app(short_config());
+    for _ in 0..20 {
+        app.update();
+    }
+    let tick = app
+        .world()
+        .resource::<crate::simulation_clock_plugin::SimulationClock>()
+        .tick as u32;
+    let calendar = *app.world().resource::<Calendar>();
+    let ticks_per_season = 2 * 3;
+    assert_eq!(calendar.season as u32, tick / ticks_per_season);
+    assert_eq!(calendar.day, tick % ticks_per_season / 2);
+
+    let log = &app.world().resource::<SeasonLog>().0;
+    assert_eq!(log.len() as u32, tick / ticks_per_season);
+    assert_eq!(log[0].previous, Season::Spring);
+    assert_eq!(log[0].calendar.season, Season::Summer);
+    assert_eq!(log[1].previous, Season::Summer);
+}
+
+#[test]
+fn calendar_stops_when_paused() {
+    let mut app = new_app(short_config());
+    for _ in 0..4 {
+        app.update();
+    }
+    app.world_mut().send_event(PlayerCommand::TogglePause);
+    app.update();
+    let paused_at = *app.world().resource::<Calendar>();
+    for _ in 0..20 {
+        app.update();
+    }
+    assert_eq!(*app.world().resource::<Calendar>(), paused_at);
+    assert!(app.world().resource::<Events<DayChanged>>().is_empty());
+}
@@ -0,0 +1,282 @@
+use std::time::Duration;
+
+use bevy::{input::InputPlugin, prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+use crate::{
+    game_state_plugin::GameStatePlugin,
+    orbit_camera_plugin::{OrbitCameraPlugin, OrbitCameraRig},
+    player_control_plugin::{CameraMode, Player, PlayerCommand, PlayerControlPlugin},
+    player_input_stage::PlayerInputStagesPlugin,
+    selection_plugin::{Selectable, Selected, SelectionPlugin},
+    test_utils::BaseTestSuite,
+};
+
+use super::{CameraBookmarks, CameraBookmarksPlugin, CameraFollow, CameraTransition};
+
+struct CameraBookmarksTestSuite {
+    app: App,
+}
+
+impl BaseTestSuite for CameraBookmarksTestSuite {
+    fn app(&mut self) -> &mut App {
+        &mut self.app
+    }
+}
+
+impl CameraBookmarksTestSuite {
+    fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            InputPlugin,
+            StatesPlugin,
+            GameStatePlugin,
+            PlayerInputStagesPlugin,
+            PlayerControlPlugin,
+            OrbitCameraPlugin,
+            SelectionPlugin,
+            CameraBookmarksPlugin,
+        ));
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            1.0 / 60.0,
+        )));
+        app.update();
+        app.update();
+        Self { app }
+    }
+
+    fn send(mut self, cmd: PlayerCommand) -> Self {
+        self.app.world_mut().send_event(cmd);
+        self.update()
+    }
+
+    fn wait(mut self, frames: usize) -> Self {
+        for _ in 0..frames {
+            self.app.update();
+        }
+        self
+    }
+
+    fn camera(&mut self) -> Transform {
+        *self
+            .app
+            .world_mut()
+            .query_filtered::<&Transform, With<Player>>()
+            .single(self.app.world())
+            .unwrap()
+    }
+
+    fn place_camera(mut self, tr: Transform) -> Self {
+        let world = self.app.world_mut();
+        let mut player = world
+            .query_filtered::<&mut Transform, With<Player>>()
+            .single_mut(world)
+            .unwrap();
+        *player = tr;
+        self
+    }
+
+    fn spawn_selectable(&mut self, pos: Vec3) -> Entity {
+        self.app
+            .world_mut()
+            .spawn((Selectable, Transform::from_translation(pos)))
+            .id()
+    }
+
+    fn is_transitioning(&mut self) -> bool {
+        self.app
+            .world_mut()
+            .query_filtered::<(), (With<Player>, With<CameraTransition>)>()
+            .single(self.app.world())
+            .is_ok()
+    }
+}
+
+fn looks_at(camera: &Transform, pos: Vec3) -> bool {
+    let to_target = (pos - camera.translation).normalize();
+    camera.forward().dot(to_target) > 0.999
+}
+
+#[test]
+fn bookmark_is_stored_with_ctrl_and_recalled_without() {
+    let bookmarked = Transform::from_xyz(1.0, 3.0, 2.0).looking_at(Vec3::ZERO, Vec3::Y);
+    let elsewhere = Transform::from_xyz(8.0, 2.0, 8.0).looking_at(Vec3::ONE, Vec3::Y);
+
+    let suite = CameraBookmarksTestSuite::new()
+        .place_camera(bookmarked)
+        .press(KeyCode::ControlLeft)
+        .press(KeyCode::Digit3)
+        .release(KeyCode::Digit3)
+        .release(KeyCode::ControlLeft);
+    {
+        let bookmarks = suite.app.world().resource::<CameraBookmarks>();
+        assert_eq!(bookmarks.0[2].unwrap().transform, bookmarked);
+        assert_eq!(bookmarks.0[2].unwrap().layer, 0);
+        assert!(bookmarks.0[0].is_none());
+    }
+
+    let mut suite = suite
+        .place_camera(elsewhere)
+        .press(KeyCode::Digit3)
+        .release(KeyCode::Digit3);
+    // Smooth transition: moved, but not there yet
+    let camera = suite.camera();
+    assert!(suite.is_transitioning());
+    assert!(camera.translation.distance(bookmarked.translation) > 0.1);
+    assert!(
+        camera.translation.distance(bookmarked.translation)
+            < elsewhere.translation.distance(bookmarked.translation)
+    );
+
+    let mut suite = suite.wait(300);
+    assert!(!suite.is_transitioning());
+    assert_eq!(suite.camera(), bookmarked);
+}
+
+#[test]
+fn recalling_empty_bookmark_does_nothing() {
+    let mut suite = CameraBookmarksTestSuite::new();
+    let initial = suite.camera();
+    let mut suite = suite
+        .send(PlayerCommand::RecallCameraBookmark(5))
+        .send(PlayerCommand::RecallCameraBookmark(100))
+        .wait(10);
+    assert!(!suite.is_transitioning());
+    assert_eq!(suite.camera(), initial);
+}
+
+#[test]
+fn moving_camera_interrupts_transition() {
+    let bookmarked = Transform::from_xyz(1.0, 3.0, 2.0).looking_at(Vec3::ZERO, Vec3::Y);
+    let mut suite = CameraBookmarksTestSuite::new()
+        .place_camera(bookmarked)
+        .send(PlayerCommand::StoreCameraBookmark(0))
+        .place_camera(Transform::from_xyz(8.0, 2.0, 8.0).looking_at(Vec3::ONE, Vec3::Y))
+        .send(PlayerCommand::RecallCameraBookmark(0));
+    assert!(suite.is_transitioning());
+
+    let mut suite = suite.send(PlayerCommand::MoveCameraInOut(1.0)).wait(2);
+    assert!(!suite.is_transitioning());
+    assert_ne!(suite.camera(), bookmarked);
+}
+
+#[test]
+fn bookmark_recall_in_orbit_mode_retargets_rig() {
+    let bookmarked = Transform::from_xyz(1.0, 3.0, 2.0).looking_at(Vec3::ZERO, Vec3::Y);
+    let mut suite = CameraBookmarksTestSuite::new()
+        .place_camera(bookmarked)
+        .send(PlayerCommand::StoreCameraBookmark(0))
+        .send(PlayerCommand::ToggleCameraMode)
+        .update()
+        .send(PlayerCommand::MoveCameraXZ(
+            crate::player_control_plugin::MoveCameraXZ::new(1.0, 1.0),
+        ))
+        .wait(100);
+    assert_eq!(
+        *suite.app.world().resource::<State<CameraMode>>().get(),
+        CameraMode::Orbit
+    );
+    assert!(
+        !suite
+            .camera()
+            .translation
+            .abs_diff_eq(bookmarked.translation, 1e-2)
+    );
+
+    let mut suite = suite.send(PlayerCommand::RecallCameraBookmark(0)).wait(300);
+    assert!(!suite.is_transitioning());
+    let rig = suite
+        .app
+        .world_mut()
+        .query::<&OrbitCameraRig>()
+        .single(suite.app.world())
+        .unwrap();
+    assert!(
+        rig.target
+            .to_transform()
+            .translation
+            .abs_diff_eq(bookmarked.translation, 1e-3)
+    );
+    assert!(
+        suite
+            .camera()
+            .translation
+            .abs_diff_eq(bookmarked.translation, 1e-2)
+    );
+}
+
+#[test]
+fn tab_cycles_selection() {
+    let mut suite = CameraBookmarksTestSuite::new();
+    let a = suite.spawn_selectable(Vec3::new(1.0, 0.0, 1.0));
+    let b = suite.spawn_selectable(Vec3::new(2.0, 0.0, 2.0));
+    let selected = |suite: &mut CameraBookmarksTestSuite| {
+        suite
+            .app
+            .world_mut()
+            .query_filtered::<Entity, With<Selected>>()
+            .iter(suite.app.world())
+            .collect::<Vec<_>>()
+    };
+
+    assert!(selected(&mut suite).is_empty());
+    let mut suite = suite.press(KeyCode::Tab).release(KeyCode::Tab);
+    assert_eq!(selected(&mut suite), vec![a]);
+    let mut suite = suite.press(KeyCode::Tab).release(KeyCode::Tab);
+    assert_eq!(selected(&mut suite), vec![b]);
+    let mut suite = suite.press(KeyCode::Tab).release(KeyCode::Tab);
+    assert_eq!(selected(&mut suite), vec![a]);
+}
+
+#[test]
+fn jump_to_selected_looks_at_it() {
+    let mut suite = CameraBookmarksTestSuite::new();
+    let pos = Vec3::new(7.0, 0.0, 2.0);
+    suite.spawn_selectable(pos);
+    let mut suite = suite
+        .send(PlayerCommand::SelectNext)
+        .press(KeyCode::KeyJ)
+        .release(KeyCode::KeyJ)
+        .wait(300);
+    assert!(looks_at(&suite.camera(), pos));
+}
+
+#[test]
+fn follow_selected_tracks_and_stops_on_manual_move() {
+    let mut suite = CameraBookmarksTestSuite::new();
+    let cat = suite.spawn_selectable(Vec3::new(7.0, 0.0, 2.0));
+    let mut suite = suite
+        .send(PlayerCommand::SelectNext)
+        .send(PlayerCommand::FollowSelected)
+        .wait(300);
+    assert!(looks_at(&suite.camera(), Vec3::new(7.0, 0.0, 2.0)));
+
+    let moved_to = Vec3::new(2.0, 0.0, 8.0);
+    suite
+        .app
+        .world_mut()
+        .get_mut::<Transform>(cat)
+        .unwrap()
+        .translation = moved_to;
+    let mut suite = suite.wait(300);
+    assert!(looks_at(&suite.camera(), moved_to));
+
+    let suite = suite.send(PlayerCommand::MoveCameraXZ(
+        crate::player_control_plugin::MoveCameraXZ::new(1.0, 0.0),
+    ));
+    assert!(suite.app.world().resource::<CameraFollow>().0.is_none());
+}
+
+#[test]
+fn follow_stops_when_entity_is_gone() {
+    let mut suite = CameraBookmarksTestSuite::new();
+    let cat = suite.spawn_selectable(Vec3::new(7.0, 0.0, 2.0));
+    let mut suite = suite
+        .send(PlayerCommand::SelectNext)
+        .send(PlayerCommand::FollowSelected);
+    assert_eq!(suite.app.world().resource::<CameraFollow>().0, Some(cat));
+
+    suite.app.world_mut().despawn(cat);
+    let suite = suite.wait(2);
+    assert!(suite.app.world().resource::<CameraFollow>().0.is_none());
+}
@@ -0,0 +1,120 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use super::{
+    CatSheet, CatTrait, CatTraits, MagicSchool, PrimaryAttribute, SecondaryAttribute, TRAIT_BONUS,
+    generate_cat_name,
+};
+
+fn sheets(seed: u64, count: usize) -> Vec<CatSheet> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    (0..count).map(|_| CatSheet::generate(&mut rng)).collect()
+}
+
+#[test]
+fn generation_is_reproducible_with_seed() {
+    assert_eq!(sheets(7, 10), sheets(7, 10));
+    assert_ne!(sheets(7, 10), sheets(8, 10));
+}
+
+#[test]
+fn attributes_are_in_default_roll_range() {
+    for sheet in sheets(1, 1000) {
+        for attr in PrimaryAttribute::ALL {
+            assert!((4..=40).contains(&sheet.primary.get(attr)));
+        }
+        for attr in SecondaryAttribute::ALL {
+            assert!((4..=40).contains(&sheet.secondary.get(attr)));
+        }
+    }
+}
+
+#[test]
+fn secondary_attributes_mostly_fit_between_ceiling_and_floor() {
+    // Constitution is capped by strength: weak cats must have lower constitution on average
+    let sheets = sheets(2, 2000);
+    let average = |filter: &dyn Fn(&CatSheet) -> bool| {
+        let values: Vec<i32> = sheets
+            .iter()
+            .filter(|s| filter(s))
+            .map(|s| s.secondary.constitution)
+            .collect();
+        values.iter().sum::<i32>() as f64 / values.len() as f64
+    };
+    let weak = average(&|s| s.primary.strength < 10);
+    let strong = average(&|s| s.primary.strength > 16);
+    assert!(weak < strong, "weak {weak}, strong {strong}");
+}
+
+#[test]
+fn traits_never_contradict() {
+    for sheet in sheets(3, 1000) {
+        let traits = &sheet.traits.0;
+        assert!(traits.len() <= 3);
+        for (i, a) in traits.iter().enumerate() {
+            for b in &traits[i + 1..] {
+                assert_ne!(a.attribute(), b.attribute(), "{traits:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn trait_modifies_attribute_roll() {
+    let mut sheet = sheets(4, 1).pop().unwrap();
+    sheet.traits = CatTraits(vec![CatTrait::Lucky, CatTrait::Clumsy]);
+    assert_eq!(
+        sheet.attribute_roll(PrimaryAttribute::Luck).bonus,
+        TRAIT_BONUS
+    );
+    assert_eq!(
+        sheet.attribute_roll(PrimaryAttribute::Agility).bonus,
+        -TRAIT_BONUS
+    );
+    assert_eq!(sheet.attribute_roll(PrimaryAttribute::Charm).bonus, 0);
+    assert_eq!(
+        sheet.attribute_roll(PrimaryAttribute::Luck).sides,
+        sheet.primary.luck as u32
+    );
+}
+
+#[test]
+fn names_are_capitalized() {
+    let mut rng = ChaCha8Rng::seed_from_u64(5);
+    for _ in 0..100 {
+        let name = generate_cat_name(&mut rng);
+        assert!(name.len() >= 4);
+        assert!(name.chars().next().unwrap().is_uppercase());
+        assert!(name.chars().skip(1).all(|c| c.is_lowercase()));
+    }
+}
+
+#[test]
+fn school_aptitude_favors_base_attribute() {
+    let mut sheet = sheets(6, 1).pop().unwrap();
+    sheet.primary.magic = 30;
+    sheet.primary.charm = 4;
+    sheet.primary.luck = 4;
+    sheet.primary.intelligence = 4;
+    sheet.secondary.perception = 4;
+    sheet.secondary.willpower = 4;
+    assert_eq!(sheet.school_aptitude(MagicSchool::Elemental), 30);
+    assert_eq!(sheet.school_aptitude(MagicSchool::Evocation), 32);
+    assert_eq!(sheet.best_school(), MagicSchool::Evocation);
+
+    sheet.primary.magic = 4;
+    sheet.primary.charm = 20;
+    assert_eq!(sheet.school_aptitude(MagicSchool::Divination), 22);
+    assert_eq!(sheet.school_aptitude(MagicSchool::Evocation), 14);
+    assert_eq!(sheet.best_school(), MagicSchool::Divination);
+}
+
+#[test]
+fn every_trait_is_listed_once() {
+    for attr in PrimaryAttribute::ALL {
+        for positive in [true, false] {
+            let t = CatTrait::of(attr, positive);
+            assert_eq!(CatTrait::ALL.iter().filter(|x| **x == t).count(), 1);
+        }
+    }
+}
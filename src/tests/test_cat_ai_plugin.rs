@@ -0,0 +1,340 @@
+use bevy::prelude::*;
+
+use crate::{
+    calendar_plugin::CalendarPlugin,
+    cat::{CatTrait, CatTraits, MagicSchool},
+    designation_plugin::{DesignationKind, JobQueue},
+    experience_plugin::{Practising, Skill},
+    needs_plugin::{
+        NEED_CRITICAL, NEED_LOW, NEED_MAX, Need, NeedSource, Needs, NeedsPlugin, Using,
+    },
+    pathfinding::cell_of,
+    rng_plugin::RngPlugin,
+    student_plugin::{Education, Enrollment},
+    test_utils::{sheet, simulation_app, start_game},
+    timetable_plugin::{Class, Classrooms, LessonSlot, Timetable, TimetablePlugin},
+};
+
+use super::{
+    Action, CLASS_UTILITY, CatAiPlugin, HOMEWORK_TICKS, Situation, Task, TaskChosen, utility,
+};
+
+fn class(teacher: Entity, students: Vec<Entity>) -> Class {
+    Class {
+        school: MagicSchool::Elemental,
+        teacher,
+        students,
+        classroom: 0,
+        slot: LessonSlot {
+            weekday: 0,
+            lesson: 0,
+        },
+    }
+}
+
+fn best(situation: &Situation) -> Action {
+    let mut best = Action::Wander;
+    for action in Action::ALL {
+        if utility(action, situation) > utility(best, situation) {
+            best = action;
+        }
+    }
+    best
+}
+
+#[test]
+fn needs_schedule_and_traits_drive_utility() {
+    let sheet = sheet();
+    let content = Needs::default();
+    let hungry = Needs {
+        hunger: NEED_CRITICAL - 1.0,
+        ..default()
+    };
+    let student = Entity::from_raw(1);
+    let class = class(Entity::from_raw(2), vec![student]);
+
+    let idle = Situation {
+        sheet: &sheet,
+        needs: &content,
+        student: false,
+        attending: None,
+        teaching: None,
+        job: None,
+    };
+    assert_eq!(best(&idle), Action::Wander);
+    assert_eq!(utility(Action::Teach, &idle), 0.0);
+    assert_eq!(utility(Action::Study, &idle), 0.0);
+    assert_eq!(
+        best(&Situation {
+            student: true,
+            ..idle
+        }),
+        Action::Study
+    );
+
+    let in_class = Situation {
+        student: true,
+        attending: Some(&class),
+        ..idle
+    };
+    assert_eq!(best(&in_class), Action::Study);
+    assert_eq!(utility(Action::Study, &in_class), CLASS_UTILITY);
+    assert_eq!(
+        best(&Situation {
+            teaching: Some(&class),
+            ..idle
+        }),
+        Action::Teach
+    );
+    // Starving cat skips the class
+    assert_eq!(
+        best(&Situation {
+            needs: &hungry,
+            ..in_class
+        }),
+        Action::Eat
+    );
+
+    // Dirty cats groom at the puddle
+    assert_eq!(
+        best(&Situation {
+            needs: &Needs {
+                hygiene: NEED_LOW - 1.0,
+                ..default()
+            },
+            ..idle
+        }),
+        Action::Groom
+    );
+    // Bored cats play with the yarn ball
+    assert_eq!(
+        best(&Situation {
+            needs: &Needs {
+                fun: NEED_LOW - 1.0,
+                ..default()
+            },
+            ..idle
+        }),
+        Action::Play
+    );
+
+    let mut queue = JobQueue::default();
+    queue.designate(DesignationKind::Dig, 0, UVec2::ZERO);
+    let working = Situation {
+        student: true,
+        job: queue.jobs.first(),
+        ..idle
+    };
+    assert_eq!(best(&working), Action::Work);
+    assert_eq!(
+        best(&Situation {
+            attending: Some(&class),
+            ..working
+        }),
+        Action::Study
+    );
+
+    let mut smarty = sheet.clone();
+    smarty.traits = CatTraits(vec![CatTrait::Smarty]);
+    assert!(
+        utility(
+            Action::Study,
+            &Situation {
+                sheet: &smarty,
+                ..in_class
+            }
+        ) > CLASS_UTILITY
+    );
+}
+
+#[derive(Resource, Default)]
+struct ChoiceLog(Vec<TaskChosen>);
+
+fn log_choices(mut evs: EventReader<TaskChosen>, mut log: ResMut<ChoiceLog>) {
+    log.0.extend(evs.read().copied());
+}
+
+fn new_app(seed: u64) -> App {
+    let mut app = simulation_app();
+    app.add_plugins((
+        RngPlugin { seed: Some(seed) },
+        CalendarPlugin,
+        TimetablePlugin,
+        NeedsPlugin,
+        CatAiPlugin,
+    ));
+    app.init_resource::<ChoiceLog>();
+    app.add_systems(Update, log_choices);
+    start_game(&mut app);
+    app
+}
+
+fn run(app: &mut App, frames: usize) {
+    for _ in 0..frames {
+        app.update();
+    }
+}
+
+fn task(app: &App, cat: Entity) -> Option<Task> {
+    app.world().get::<Task>(cat).copied()
+}
+
+fn spawn_cat(app: &mut App, needs: Needs, at: Vec3) -> Entity {
+    app.world_mut()
+        .spawn((sheet(), needs, Transform::from_translation(at)))
+        .id()
+}
+
+#[test]
+fn hungry_cat_walks_to_food_and_eats() {
+    let mut app = new_app(1);
+    let bowl = app
+        .world_mut()
+        .spawn((
+            NeedSource {
+                need: Need::Hunger,
+                per_tick: 5.0,
+            },
+            Transform::from_xyz(3.0, 0.7, 0.0),
+        ))
+        .id();
+    let cat = spawn_cat(
+        &mut app,
+        Needs {
+            hunger: NEED_CRITICAL - 1.0,
+            ..default()
+        },
+        Vec3::ZERO,
+    );
+    run(&mut app, 2);
+    let eating = task(&app, cat).unwrap();
+    assert_eq!(eating.action, Action::Eat);
+    assert_eq!(eating.target, Some(bowl));
+    assert_eq!(eating.destination, UVec2::new(3, 0));
+
+    let mut used = false;
+    for _ in 0..200 {
+        app.update();
+        used |= app.world().get::<Using>(cat) == Some(&Using(bowl));
+        if task(&app, cat).is_none_or(|task| task.action != Action::Eat) {
+            break;
+        }
+    }
+    assert!(used);
+    assert_eq!(
+        cell_of(app.world().get::<Transform>(cat).unwrap().translation),
+        UVec2::new(3, 0)
+    );
+    assert!(app.world().get::<Needs>(cat).unwrap().hunger > NEED_MAX - 1.0);
+}
+
+#[test]
+fn teacher_and_students_go_to_class() {
+    let mut app = new_app(1);
+    let teacher = spawn_cat(&mut app, Needs::default(), Vec3::new(5.0, 0.0, 0.0));
+    let student = spawn_cat(&mut app, Needs::default(), Vec3::new(0.0, 0.0, 0.0));
+    app.world_mut().entity_mut(student).insert(Enrollment {
+        enrolled_season: 0,
+        education: Education::General,
+        education_started: 0,
+    });
+    app.insert_resource(Timetable {
+        classes: vec![class(teacher, vec![student])],
+    });
+    run(&mut app, 200);
+
+    let room = app.world().resource::<Classrooms>().0[0].clone();
+    let position = |cat| cell_of(app.world().get::<Transform>(cat).unwrap().translation);
+    assert_eq!(task(&app, teacher).unwrap().action, Action::Teach);
+    assert_eq!(task(&app, student).unwrap().action, Action::Study);
+    assert_eq!(position(teacher), room.min);
+    assert_eq!(position(student), room.min + UVec2::X);
+}
+
+#[test]
+fn student_without_class_practises() {
+    let mut app = new_app(1);
+    let student = spawn_cat(&mut app, Needs::default(), Vec3::ZERO);
+    app.world_mut().entity_mut(student).insert(Enrollment {
+        enrolled_season: 0,
+        education: Education::General,
+        education_started: 0,
+    });
+    run(&mut app, 3);
+    let homework = task(&app, student).unwrap();
+    assert_eq!(homework.action, Action::Study);
+    assert_eq!(homework.lesson, None);
+    let school = sheet().best_school();
+    assert_eq!(
+        app.world().get::<Practising>(student),
+        Some(&Practising(Skill::School(school)))
+    );
+    // The task ends and the student picks the next one
+    run(&mut app, HOMEWORK_TICKS as usize);
+    assert!(task(&app, student).unwrap().started > homework.started);
+}
+
+#[test]
+fn critical_need_interrupts_task() {
+    let mut app = new_app(1);
+    let student = spawn_cat(&mut app, Needs::default(), Vec3::ZERO);
+    app.world_mut().entity_mut(student).insert(Enrollment {
+        enrolled_season: 0,
+        education: Education::General,
+        education_started: 0,
+    });
+    app.world_mut().spawn((
+        NeedSource {
+            need: Need::Energy,
+            per_tick: 1.0,
+        },
+        Transform::from_xyz(2.0, 0.7, 2.0),
+    ));
+    run(&mut app, 3);
+    assert_eq!(task(&app, student).unwrap().action, Action::Study);
+    app.world_mut().get_mut::<Needs>(student).unwrap().energy = 0.0;
+    run(&mut app, 2);
+    assert_eq!(task(&app, student).unwrap().action, Action::Sleep);
+}
+
+/// Starving and exhausted cats with food and bed at the same distance
+fn tie_choices(seed: u64) -> Vec<Action> {
+    let mut app = new_app(seed);
+    for (need, x) in [(Need::Hunger, -2.0), (Need::Energy, 2.0)] {
+        app.world_mut().spawn((
+            NeedSource {
+                need,
+                per_tick: 1.0,
+            },
+            Transform::from_xyz(x, 0.7, 0.0),
+        ));
+    }
+    for _ in 0..8 {
+        spawn_cat(
+            &mut app,
+            Needs {
+                hunger: 0.0,
+                energy: 0.0,
+                ..default()
+            },
+            Vec3::ZERO,
+        );
+    }
+    run(&mut app, 2);
+    app.world()
+        .resource::<ChoiceLog>()
+        .0
+        .iter()
+        .map(|choice| choice.action)
+        .collect()
+}
+
+#[test]
+fn ties_are_broken_reproducibly() {
+    let choices = tie_choices(7);
+    assert_eq!(choices.len(), 8);
+    assert!(choices.contains(&Action::Eat));
+    assert!(choices.contains(&Action::Sleep));
+    assert_eq!(tie_choices(7), choices);
+    assert_ne!(tie_choices(8), choices);
+}
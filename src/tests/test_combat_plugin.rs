@@ -0,0 +1,279 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    calendar_plugin::CalendarPlugin,
+    cat::{CatSheet, SecondaryAttributes},
+    cat_ai_plugin::{CatAiPlugin, Task},
+    needs_plugin::Needs,
+    rng_plugin::RngPlugin,
+    test_utils::{sheet, simulation_app, start_game},
+    timetable_plugin::TimetablePlugin,
+};
+
+use super::{
+    Attacked, CombatPlugin, CombatStyle, Creature, CreatureKind, Defeated, ENGAGE_DISTANCE,
+    Encounter, EncounterEnded, Fighting, HEALTH_PER_CONSTITUTION, Health, StartEncounter,
+    StatusEffect, TURN_TICKS, max_health, strike,
+};
+
+fn attrs(value: i32) -> SecondaryAttributes {
+    SecondaryAttributes {
+        constitution: value,
+        speed: value,
+        perception: value,
+        melee_combat: value,
+        ranged_combat: value,
+        magic_combat: value,
+        willpower: value,
+    }
+}
+
+fn fighter(value: i32) -> CatSheet {
+    let mut sheet = sheet();
+    sheet.secondary = attrs(value);
+    sheet
+}
+
+#[test]
+fn styles_use_matching_attributes() {
+    let mut archer = attrs(10);
+    archer.ranged_combat = 20;
+    assert_eq!(CombatStyle::best(&archer), CombatStyle::Ranged);
+    assert_eq!(CombatStyle::best(&attrs(10)), CombatStyle::Melee);
+    assert_eq!(
+        CombatStyle::best(&CreatureKind::Wisp.attributes()),
+        CombatStyle::Magic
+    );
+    assert!(CombatStyle::Magic.effect().is_mental());
+    assert!(!CombatStyle::Melee.effect().is_mental());
+    assert_eq!(max_health(&attrs(12)), 12 * HEALTH_PER_CONSTITUTION);
+}
+
+#[test]
+fn strong_attacks_hit_harder() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let (mut strong_damage, mut weak_damage) = (0, 0);
+    for _ in 0..1000 {
+        let hit = strike(&attrs(30), &attrs(8), CombatStyle::Melee, &mut rng);
+        assert_eq!(hit.damage > 0, hit.attack > hit.defence);
+        strong_damage += hit.damage;
+        weak_damage += strike(&attrs(8), &attrs(30), CombatStyle::Melee, &mut rng).damage;
+    }
+    assert!(strong_damage > weak_damage * 5);
+}
+
+#[test]
+fn willpower_resists_mental_effects() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let mut mage = attrs(10);
+    mage.magic_combat = 20;
+    let mut stubborn = attrs(10);
+    stubborn.willpower = 40;
+    let frightened = |defender: &SecondaryAttributes, rng: &mut ChaCha8Rng| {
+        (0..1000)
+            .map(|_| strike(&mage, defender, CombatStyle::Magic, rng))
+            .filter(|hit| hit.effect == Some(StatusEffect::Frightened))
+            .count()
+    };
+    let mut weak = attrs(10);
+    weak.willpower = 4;
+    let weak_willed = frightened(&weak, &mut rng);
+    let strong_willed = frightened(&stubborn, &mut rng);
+    assert!(weak_willed > 100, "{weak_willed}");
+    assert!(strong_willed < weak_willed / 2, "{strong_willed}");
+}
+
+#[derive(Resource, Default)]
+struct CombatLog {
+    attacks: Vec<Attacked>,
+    defeats: Vec<Defeated>,
+    ends: Vec<EncounterEnded>,
+}
+
+fn log_combat(
+    mut attacks: EventReader<Attacked>,
+    mut defeats: EventReader<Defeated>,
+    mut ends: EventReader<EncounterEnded>,
+    mut log: ResMut<CombatLog>,
+) {
+    log.attacks.extend(attacks.read().copied());
+    log.defeats.extend(defeats.read().copied());
+    log.ends.extend(ends.read().copied());
+}
+
+fn new_app(seed: u64) -> App {
+    let mut app = simulation_app();
+    app.add_plugins((
+        RngPlugin { seed: Some(seed) },
+        CalendarPlugin,
+        TimetablePlugin,
+        CatAiPlugin,
+        CombatPlugin,
+    ));
+    app.init_resource::<CombatLog>();
+    app.add_systems(Update, log_combat);
+    start_game(&mut app);
+    app
+}
+
+fn fight(app: &mut App, sides: [Vec<Entity>; 2]) {
+    app.world_mut().trigger(StartEncounter { sides });
+    finish_fight(app);
+}
+
+fn finish_fight(app: &mut App) {
+    for _ in 0..TURN_TICKS * 200 {
+        app.update();
+        if !app.world().resource::<CombatLog>().ends.is_empty() {
+            break;
+        }
+    }
+}
+
+fn health(app: &App, fighter: Entity) -> Health {
+    *app.world().get::<Health>(fighter).unwrap()
+}
+
+#[test]
+fn fighters_get_health() {
+    let mut app = new_app(1);
+    let cat = app.world_mut().spawn(fighter(12)).id();
+    let golem = app.world_mut().spawn(Creature(CreatureKind::Golem)).id();
+    assert_eq!(health(&app, cat), Health::full(24));
+    assert_eq!(
+        health(&app, golem),
+        Health::full(max_health(&CreatureKind::Golem.attributes()))
+    );
+}
+
+#[test]
+fn duel_ends_with_stronger_cat_standing() {
+    let mut app = new_app(1);
+    let champion = app.world_mut().spawn(fighter(30)).id();
+    let novice = app.world_mut().spawn(fighter(6)).id();
+    fight(&mut app, [vec![novice], vec![champion]]);
+
+    let log = app.world().resource::<CombatLog>();
+    assert_eq!(log.ends.len(), 1);
+    assert_eq!(log.ends[0].winner, Some(1));
+    assert_eq!(log.defeats.len(), 1);
+    assert_eq!(log.defeats[0].fighter, novice);
+    assert!(log.attacks.iter().any(|attack| attack.attacker == novice));
+    assert_eq!(health(&app, novice).current, 0);
+    assert!(health(&app, champion).current > 0);
+    assert!(app.world().get::<Fighting>(champion).is_none());
+    assert!(app.world().get::<Fighting>(novice).is_none());
+    assert!(
+        app.world().get_entity(log.ends[0].encounter).is_err(),
+        "encounter is despawned"
+    );
+}
+
+#[test]
+fn defeated_creatures_vanish() {
+    let mut app = new_app(1);
+    let students: Vec<Entity> = (0..3)
+        .map(|_| app.world_mut().spawn(fighter(20)).id())
+        .collect();
+    let imp = app.world_mut().spawn(Creature(CreatureKind::Imp)).id();
+    fight(&mut app, [students.clone(), vec![imp]]);
+
+    let log = app.world().resource::<CombatLog>();
+    assert_eq!(log.ends[0].winner, Some(0));
+    assert!(app.world().get_entity(imp).is_err());
+    let targets: Vec<Entity> = log
+        .attacks
+        .iter()
+        .filter(|attack| attack.attacker == imp)
+        .map(|attack| attack.defender)
+        .collect();
+    assert!(targets.iter().all(|target| students.contains(target)));
+}
+
+#[test]
+fn fights_are_reproducible() {
+    let duel = |seed| {
+        let mut app = new_app(seed);
+        let a = app.world_mut().spawn(fighter(14)).id();
+        let b = app.world_mut().spawn(fighter(14)).id();
+        fight(&mut app, [vec![a], vec![b]]);
+        let log = app.world().resource::<CombatLog>();
+        let strikes: Vec<_> = log.attacks.iter().map(|attack| attack.strike).collect();
+        (strikes, log.ends[0].winner)
+    };
+    assert_eq!(duel(3), duel(3));
+    assert_ne!(duel(3).0, duel(4).0);
+}
+
+#[test]
+fn fighters_drop_their_tasks_and_heal_afterwards() {
+    let mut app = new_app(1);
+    let cat = app
+        .world_mut()
+        .spawn((fighter(12), Needs::default(), Transform::default()))
+        .id();
+    app.update();
+    assert!(app.world().get::<Task>(cat).is_some());
+    let golem = app.world_mut().spawn(Creature(CreatureKind::Golem)).id();
+    app.world_mut().trigger(StartEncounter {
+        sides: [vec![cat], vec![golem]],
+    });
+    app.update();
+    assert!(app.world().get::<Fighting>(cat).is_some());
+    assert!(app.world().get::<Task>(cat).is_none());
+
+    finish_fight(&mut app);
+    assert!(app.world().get::<Fighting>(cat).is_none());
+    let wounded = health(&app, cat).current;
+    assert!(wounded < health(&app, cat).max);
+    for _ in 0..200 {
+        app.update();
+    }
+    assert!(health(&app, cat).current > wounded);
+    assert!(app.world().get::<Task>(cat).is_some());
+}
+
+#[test]
+fn busy_fighters_stay_out_of_other_encounters() {
+    let mut app = new_app(1);
+    let a = app.world_mut().spawn(fighter(12)).id();
+    let b = app.world_mut().spawn(fighter(12)).id();
+    let c = app.world_mut().spawn(fighter(12)).id();
+    app.world_mut().trigger(StartEncounter {
+        sides: [vec![a], vec![b]],
+    });
+    app.update();
+    app.world_mut().trigger(StartEncounter {
+        sides: [vec![c], vec![b]],
+    });
+    app.update();
+    let encounter = app.world().get::<Fighting>(a).unwrap().encounter;
+    assert_eq!(app.world().get::<Fighting>(b).unwrap().encounter, encounter);
+    assert!(app.world().get::<Fighting>(c).is_none());
+}
+
+#[test]
+fn creatures_attack_nearby_cats() {
+    let mut app = new_app(1);
+    let at = |x: f32| Transform::from_xyz(x, 0.0, 0.0);
+    let prey = app.world_mut().spawn((fighter(12), at(0.0))).id();
+    let far = app.world_mut().spawn((fighter(12), at(20.0))).id();
+    let imp = app
+        .world_mut()
+        .spawn((Creature(CreatureKind::Imp), at(ENGAGE_DISTANCE)))
+        .id();
+    // Near the prey but too far from the first imp
+    let pack = app
+        .world_mut()
+        .spawn((Creature(CreatureKind::Imp), at(-ENGAGE_DISTANCE)))
+        .id();
+    app.update();
+    let encounter = app.world().get::<Fighting>(prey).unwrap().encounter;
+    assert_eq!(
+        app.world().get::<Encounter>(encounter).unwrap().sides,
+        [vec![prey], vec![imp, pack]]
+    );
+    assert!(app.world().get::<Fighting>(far).is_none());
+}
@@ -0,0 +1,320 @@
+use bevy::prelude::*;
+
+use crate::{
+    alchemy_plugin::StationKind,
+    calendar_plugin::CalendarPlugin,
+    cat::CatSheet,
+    cat_ai_plugin::{Action, CatAiPlugin, Task},
+    game_map_plugin::{GameMapCellFloor, GameMapPlugin, SetCellFloorEvent},
+    needs_plugin::Needs,
+    player_control_plugin::PlayerCommand,
+    rng_plugin::RngPlugin,
+    test_utils::{sheet, simulation_app, start_game},
+    timetable_plugin::TimetablePlugin,
+};
+
+use super::{
+    DesignationKind, DesignationPlugin, DesignationTool, JOB_RETRY_TICKS, JOB_WORK, JobDone,
+    JobQueue, JobState, MIN_JOB_ATTRIBUTE, Working,
+};
+
+#[derive(Resource, Default)]
+struct JobLog {
+    done: Vec<JobDone>,
+    floors: Vec<SetCellFloorEvent>,
+}
+
+fn log_jobs(mut evs: EventReader<JobDone>, mut log: ResMut<JobLog>) {
+    log.done.extend(evs.read().copied());
+}
+
+fn log_floors(ev: Trigger<SetCellFloorEvent>, mut log: ResMut<JobLog>) {
+    log.floors.push(*ev);
+}
+
+fn new_app() -> App {
+    new_app_with_map(false)
+}
+
+/// The map needs render assets, which are made without rendering
+fn new_app_with_map(map: bool) -> App {
+    let mut app = simulation_app();
+    app.add_plugins((
+        RngPlugin { seed: Some(1) },
+        CalendarPlugin,
+        TimetablePlugin,
+        CatAiPlugin,
+        DesignationPlugin,
+    ));
+    if map {
+        app.add_plugins(AssetPlugin::default());
+        app.init_asset::<Mesh>();
+        app.init_asset::<StandardMaterial>();
+        app.init_asset::<Scene>();
+        app.add_plugins(GameMapPlugin);
+    }
+    app.init_resource::<JobLog>();
+    app.add_systems(Update, log_jobs);
+    app.add_observer(log_floors);
+    start_game(&mut app);
+    app
+}
+
+fn worker(strength: i32) -> CatSheet {
+    let mut sheet = sheet();
+    sheet.primary.strength = strength;
+    sheet.primary.agility = 13;
+    sheet
+}
+
+fn queue(app: &App) -> &JobQueue {
+    app.world().resource::<JobQueue>()
+}
+
+#[test]
+fn tool_cycles_through_every_kind() {
+    let mut tool = DesignationTool::Off;
+    let mut seen = vec![];
+    for _ in 0..8 {
+        tool = tool.next();
+        seen.push(tool);
+    }
+    assert_eq!(tool, DesignationTool::Off);
+    assert!(seen.contains(&DesignationTool::Designate(DesignationKind::ClearGrass)));
+    assert!(!seen.contains(&DesignationTool::Designate(DesignationKind::Haul)));
+    assert!(seen.contains(&DesignationTool::Stockpile));
+    assert!(seen.contains(&DesignationTool::Workstation(StationKind::Cauldron)));
+    assert!(seen.contains(&DesignationTool::Workstation(StationKind::AlchemyTable)));
+    assert!(seen.contains(&DesignationTool::Cancel));
+}
+
+#[test]
+fn designations_depend_on_floor() {
+    use GameMapCellFloor::*;
+    assert!(DesignationKind::Dig.applies_to(Stone));
+    assert!(!DesignationKind::Dig.applies_to(None));
+    assert!(DesignationKind::Build.applies_to(None));
+    assert!(!DesignationKind::Build.applies_to(Grass));
+    assert!(DesignationKind::ClearGrass.applies_to(Grass));
+    assert!(!DesignationKind::ClearGrass.applies_to(Ground));
+    assert_eq!(DesignationKind::Dig.result(), Some(None));
+    assert_eq!(DesignationKind::ClearGrass.result(), Some(Ground));
+    assert_eq!(DesignationKind::Haul.result(), Option::None);
+}
+
+#[test]
+fn one_job_per_cell() {
+    let mut queue = JobQueue::default();
+    let cell = UVec2::new(1, 2);
+    let dig = queue.designate(DesignationKind::Dig, 0, cell);
+    queue.designate(DesignationKind::Dig, 1, cell);
+    let haul = queue.designate(DesignationKind::Haul, 0, cell);
+    assert_ne!(dig, haul);
+    assert_eq!(queue.jobs.len(), 2);
+    assert_eq!(queue.at(0, cell).unwrap().kind, DesignationKind::Haul);
+    assert_eq!(queue.cancel(0, cell).unwrap().id, haul);
+    assert!(queue.at(0, cell).is_none());
+    assert!(queue.at(1, cell).is_some());
+}
+
+#[test]
+fn player_designates_and_cancels_areas() {
+    let mut app = new_app();
+    let area = |from: (u32, u32), to: (u32, u32)| PlayerCommand::DesignateArea {
+        from: UVec2::new(from.0, from.1),
+        to: UVec2::new(to.0, to.1),
+    };
+
+    app.world_mut().send_event(area((0, 0), (1, 1)));
+    app.update();
+    assert!(queue(&app).jobs.is_empty());
+
+    app.world_mut()
+        .send_event(PlayerCommand::CycleDesignationTool);
+    app.update();
+    assert_eq!(
+        *app.world().resource::<DesignationTool>(),
+        DesignationTool::Designate(DesignationKind::Dig)
+    );
+    app.world_mut().send_event(area((2, 3), (1, 2)));
+    app.update();
+    assert_eq!(queue(&app).jobs.len(), 4);
+    assert!(
+        queue(&app)
+            .jobs
+            .iter()
+            .all(|job| job.kind == DesignationKind::Dig && job.state == JobState::Pending)
+    );
+
+    *app.world_mut().resource_mut::<DesignationTool>() = DesignationTool::Cancel;
+    app.world_mut().send_event(area((1, 2), (2, 2)));
+    app.update();
+    let cells: Vec<UVec2> = queue(&app).jobs.iter().map(|job| job.cell).collect();
+    assert_eq!(cells, vec![UVec2::new(1, 3), UVec2::new(2, 3)]);
+}
+
+#[test]
+fn jobs_go_to_strongest_eligible_cats() {
+    let mut app = new_app();
+    let weak = app.world_mut().spawn(worker(MIN_JOB_ATTRIBUTE - 1)).id();
+    let strong = app.world_mut().spawn(worker(20)).id();
+    let average = app.world_mut().spawn(worker(12)).id();
+    {
+        let mut queue = app.world_mut().resource_mut::<JobQueue>();
+        for col in 0..3 {
+            queue.designate(DesignationKind::Haul, 0, UVec2::new(col, 0));
+        }
+    }
+    app.update();
+    let states: Vec<JobState> = queue(&app).jobs.iter().map(|job| job.state).collect();
+    assert_eq!(
+        states,
+        vec![
+            JobState::Assigned(strong),
+            JobState::Assigned(average),
+            JobState::Pending
+        ]
+    );
+    assert!(queue(&app).assigned_to(weak).is_none());
+
+    app.world_mut().despawn(strong);
+    app.update();
+    assert_eq!(
+        queue(&app).jobs[0].state,
+        JobState::Pending,
+        "job of a departed cat is free again"
+    );
+}
+
+#[test]
+fn working_cat_finishes_job() {
+    let mut app = new_app();
+    let cat = app.world_mut().spawn(worker(13)).id();
+    let cell = UVec2::new(4, 4);
+    let id = app
+        .world_mut()
+        .resource_mut::<JobQueue>()
+        .designate(DesignationKind::Dig, 0, cell);
+    app.update();
+    assert_eq!(queue(&app).jobs[0].state, JobState::Assigned(cat));
+    app.world_mut().entity_mut(cat).insert(Working(id));
+    for _ in 0..JOB_WORK as usize + 2 {
+        app.update();
+    }
+    assert!(queue(&app).jobs.is_empty());
+    assert!(app.world().get::<Working>(cat).is_none());
+    let log = app.world().resource::<JobLog>();
+    assert_eq!(
+        log.done,
+        vec![JobDone {
+            cat,
+            kind: DesignationKind::Dig,
+            layer: 0,
+            cell
+        }]
+    );
+    assert_eq!(
+        log.floors,
+        vec![SetCellFloorEvent {
+            layer: 0,
+            cell,
+            floor: GameMapCellFloor::None
+        }]
+    );
+}
+
+#[test]
+fn cancelled_job_stops_work() {
+    let mut app = new_app();
+    let cat = app.world_mut().spawn(worker(13)).id();
+    let id =
+        app.world_mut()
+            .resource_mut::<JobQueue>()
+            .designate(DesignationKind::Haul, 0, UVec2::ZERO);
+    app.update();
+    app.world_mut().entity_mut(cat).insert(Working(id));
+    app.world_mut()
+        .resource_mut::<JobQueue>()
+        .cancel(0, UVec2::ZERO);
+    app.update();
+    assert!(app.world().get::<Working>(cat).is_none());
+    assert!(app.world().resource::<JobLog>().done.is_empty());
+}
+
+#[test]
+fn cats_walk_to_jobs_and_do_them() {
+    let mut app = new_app();
+    let cat = app
+        .world_mut()
+        .spawn((worker(13), Needs::default(), Transform::default()))
+        .id();
+    let cell = UVec2::new(3, 0);
+    app.world_mut()
+        .resource_mut::<JobQueue>()
+        .designate(DesignationKind::ClearGrass, 0, cell);
+    app.update();
+    app.update();
+    let task = app.world().get::<Task>(cat).unwrap();
+    assert_eq!(task.action, Action::Work);
+    assert_eq!(task.destination, cell);
+
+    for _ in 0..300 {
+        app.update();
+        if !app.world().resource::<JobLog>().done.is_empty() {
+            break;
+        }
+    }
+    assert_eq!(app.world().resource::<JobLog>().done.len(), 1);
+    assert!(queue(&app).jobs.is_empty());
+}
+
+#[test]
+fn jobs_off_the_cat_layer_are_not_assigned() {
+    let mut app = new_app();
+    let cat = app.world_mut().spawn(worker(13)).id();
+    {
+        let mut queue = app.world_mut().resource_mut::<JobQueue>();
+        queue.designate(DesignationKind::Build, 1, UVec2::ZERO);
+        queue.designate(DesignationKind::Dig, 0, UVec2::ZERO);
+    }
+    app.update();
+    let states: Vec<JobState> = queue(&app).jobs.iter().map(|job| job.state).collect();
+    assert_eq!(states, vec![JobState::Pending, JobState::Assigned(cat)]);
+}
+
+#[test]
+fn unreachable_jobs_go_back_to_pending() {
+    let mut app = new_app_with_map(true);
+    let walled_in = UVec2::new(8, 8);
+    for cell in [(7, 8), (9, 8), (8, 7), (8, 9)] {
+        app.world_mut().trigger(SetCellFloorEvent {
+            layer: 0,
+            cell: cell.into(),
+            floor: GameMapCellFloor::None,
+        });
+    }
+    let cat = app
+        .world_mut()
+        .spawn((worker(13), Needs::default(), Transform::default()))
+        .id();
+    {
+        let mut queue = app.world_mut().resource_mut::<JobQueue>();
+        queue.designate(DesignationKind::Dig, 0, walled_in);
+        queue.designate(DesignationKind::Dig, 0, UVec2::new(2, 0));
+    }
+    app.update();
+    let job = queue(&app).jobs[0];
+    assert_eq!(job.state, JobState::Pending);
+    assert!(job.retry_at >= JOB_RETRY_TICKS);
+
+    for _ in 0..300 {
+        app.update();
+        if !app.world().resource::<JobLog>().done.is_empty() {
+            break;
+        }
+    }
+    let log = app.world().resource::<JobLog>();
+    assert_eq!(log.done.len(), 1, "the cat is free for other jobs");
+    assert_eq!((log.done[0].cat, log.done[0].cell), (cat, UVec2::new(2, 0)));
+    assert_eq!(queue(&app).jobs[0].cell, walled_in);
+}
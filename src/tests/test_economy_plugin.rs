@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+
+use crate::{
+    calendar_plugin::{Calendar, CalendarConfig, CalendarPlugin},
+    cat::{CatSheet, MagicSchool},
+    hauling_plugin::Stockpile,
+    item::{Inventory, ItemKind, ItemQuality},
+    student_plugin::{Education, Enrollment, Teacher},
+    test_utils::{sheet, simulation_app, start_game},
+};
+
+use super::{Commodity, EconomyConfig, EconomyPlugin, Ledger, LedgerReason, Treasury, holdings};
+
+fn new_app() -> App {
+    let mut app = simulation_app();
+    app.add_plugins((CalendarPlugin, EconomyPlugin));
+    // A season lasts two ticks
+    app.insert_resource(CalendarConfig {
+        ticks_per_day: 1,
+        days_per_season: 2,
+    });
+    start_game(&mut app);
+    app
+}
+
+fn run_until_season(app: &mut App, season: u32) {
+    while app.world().resource::<Calendar>().season_index() < season {
+        app.update();
+    }
+}
+
+fn student() -> (CatSheet, Enrollment) {
+    (
+        sheet(),
+        Enrollment {
+            enrolled_season: 0,
+            education: Education::General,
+            education_started: 0,
+        },
+    )
+}
+
+fn stockpile(items: &[(ItemKind, u32)]) -> (Stockpile, Inventory) {
+    let mut inventory = Inventory::default();
+    for (kind, count) in items {
+        inventory.add(*kind, ItemQuality::Common, *count);
+    }
+    (
+        Stockpile {
+            layer: 0,
+            cell: UVec2::ZERO,
+        },
+        inventory,
+    )
+}
+
+#[test]
+fn holdings_come_from_treasury_and_stockpiles() {
+    let treasury = Treasury { gold: -5, books: 3 };
+    let (_, a) = stockpile(&[(ItemKind::Mushroom, 4), (ItemKind::Crystal, 1)]);
+    let (_, b) = stockpile(&[(ItemKind::Mushroom, 2), (ItemKind::Herb, 7)]);
+    let all = holdings(&treasury, [&a, &b].into_iter());
+    let of = |commodity: Commodity| all[commodity as usize];
+    assert_eq!(of(Commodity::Food), 6);
+    assert_eq!(of(Commodity::Herbs), 7);
+    assert_eq!(of(Commodity::Gold), -5);
+    assert_eq!(of(Commodity::Books), 3);
+    assert_eq!(of(Commodity::ManaCrystals), 1);
+}
+
+#[test]
+fn seasons_are_settled() {
+    let mut app = new_app();
+    let config = EconomyConfig::default();
+    let gold = app.world().resource::<Treasury>().gold;
+    app.world_mut().spawn(student());
+    app.world_mut().spawn(student());
+    app.world_mut().spawn((
+        sheet(),
+        Teacher {
+            school: MagicSchool::Alchemy,
+        },
+    ));
+    let older = app
+        .world_mut()
+        .spawn(stockpile(&[(ItemKind::Mushroom, 4)]))
+        .id();
+    let newer = app
+        .world_mut()
+        .spawn(stockpile(&[(ItemKind::Mushroom, 20)]))
+        .id();
+
+    run_until_season(&mut app, 1);
+    let ledger = app.world().resource::<Ledger>();
+    assert_eq!(
+        ledger.net(0, Commodity::Gold),
+        2 * config.tuition - config.salary
+    );
+    assert_eq!(
+        ledger.net(0, Commodity::Food),
+        -3 * config.food_per_cat as i64
+    );
+    assert!(
+        ledger
+            .entries
+            .iter()
+            .any(|entry| entry.reason == LedgerReason::Salaries && entry.amount < 0)
+    );
+    assert_eq!(
+        app.world().resource::<Treasury>().gold,
+        gold + 2 * config.tuition - config.salary
+    );
+    let food = |app: &App, entity| {
+        app.world()
+            .get::<Inventory>(entity)
+            .unwrap()
+            .count(ItemKind::Mushroom)
+    };
+    assert_eq!(food(&app, older), 0, "oldest stockpile is emptied first");
+    assert_eq!(food(&app, newer), 24 - 15);
+
+    // Not enough food for another season
+    run_until_season(&mut app, 2);
+    let ledger = app.world().resource::<Ledger>();
+    assert_eq!(ledger.net(1, Commodity::Food), -9);
+    assert_eq!(
+        ledger.series(Commodity::Food),
+        vec![(0, 9), (1, 0)],
+        "history for charting"
+    );
+}
+
+#[test]
+fn history_is_limited() {
+    let mut app = new_app();
+    app.world_mut()
+        .resource_mut::<EconomyConfig>()
+        .history_seasons = 3;
+    app.world_mut().spawn(student());
+    run_until_season(&mut app, 5);
+    let ledger = app.world().resource::<Ledger>();
+    let seasons: Vec<u32> = ledger
+        .series(Commodity::Gold)
+        .iter()
+        .map(|(season, _)| *season)
+        .collect();
+    assert_eq!(seasons, vec![2, 3, 4]);
+    assert!(ledger.entries.iter().all(|entry| entry.season >= 2));
+    assert_eq!(
+        ledger.net(4, Commodity::Gold),
+        EconomyConfig::default().tuition
+    );
+}
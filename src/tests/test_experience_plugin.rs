@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+
+use crate::{
+    calendar_plugin::{Calendar, CalendarConfig, CalendarPlugin},
+    cat::{CatSheet, CatTrait, CatTraits, MagicSchool, SecondaryAttribute},
+    test_utils::{sheet, simulation_app},
+    timetable_plugin::{Class, LessonSlot, Timetable, TimetablePlugin},
+};
+
+use super::{
+    CLASS_XP_PER_TICK, Experience, ExperiencePlugin, LEVEL_XP, LevelUp, PRACTICE_XP_PER_TICK,
+    Practising, Skill, learning_gain, level_cap, xp_for_level,
+};
+
+/// Average cat without traits
+fn average_cat() -> CatSheet {
+    let mut sheet = sheet();
+    sheet.primary.strength = 12;
+    sheet.primary.intelligence = 10;
+    sheet.primary.luck = 12;
+    sheet.primary.agility = 12;
+    sheet.primary.magic = 15;
+    sheet.primary.charm = 12;
+    sheet.secondary.perception = 12;
+    sheet.secondary.willpower = 12;
+    sheet.traits = CatTraits::default();
+    sheet
+}
+
+#[test]
+fn level_thresholds_grow() {
+    assert_eq!(xp_for_level(0), 0);
+    assert_eq!(xp_for_level(1), LEVEL_XP);
+    assert_eq!(xp_for_level(2), 3 * LEVEL_XP);
+    assert_eq!(xp_for_level(3), 6 * LEVEL_XP);
+}
+
+#[test]
+fn attributes_and_traits_affect_caps_and_learning() {
+    let mut sheet = average_cat();
+    assert_eq!(
+        level_cap(&sheet, Skill::Attribute(SecondaryAttribute::Speed)),
+        4
+    );
+    // Magic 15 + charm 12 / 2
+    assert_eq!(level_cap(&sheet, Skill::School(MagicSchool::Evocation)), 7);
+    assert_eq!(learning_gain(&sheet, 10), 20);
+
+    sheet.traits = CatTraits(vec![CatTrait::Swifty, CatTrait::Dumby]);
+    assert_eq!(
+        level_cap(&sheet, Skill::Attribute(SecondaryAttribute::Speed)),
+        5
+    );
+    assert_eq!(learning_gain(&sheet, 10), 17);
+}
+
+#[test]
+fn gain_levels_up_and_stops_at_cap() {
+    let sheet = average_cat();
+    let skill = Skill::Attribute(SecondaryAttribute::Speed);
+    let mut experience = Experience::default();
+
+    // Intelligence 10 doubles the experience
+    assert_eq!(experience.gain(&sheet, skill, LEVEL_XP / 2 - 1), 0);
+    assert_eq!(experience.get(skill).level, 0);
+    assert_eq!(experience.gain(&sheet, skill, 1), 1);
+    assert_eq!(experience.get(skill).level, 1);
+    assert_eq!(experience.gain(&sheet, skill, 5 * LEVEL_XP / 2), 2);
+    assert_eq!(experience.get(skill).level, 3);
+
+    let cap = level_cap(&sheet, skill);
+    assert_eq!(experience.gain(&sheet, skill, 100 * LEVEL_XP), cap - 3);
+    assert_eq!(experience.get(skill).level, cap);
+    assert_eq!(experience.get(skill).xp, xp_for_level(cap));
+    assert_eq!(experience.gain(&sheet, skill, LEVEL_XP), 0);
+    assert_eq!(experience.get(skill).xp, xp_for_level(cap));
+
+    // Other skills are unaffected
+    assert_eq!(experience.get(Skill::School(MagicSchool::Alchemy)).xp, 0);
+}
+
+#[derive(Resource, Default)]
+struct LevelUpLog(Vec<LevelUp>);
+
+fn log_level_ups(mut evs: EventReader<LevelUp>, mut log: ResMut<LevelUpLog>) {
+    log.0.extend(evs.read().copied());
+}
+
+const LESSON: LessonSlot = LessonSlot {
+    weekday: 0,
+    lesson: 1,
+};
+
+fn new_app() -> App {
+    let mut app = simulation_app();
+    app.add_plugins((CalendarPlugin, TimetablePlugin, ExperiencePlugin));
+    // A lesson lasts one tick
+    app.insert_resource(CalendarConfig {
+        ticks_per_day: 4,
+        days_per_season: 28,
+    });
+    app.init_resource::<LevelUpLog>();
+    app.add_systems(Update, log_level_ups);
+    // Calendar of the game is inserted in Init
+    app.update();
+    app
+}
+
+fn run_until_day(app: &mut App, day: u32) {
+    while app.world().resource::<Calendar>().day < day {
+        app.update();
+    }
+}
+
+#[test]
+fn class_attendees_learn_its_school() {
+    let mut app = new_app();
+    let teacher = app.world_mut().spawn(average_cat()).id();
+    let student = app.world_mut().spawn(average_cat()).id();
+    let truant = app.world_mut().spawn(average_cat()).id();
+    app.insert_resource(Timetable {
+        classes: vec![Class {
+            school: MagicSchool::Divination,
+            teacher,
+            students: vec![student],
+            classroom: 0,
+            slot: LESSON,
+        }],
+    });
+    // Two weeks, two lessons
+    run_until_day(&mut app, 14);
+
+    let skill = Skill::School(MagicSchool::Divination);
+    let xp = |app: &App, cat| app.world().get::<Experience>(cat).unwrap().get(skill).xp;
+    let expected = 2 * learning_gain(&average_cat(), CLASS_XP_PER_TICK);
+    assert_eq!(xp(&app, teacher), expected);
+    assert_eq!(xp(&app, student), expected);
+    assert_eq!(xp(&app, truant), 0);
+    assert_eq!(
+        app.world()
+            .get::<Experience>(student)
+            .unwrap()
+            .get(Skill::School(MagicSchool::Alchemy))
+            .xp,
+        0
+    );
+}
+
+#[test]
+fn practice_levels_up_secondary_attribute() {
+    let mut app = new_app();
+    let skill = Skill::Attribute(SecondaryAttribute::Willpower);
+    let cat = app
+        .world_mut()
+        .spawn((average_cat(), Practising(skill)))
+        .id();
+    let willpower = average_cat().secondary.willpower;
+
+    let ticks_to_level = LEVEL_XP / learning_gain(&average_cat(), PRACTICE_XP_PER_TICK);
+    for _ in 0..ticks_to_level + 10 {
+        app.update();
+    }
+    let experience = app.world().get::<Experience>(cat).unwrap();
+    assert_eq!(experience.get(skill).level, 1);
+    let sheet = app.world().get::<CatSheet>(cat).unwrap();
+    assert_eq!(sheet.secondary.willpower, willpower + 1);
+    assert_eq!(
+        app.world().resource::<LevelUpLog>().0,
+        vec![LevelUp {
+            cat,
+            skill,
+            level: 1
+        }]
+    );
+}
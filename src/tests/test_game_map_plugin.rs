@@ -72,7 +72,7 @@ fn test_dummy_map_default_renderer_renders_layer_0() {
         let map_layer = app
             .world_mut()
             .query::<&GameMapLayerRenderer>()
-            .single(&app.world())
+            .single(app.world())
             .unwrap();
         assert_eq!(map_layer.0, 0);
     }
@@ -131,4 +131,4 @@ fn test_dummy_map_floor_material() {
 
 #[cfg(test)]
 #[path = "test_game_map_plugin_layer_view_shift.rs"]
-mod test_game_map_plugin;
+mod test_game_map_plugin_layer_view_shift;
@@ -2,7 +2,8 @@ use approx::assert_abs_diff_eq;
 use bevy::prelude::*;
 
 use crate::{
-    game_map_plugin::{GameMapLayerRenderer, GameMapPlugin},
+    camera_bookmarks_plugin::CameraBookmarksPlugin,
+    game_map_plugin::{GameMapLayerRenderer, GameMapPlugin, LayerObject},
     game_state_plugin::GameStatePlugin,
     player_control_plugin::PlayerControlPlugin,
     player_input_stage::PlayerInputStagesPlugin,
@@ -33,6 +34,7 @@ impl LayerViewShiftTestSuite {
             GameMapPlugin,
             PlayerInputStagesPlugin,
             PlayerControlPlugin,
+            CameraBookmarksPlugin,
         ));
 
         app.update();
@@ -40,7 +42,7 @@ impl LayerViewShiftTestSuite {
         let ground_renderer = app
             .world_mut()
             .query_filtered::<Entity, With<GameMapLayerRenderer>>()
-            .single(&app.world())
+            .single(app.world())
             .unwrap();
 
         Self {
@@ -140,3 +142,32 @@ fn layer_view_shift_goes_down_shift_must_be_pressed_first() {
         .release(KeyCode::ShiftRight)
         .press_then_release_w_assert(KeyCode::Period, -2.0);
 }
+
+#[test]
+fn camera_bookmark_restores_active_layer() {
+    LayerViewShiftTestSuite::new()
+        .press(KeyCode::ControlLeft)
+        .press_then_release_w_assert(KeyCode::Digit1, 0.0)
+        .release(KeyCode::ControlLeft)
+        .press(KeyCode::ShiftLeft)
+        .press_then_release_w_assert(KeyCode::Comma, -1.0)
+        .press_then_release_w_assert(KeyCode::Comma, -2.0)
+        .release(KeyCode::ShiftLeft)
+        .press_then_release_w_assert(KeyCode::Digit1, 0.0);
+}
+
+#[test]
+fn layer_objects_shift_with_the_layers() {
+    let mut suite = LayerViewShiftTestSuite::new()
+        .press(KeyCode::ShiftLeft)
+        .press_then_release_w_assert(KeyCode::Comma, -1.0);
+    let object = suite
+        .app
+        .world_mut()
+        .spawn((LayerObject(2), Transform::from_xyz(0.0, 2.5, 0.0)))
+        .id();
+    // Spawned while layer 1 is active
+    assert_abs_diff_eq!(get_position(&suite.app, object).y, 1.5);
+    suite = suite.press_then_release_w_assert(KeyCode::Period, 0.0);
+    assert_abs_diff_eq!(get_position(&suite.app, object).y, 2.5);
+}
@@ -1,14 +1,42 @@
 #![cfg(test)]
-use bevy::{prelude::*, state::app::StatesPlugin};
+use std::time::Duration;
 
-use crate::test_utils::{get_resource, is_entity_alive};
+use bevy::{prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy};
 
-use super::{GameObject, GameState, GameStatePlugin};
+use crate::{
+    calendar_plugin::{Calendar, CalendarPlugin},
+    camera_bookmarks_plugin::{
+        CAMERA_BOOKMARKS, CameraBookmark, CameraBookmarks, CameraBookmarksPlugin, CameraFollow,
+    },
+    cat::MagicSchool,
+    designation_plugin::{DesignationKind, DesignationPlugin, Job, JobQueue},
+    economy_plugin::{Commodity, EconomyPlugin, Ledger, LedgerEntry, LedgerReason, Treasury},
+    game_map_plugin::{GameMapData, GameMapPlugin, ShiftActiveLayerEvent},
+    hauling_plugin::{Haul, HaulState, HaulingPlugin, Hauls},
+    item::ItemKind,
+    light_plugin::LightPlugin,
+    player_control_plugin::PlayerCommand,
+    rng_plugin::{GameRng, RngPlugin},
+    simulation_clock_plugin::{SimulationClock, SimulationSpeed},
+    student_plugin::StudentPlugin,
+    test_utils::{get_resource, is_entity_alive, simulation_app},
+    timetable_plugin::{
+        Class, Classroom, Classrooms, LessonSlot, Timetable, TimetableConflict, TimetableConflicts,
+        TimetablePlugin,
+    },
+};
+
+use super::{
+    AfterUninit, GameObject, GameResourceAppExt, GameState, GameStatePlugin, Persistent,
+    leaked_entities,
+};
 
 #[derive(Resource, Default)]
 struct UninitCounter(u32);
 #[derive(Resource, Default)]
 struct InitCounter(u32);
+#[derive(Resource, Default, Debug, PartialEq)]
+struct Score(u32);
 
 fn make_app() -> App {
     let mut app = App::new();
@@ -180,3 +208,242 @@ fn test_uninit_auto_despawn_game_objects() {
         assert_eq!(state.0, 1);
     }
 }
+
+#[test]
+fn test_uninit_can_return_to_menu() {
+    let mut app = make_app();
+    let ent: Entity = app.world_mut().spawn(GameObject).id();
+    app.update(); // Init
+    app.update(); // Game
+    app.insert_resource(AfterUninit(GameState::MainMenu));
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Uninit);
+    app.update();
+    assert!(!is_entity_alive(&app, ent));
+    for _ in 0..3 {
+        app.update();
+        let state = get_resource::<State<GameState>>(&app);
+        assert_eq!(*state, GameState::MainMenu);
+    }
+    // Target is reset for the next time
+    assert_eq!(
+        *get_resource::<AfterUninit>(&app),
+        AfterUninit(GameState::Init)
+    );
+}
+
+fn leave_game(app: &mut App, after: GameState) {
+    app.insert_resource(AfterUninit(after));
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Uninit);
+    app.update();
+    app.update();
+}
+
+#[test]
+fn test_game_resources_are_fresh_in_every_game() {
+    let mut app = make_app();
+    app.init_game_resource::<Score>();
+    app.update(); // Init
+    app.update(); // Game
+    app.world_mut().resource_mut::<Score>().0 = 7;
+
+    leave_game(&mut app, GameState::Init);
+    app.update(); // Game
+    assert_eq!(*get_resource::<Score>(&app), Score(0));
+
+    app.world_mut().resource_mut::<Score>().0 = 7;
+    leave_game(&mut app, GameState::MainMenu);
+    assert!(!app.world().contains_resource::<Score>());
+}
+
+#[test]
+fn test_game_resources_inserted_beforehand_are_kept() {
+    let mut app = make_app();
+    app.init_game_resource::<Score>();
+    app.insert_resource(Score(3));
+    app.update();
+    assert_eq!(*get_resource::<Score>(&app), Score(3));
+}
+
+#[test]
+fn test_entities_left_after_uninit_are_found() {
+    let mut app = make_app();
+    let before_game = app.world_mut().spawn_empty().id();
+    app.update(); // Init
+    app.update(); // Game
+    let leaked = app.world_mut().spawn(Name::new("Leaked")).id();
+    app.world_mut().spawn(GameObject).with_child(());
+    app.world_mut().spawn(Persistent);
+    app.world_mut()
+        .add_observer(|_: Trigger<OnAdd, GameObject>| {});
+
+    let found = app.world_mut().run_system_cached(leaked_entities).unwrap();
+    assert_eq!(found, vec![leaked]);
+    assert!(!found.contains(&before_game));
+}
+
+/// Everything a restart must bring back to how the game started
+#[derive(Debug, PartialEq)]
+struct WorldSnapshot {
+    entities: usize,
+    names: Vec<String>,
+    /// (layers, width, height)
+    map_size: (usize, usize, usize),
+    current_layer: usize,
+    rng_word_pos: u128,
+    calendar: Calendar,
+    treasury: Treasury,
+    ledger: Ledger,
+    jobs: Vec<Job>,
+    hauls: Vec<Haul>,
+    /// (tick, paused, speed)
+    clock: (u64, bool, SimulationSpeed),
+    classes: Vec<Class>,
+    classrooms: Vec<Classroom>,
+    conflicts: Vec<TimetableConflict>,
+    camera_follow: Option<Entity>,
+    bookmarks: [Option<CameraBookmark>; CAMERA_BOOKMARKS],
+}
+
+fn world_snapshot(app: &mut App) -> WorldSnapshot {
+    let mut names: Vec<String> = app
+        .world_mut()
+        .query::<&Name>()
+        .iter(app.world())
+        .map(|name| name.to_string())
+        .collect();
+    names.sort();
+    let world = app.world();
+    let map_data = world.resource::<GameMapData>();
+    let map = map_data.map();
+    WorldSnapshot {
+        entities: world.entities().len() as usize,
+        names,
+        map_size: (map.layers, map.width, map.height),
+        current_layer: map_data.current_layer(),
+        rng_word_pos: world.resource::<GameRng>().get_word_pos(),
+        calendar: *world.resource::<Calendar>(),
+        treasury: *world.resource::<Treasury>(),
+        ledger: world.resource::<Ledger>().clone(),
+        jobs: world.resource::<JobQueue>().jobs.clone(),
+        hauls: world.resource::<Hauls>().0.clone(),
+        clock: {
+            let clock = world.resource::<SimulationClock>();
+            (clock.tick, clock.paused, clock.speed)
+        },
+        classes: world.resource::<Timetable>().classes.clone(),
+        classrooms: world.resource::<Classrooms>().0.clone(),
+        conflicts: world.resource::<TimetableConflicts>().0.clone(),
+        camera_follow: world.resource::<CameraFollow>().0,
+        bookmarks: world.resource::<CameraBookmarks>().0,
+    }
+}
+
+/// Change what the simulation would change while the game is played,
+/// the camera follows the entity which lives through restarts
+fn play(app: &mut App, followed: Entity) {
+    let world = app.world_mut();
+    world.trigger(ShiftActiveLayerEvent(1));
+    world.resource_mut::<Calendar>().day += 1;
+    world.resource_mut::<Treasury>().gold -= 50;
+    world.resource_mut::<Ledger>().entries.push(LedgerEntry {
+        season: 0,
+        reason: LedgerReason::Salaries,
+        commodity: Commodity::Gold,
+        amount: -50,
+    });
+    world
+        .resource_mut::<JobQueue>()
+        .designate(DesignationKind::Dig, 0, UVec2::ONE);
+    let site = world.spawn_empty().id();
+    world.resource_mut::<Hauls>().0.push(Haul {
+        from: site,
+        to: site,
+        layer: 0,
+        from_cell: UVec2::ZERO,
+        to_cell: UVec2::ONE,
+        kind: ItemKind::Herb,
+        count: 1,
+        state: HaulState::Waiting,
+    });
+    let mut clock = world.resource_mut::<SimulationClock>();
+    clock.tick += 10;
+    clock.step();
+    clock.speed = SimulationSpeed::Fastest;
+    world.resource_mut::<Timetable>().classes.push(Class {
+        school: MagicSchool::Alchemy,
+        teacher: site,
+        students: vec![],
+        classroom: 0,
+        slot: LessonSlot {
+            weekday: 0,
+            lesson: 0,
+        },
+    });
+    world.resource_mut::<Classrooms>().0.clear();
+    world
+        .resource_mut::<TimetableConflicts>()
+        .0
+        .push(TimetableConflict::UnknownClassroom { class: 0 });
+    world.resource_mut::<CameraFollow>().0 = Some(followed);
+    world.resource_mut::<CameraBookmarks>().0[0] = Some(CameraBookmark {
+        transform: Transform::IDENTITY,
+        layer: 1,
+    });
+    world.despawn(site);
+}
+
+#[test]
+fn test_restarting_leaks_no_entities_or_resources() {
+    let mut app = simulation_app();
+    app.add_plugins((
+        AssetPlugin::default(),
+        RngPlugin { seed: Some(1) },
+        CalendarPlugin,
+        EconomyPlugin,
+        DesignationPlugin,
+        HaulingPlugin,
+        TimetablePlugin,
+        CameraBookmarksPlugin,
+    ));
+    // Render assets without rendering, the map makes its materials on build
+    app.init_asset::<Mesh>();
+    app.init_asset::<StandardMaterial>();
+    app.init_asset::<Scene>();
+    app.add_plugins((GameMapPlugin, LightPlugin, StudentPlugin));
+    // Nothing changes between the restarts but what `play` changes
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+    let followed = app
+        .world_mut()
+        .spawn((Persistent, Transform::default()))
+        .id();
+    app.update();
+    app.update();
+    assert_eq!(*get_resource::<State<GameState>>(&app), GameState::Game);
+    let started = world_snapshot(&mut app);
+    assert!(started.names.contains(&"Player Camera".to_string()));
+
+    for _ in 0..3 {
+        play(&mut app, followed);
+        assert_ne!(world_snapshot(&mut app), started);
+        app.world_mut().send_event(PlayerCommand::RestartGame);
+        let mut states = vec![];
+        for _ in 0..4 {
+            app.update();
+            states.push(*get_resource::<State<GameState>>(&app).get());
+        }
+        assert_eq!(
+            states,
+            vec![
+                GameState::Uninit,
+                GameState::Init,
+                GameState::Game,
+                GameState::Game
+            ]
+        );
+        assert_eq!(world_snapshot(&mut app), started);
+    }
+}
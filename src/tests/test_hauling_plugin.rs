@@ -0,0 +1,354 @@
+use bevy::prelude::*;
+
+use crate::{
+    alchemy_plugin::{AlchemyPlugin, OrderCraft, PlaceWorkstation, StationKind, Workstation},
+    calendar_plugin::CalendarPlugin,
+    cat::CatSheet,
+    cat_ai_plugin::CatAiPlugin,
+    designation_plugin::{DesignationKind, DesignationPlugin, JobQueue},
+    item::{CARRY_PER_STRENGTH, Inventory, ItemKind, ItemQuality},
+    needs_plugin::Needs,
+    rng_plugin::RngPlugin,
+    test_utils::{sheet, simulation_app, start_game},
+    timetable_plugin::TimetablePlugin,
+};
+
+use super::{HaulState, Hauled, HaulingPlugin, Hauls, PlaceStockpile, RequestHaul, Stockpile};
+
+#[derive(Resource, Default)]
+struct HaulLog(Vec<Hauled>);
+
+fn log_hauls(mut evs: EventReader<Hauled>, mut log: ResMut<HaulLog>) {
+    log.0.extend(evs.read().copied());
+}
+
+fn new_app() -> App {
+    let mut app = simulation_app();
+    app.add_plugins((
+        RngPlugin { seed: Some(1) },
+        CalendarPlugin,
+        TimetablePlugin,
+        CatAiPlugin,
+        DesignationPlugin,
+        AlchemyPlugin,
+        HaulingPlugin,
+    ));
+    app.init_resource::<HaulLog>();
+    app.add_systems(Update, log_hauls);
+    start_game(&mut app);
+    app
+}
+
+fn hauler(strength: i32) -> (CatSheet, Needs, Transform) {
+    let mut sheet = sheet();
+    sheet.primary.strength = strength;
+    sheet.primary.intelligence = 1;
+    sheet.primary.agility = 1;
+    (sheet, Needs::default(), Transform::default())
+}
+
+fn stockpile(app: &mut App, cell: UVec2) -> Entity {
+    app.world_mut().trigger(PlaceStockpile { layer: 0, cell });
+    app.update();
+    let mut stockpiles = app.world_mut().query::<(Entity, &Stockpile)>();
+    stockpiles
+        .iter(app.world())
+        .find(|(_, stockpile)| stockpile.cell == cell)
+        .map(|(entity, _)| entity)
+        .unwrap()
+}
+
+fn hauls(app: &App) -> &[super::Haul] {
+    &app.world().resource::<Hauls>().0
+}
+
+#[test]
+fn hauls_need_two_sites_on_a_layer() {
+    let mut app = new_app();
+    let a = stockpile(&mut app, UVec2::new(0, 0));
+    let again = stockpile(&mut app, UVec2::new(0, 0));
+    assert_eq!(a, again, "one stockpile per cell");
+    let b = stockpile(&mut app, UVec2::new(2, 0));
+    let cat = app.world_mut().spawn(hauler(10)).id();
+    let request = |from, to, count| RequestHaul {
+        from,
+        to,
+        kind: ItemKind::Herb,
+        count,
+    };
+
+    for ev in [request(a, a, 1), request(a, cat, 1), request(a, b, 0)] {
+        app.world_mut().trigger(ev);
+    }
+    app.update();
+    assert!(hauls(&app).is_empty());
+
+    app.world_mut().trigger(request(a, b, 3));
+    app.update();
+    assert_eq!(hauls(&app).len(), 1);
+    assert_eq!(hauls(&app)[0].state, HaulState::Waiting, "nothing to haul");
+    assert!(app.world().resource::<JobQueue>().jobs.is_empty());
+}
+
+#[test]
+fn strength_limits_each_trip() {
+    let mut app = new_app();
+    let from = stockpile(&mut app, UVec2::new(0, 0));
+    app.world_mut().trigger(PlaceWorkstation {
+        kind: StationKind::Cauldron,
+        layer: 0,
+        cell: UVec2::new(3, 0),
+    });
+    app.update();
+    let mut stations = app
+        .world_mut()
+        .query_filtered::<Entity, With<Workstation>>();
+    let to = stations.single(app.world()).unwrap();
+    app.world_mut().get_mut::<Inventory>(from).unwrap().add(
+        ItemKind::Crystal,
+        ItemQuality::Common,
+        10,
+    );
+    // Four crystals per trip
+    let cat = app.world_mut().spawn(hauler(10)).id();
+    assert_eq!(10 * CARRY_PER_STRENGTH / ItemKind::Crystal.weight(), 4);
+    app.world_mut().trigger(RequestHaul {
+        from,
+        to,
+        kind: ItemKind::Crystal,
+        count: 9,
+    });
+    app.update();
+    let job = *app
+        .world()
+        .resource::<JobQueue>()
+        .at(0, UVec2::ZERO)
+        .unwrap();
+    assert_eq!(job.kind, DesignationKind::Haul);
+
+    for _ in 0..5000 {
+        app.update();
+        if hauls(&app).is_empty() {
+            break;
+        }
+    }
+    let trips: Vec<u32> = app
+        .world()
+        .resource::<HaulLog>()
+        .0
+        .iter()
+        .map(|hauled| hauled.count)
+        .collect();
+    assert_eq!(trips, vec![4, 4, 1]);
+    assert!(hauls(&app).is_empty());
+    let count = |entity| {
+        app.world()
+            .get::<Inventory>(entity)
+            .unwrap()
+            .count(ItemKind::Crystal)
+    };
+    assert_eq!(count(from), 1);
+    assert_eq!(count(to), 9);
+    assert_eq!(count(cat), 0);
+}
+
+#[test]
+fn cancelled_haul_is_dropped() {
+    let mut app = new_app();
+    let from = stockpile(&mut app, UVec2::new(1, 0));
+    let to = stockpile(&mut app, UVec2::new(2, 0));
+    app.world_mut()
+        .get_mut::<Inventory>(from)
+        .unwrap()
+        .add(ItemKind::Herb, ItemQuality::Common, 2);
+    app.world_mut().trigger(RequestHaul {
+        from,
+        to,
+        kind: ItemKind::Herb,
+        count: 2,
+    });
+    app.update();
+    assert!(matches!(hauls(&app)[0].state, HaulState::PickingUp(_)));
+    app.world_mut()
+        .resource_mut::<JobQueue>()
+        .cancel(0, UVec2::new(1, 0));
+    app.update();
+    assert!(hauls(&app).is_empty());
+    assert_eq!(
+        app.world()
+            .get::<Inventory>(from)
+            .unwrap()
+            .count(ItemKind::Herb),
+        2
+    );
+}
+
+#[test]
+fn crafting_waits_for_deliveries() {
+    let mut app = new_app();
+    let from = stockpile(&mut app, UVec2::new(0, 0));
+    let cell = UVec2::new(3, 0);
+    app.world_mut().trigger(PlaceWorkstation {
+        kind: StationKind::Cauldron,
+        layer: 0,
+        cell,
+    });
+    app.update();
+    let mut stations = app
+        .world_mut()
+        .query_filtered::<Entity, With<Workstation>>();
+    let to = stations.single(app.world()).unwrap();
+    for _ in 0..2 {
+        app.world_mut().trigger(OrderCraft {
+            station: to,
+            recipe: "Willpower Draught".into(),
+        });
+    }
+    app.world_mut().get_mut::<Inventory>(from).unwrap().add(
+        ItemKind::Crystal,
+        ItemQuality::Common,
+        6,
+    );
+    // Three crystals per trip, the first order is crafted between the trips
+    let mut alchemist = hauler(8);
+    alchemist.0.primary.intelligence = 20;
+    let cat = app.world_mut().spawn(alchemist).id();
+    app.world_mut().trigger(RequestHaul {
+        from,
+        to,
+        kind: ItemKind::Crystal,
+        count: 6,
+    });
+    for _ in 0..5000 {
+        app.update();
+        if matches!(hauls(&app)[0].state, HaulState::Delivering { .. }) {
+            break;
+        }
+    }
+    assert!(matches!(hauls(&app)[0].state, HaulState::Delivering { .. }));
+    // The first order becomes ready while the cat brings the first batch
+    let mut inventory = app.world_mut().get_mut::<Inventory>(to).unwrap();
+    inventory.add(ItemKind::Crystal, ItemQuality::Common, 1);
+    inventory.add(ItemKind::Herb, ItemQuality::Common, 2);
+    app.update();
+    let queue = app.world().resource::<JobQueue>();
+    assert_eq!(queue.at(0, cell).unwrap().kind, DesignationKind::Haul);
+
+    for _ in 0..5000 {
+        app.update();
+        if hauls(&app).is_empty() {
+            break;
+        }
+    }
+    app.update();
+    let trips: Vec<u32> = app
+        .world()
+        .resource::<HaulLog>()
+        .0
+        .iter()
+        .map(|hauled| hauled.count)
+        .collect();
+    assert_eq!(trips, vec![3, 3]);
+    let count = |entity| {
+        app.world()
+            .get::<Inventory>(entity)
+            .unwrap()
+            .count(ItemKind::Crystal)
+    };
+    assert_eq!(count(cat), 0, "nothing is left with the hauler");
+    // Only the first order took its crystal, the second one waits for herbs
+    assert_eq!(count(to), 6);
+}
+
+#[test]
+fn found_items_go_to_the_closest_stockpile() {
+    let mut app = new_app();
+    let far = stockpile(&mut app, UVec2::new(4, 0));
+    let near = stockpile(&mut app, UVec2::new(2, 0));
+    let mut digger = hauler(15);
+    digger.0.primary.agility = 15;
+    let cat = app.world_mut().spawn(digger).id();
+    {
+        let mut queue = app.world_mut().resource_mut::<JobQueue>();
+        queue.designate(DesignationKind::Dig, 0, UVec2::new(0, 0));
+        queue.designate(DesignationKind::ClearGrass, 0, UVec2::new(1, 0));
+    }
+    for _ in 0..5000 {
+        app.update();
+        if app.world().resource::<HaulLog>().0.len() == 2 {
+            break;
+        }
+    }
+    let log = &app.world().resource::<HaulLog>().0;
+    assert_eq!(log.len(), 2);
+    assert!(
+        log.iter()
+            .all(|hauled| hauled.from == cat && hauled.to == near)
+    );
+    let inventory = app.world().get::<Inventory>(near).unwrap();
+    assert_eq!(inventory.count(ItemKind::Crystal), 1);
+    assert_eq!(inventory.0.iter().map(|stack| stack.count).sum::<u32>(), 2);
+    assert!(app.world().get::<Inventory>(far).unwrap().0.is_empty());
+    assert!(app.world().get::<Inventory>(cat).unwrap().0.is_empty());
+}
+
+#[test]
+fn workstations_get_the_ingredients_of_their_orders() {
+    let mut app = new_app();
+    let near = stockpile(&mut app, UVec2::new(2, 0));
+    let far = stockpile(&mut app, UVec2::new(0, 0));
+    app.world_mut()
+        .get_mut::<Inventory>(near)
+        .unwrap()
+        .add(ItemKind::Herb, ItemQuality::Common, 1);
+    let mut inventory = app.world_mut().get_mut::<Inventory>(far).unwrap();
+    inventory.add(ItemKind::Herb, ItemQuality::Common, 3);
+    inventory.add(ItemKind::Mushroom, ItemQuality::Common, 2);
+    app.world_mut().trigger(PlaceWorkstation {
+        kind: StationKind::Cauldron,
+        layer: 0,
+        cell: UVec2::new(3, 0),
+    });
+    app.update();
+    let mut stations = app
+        .world_mut()
+        .query_filtered::<Entity, With<Workstation>>();
+    let station = stations.single(app.world()).unwrap();
+    app.world_mut().trigger(OrderCraft {
+        station,
+        recipe: "Stamina Potion".into(),
+    });
+    app.update();
+    let mut requested: Vec<(Entity, ItemKind, u32)> = hauls(&app)
+        .iter()
+        .map(|haul| (haul.from, haul.kind, haul.count))
+        .collect();
+    requested.sort_by_key(|(from, kind, _)| (*kind, *from));
+    assert_eq!(
+        requested,
+        vec![
+            (near, ItemKind::Herb, 1),
+            (far, ItemKind::Herb, 1),
+            (far, ItemKind::Mushroom, 1)
+        ]
+    );
+    app.update();
+    assert_eq!(hauls(&app).len(), 3, "requested hauls are counted");
+
+    // The hauler can't craft, so the ingredients wait in the job
+    app.world_mut().spawn(hauler(15));
+    for _ in 0..5000 {
+        app.update();
+        if hauls(&app).is_empty() {
+            break;
+        }
+    }
+    app.update();
+    let station = app.world().get::<Workstation>(station).unwrap();
+    assert!(station.orders.is_empty());
+    assert!(station.crafting.is_some());
+    let count = |entity, kind| app.world().get::<Inventory>(entity).unwrap().count(kind);
+    assert_eq!(count(near, ItemKind::Herb), 0);
+    assert_eq!(count(far, ItemKind::Herb), 2);
+    assert_eq!(count(far, ItemKind::Mushroom), 1);
+}
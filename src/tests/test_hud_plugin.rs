@@ -0,0 +1,40 @@
+use crate::{
+    calendar_plugin::{Calendar, Season},
+    cat::{CatTrait, CatTraits, PrimaryAttribute},
+    economy_plugin::Commodity,
+    simulation_clock_plugin::{SimulationClock, SimulationSpeed},
+    test_utils::sheet,
+};
+
+use super::{attribute_label, commodity_label, date_label, speed_label};
+
+#[test]
+fn labels_read_well() {
+    let calendar = Calendar {
+        tick_of_day: 5,
+        day: 0,
+        season: Season::Autumn,
+        year: 2,
+    };
+    assert_eq!(date_label(&calendar), "Autumn, day 1, year 2");
+    assert_eq!(commodity_label(Commodity::ManaCrystals), "Mana crystals");
+
+    let mut clock = SimulationClock::default();
+    clock.speed = SimulationSpeed::Fastest;
+    assert_eq!(speed_label(&clock), "Speed x5");
+    clock.paused = true;
+    assert_eq!(speed_label(&clock), "Paused");
+}
+
+#[test]
+fn attributes_show_trait_modifiers() {
+    let mut sheet = sheet();
+    sheet.primary.strength = 12;
+    sheet.primary.charm = 7;
+    sheet.traits = CatTraits(vec![CatTrait::Mighty]);
+    assert_eq!(
+        attribute_label(&sheet, PrimaryAttribute::Strength),
+        "Strength 12 (+3)"
+    );
+    assert_eq!(attribute_label(&sheet, PrimaryAttribute::Charm), "Charm 7");
+}
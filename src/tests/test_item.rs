@@ -0,0 +1,79 @@
+use crate::{cat::SecondaryAttribute, needs_plugin::Need, test_utils::sheet};
+
+use super::{
+    CARRY_PER_STRENGTH, Inventory, ItemEffect, ItemKind, ItemQuality, carry_capacity,
+    overload_speed,
+};
+
+#[test]
+fn stacks_merge_by_kind_and_quality() {
+    let mut inventory = Inventory::default();
+    inventory.add(ItemKind::Herb, ItemQuality::Common, 2);
+    inventory.add(ItemKind::Crystal, ItemQuality::Common, 1);
+    inventory.add(ItemKind::Herb, ItemQuality::Fine, 1);
+    inventory.add(ItemKind::Herb, ItemQuality::Common, 3);
+    inventory.add(ItemKind::Feather, ItemQuality::Common, 0);
+    assert_eq!(inventory.0.len(), 3);
+    assert_eq!(inventory.count(ItemKind::Herb), 6);
+    assert_eq!(inventory.count(ItemKind::Feather), 0);
+}
+
+#[test]
+fn worst_items_are_used_up_first() {
+    let mut inventory = Inventory::default();
+    inventory.add(ItemKind::Herb, ItemQuality::Fine, 2);
+    inventory.add(ItemKind::Herb, ItemQuality::Crude, 1);
+    assert!(!inventory.remove(ItemKind::Herb, 4));
+    assert_eq!(inventory.count(ItemKind::Herb), 3);
+    assert!(inventory.remove(ItemKind::Herb, 2));
+    assert_eq!(inventory.0.len(), 1);
+    assert_eq!(inventory.0[0].quality, ItemQuality::Fine);
+    assert_eq!(inventory.take_best(ItemKind::Herb), Some(ItemQuality::Fine));
+    assert_eq!(inventory.take_best(ItemKind::Herb), None);
+    assert!(inventory.0.is_empty());
+}
+
+#[test]
+fn quality_scales_effects() {
+    assert_eq!(ItemQuality::from_margin(1), ItemQuality::Crude);
+    assert_eq!(ItemQuality::from_margin(4), ItemQuality::Common);
+    assert_eq!(ItemQuality::from_margin(11), ItemQuality::Fine);
+    assert_eq!(ItemQuality::from_margin(30), ItemQuality::Superb);
+    assert_eq!(ItemKind::Herb.effect(), None);
+    let potion = ItemKind::StaminaPotion.effect().unwrap();
+    assert_eq!(
+        ItemQuality::Superb.scale(potion),
+        ItemEffect::RestoreNeed(Need::Energy, 80.0)
+    );
+    let draught = ItemKind::WillpowerDraught.effect().unwrap();
+    assert_eq!(
+        ItemQuality::Fine.scale(draught),
+        ItemEffect::RaiseAttribute(SecondaryAttribute::Willpower, 2)
+    );
+}
+
+#[test]
+fn strong_cats_carry_more() {
+    let mut sheet = sheet();
+    sheet.primary.strength = 10;
+    assert_eq!(carry_capacity(&sheet), 10 * CARRY_PER_STRENGTH);
+    sheet.primary.strength = -3;
+    assert_eq!(carry_capacity(&sheet), 0);
+
+    let mut inventory = Inventory::default();
+    inventory.add(ItemKind::Crystal, ItemQuality::Fine, 2);
+    inventory.add(ItemKind::Crystal, ItemQuality::Crude, 1);
+    inventory.add(ItemKind::Herb, ItemQuality::Common, 4);
+    assert_eq!(inventory.weight(), 3 * 5 + 4);
+    let taken = inventory.take(ItemKind::Crystal, 2);
+    assert_eq!(
+        taken.iter().map(|stack| stack.quality).collect::<Vec<_>>(),
+        vec![ItemQuality::Crude, ItemQuality::Fine]
+    );
+    assert_eq!(inventory.take(ItemKind::Crystal, 5)[0].count, 1);
+    assert!(inventory.take(ItemKind::Crystal, 5).is_empty());
+
+    assert_eq!(overload_speed(20, 20), 1.0);
+    assert_eq!(overload_speed(40, 20), 0.5);
+    assert_eq!(overload_speed(40, 0), 0.25);
+}
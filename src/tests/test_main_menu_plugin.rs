@@ -0,0 +1,51 @@
+use bevy::{prelude::*, state::app::StatesPlugin};
+use rand::Rng;
+
+use crate::{
+    game_state_plugin::{GameState, GameStatePlugin},
+    rng_plugin::GameRng,
+    test_utils::get_resource,
+};
+
+use super::{NewGameSettings, StartNewGame, start_new_game};
+
+#[test]
+fn founders_are_reproducible() {
+    let settings = NewGameSettings::new(7);
+    assert_eq!(settings, NewGameSettings::new(7));
+    assert_eq!(settings.map_size, UVec2::splat(10));
+
+    let mut rerolled = settings.clone();
+    rerolled.reroll_founder();
+    assert_ne!(rerolled.founder, settings.founder);
+    let mut again = NewGameSettings::new(7);
+    again.reroll_founder();
+    assert_eq!(again, rerolled);
+
+    rerolled.map_size = UVec2::new(20, 12);
+    rerolled.set_seed(7);
+    assert_eq!(rerolled.founder, settings.founder, "seed starts over");
+    assert_eq!(rerolled.map_size, UVec2::new(20, 12));
+}
+
+#[test]
+fn new_game_leaves_the_menu() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin, GameStatePlugin));
+    app.insert_state(GameState::MainMenu);
+    app.insert_resource(NewGameSettings::new(7));
+    app.insert_resource(GameRng::from_seed(1));
+    app.add_observer(start_new_game);
+    for _ in 0..3 {
+        app.update();
+        assert_eq!(*get_resource::<State<GameState>>(&app), GameState::MainMenu);
+    }
+
+    app.world_mut().trigger(StartNewGame);
+    app.update();
+    assert_eq!(*get_resource::<State<GameState>>(&app), GameState::Init);
+    app.update();
+    assert_eq!(*get_resource::<State<GameState>>(&app), GameState::Game);
+    let drawn: u64 = app.world_mut().resource_mut::<GameRng>().r#gen();
+    assert_eq!(drawn, GameRng::from_seed(7).r#gen::<u64>());
+}
@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+
+use crate::{
+    main_menu_plugin::MIN_MAP_SIZE,
+    test_utils::{sheet, simulation_app, start_game},
+};
+
+use super::{
+    AMENITIES, NEED_LOW, NEED_MAX, Need, NeedLevel, NeedLevelChanged, NeedSource, Needs,
+    NeedsPlugin, Using,
+};
+
+#[derive(Resource, Default)]
+struct NeedLog(Vec<NeedLevelChanged>);
+
+fn log_needs(mut evs: EventReader<NeedLevelChanged>, mut log: ResMut<NeedLog>) {
+    log.0.extend(evs.read().copied());
+}
+
+fn new_app() -> App {
+    let mut app = simulation_app();
+    app.add_plugins(NeedsPlugin);
+    app.init_resource::<NeedLog>();
+    app.add_systems(Update, log_needs);
+    start_game(&mut app);
+    app
+}
+
+fn run(app: &mut App, frames: usize) {
+    for _ in 0..frames {
+        app.update();
+    }
+}
+
+fn needs(app: &App, cat: Entity) -> Needs {
+    app.world().get::<Needs>(cat).unwrap().clone()
+}
+
+#[test]
+fn attributes_affect_decay() {
+    let mut hardy = sheet();
+    hardy.secondary.constitution = 26;
+    let mut frail = sheet();
+    frail.secondary.constitution = 6;
+    assert!(Need::Energy.decay(Some(&hardy)) < Need::Energy.decay(None));
+    assert!(Need::Energy.decay(Some(&frail)) > Need::Energy.decay(None));
+
+    let mut charming = sheet();
+    charming.primary.charm = 26;
+    assert!(Need::Social.decay(Some(&charming)) > Need::Social.decay(None));
+    assert_eq!(
+        Need::Hunger.decay(Some(&charming)),
+        Need::Hunger.decay(None)
+    );
+}
+
+#[test]
+fn cats_get_needs_which_decay() {
+    let mut app = new_app();
+    let cat = app.world_mut().spawn((sheet(), Transform::default())).id();
+    assert_eq!(needs(&app, cat), Needs::default());
+    run(&mut app, 10);
+    let needs = needs(&app, cat);
+    for need in Need::ALL {
+        assert!(needs.get(need) < NEED_MAX);
+    }
+    assert!(app.world().resource::<NeedLog>().0.is_empty());
+}
+
+#[test]
+fn crossing_threshold_sends_event() {
+    let mut app = new_app();
+    let cat = app
+        .world_mut()
+        .spawn((
+            Needs {
+                hunger: NEED_LOW + 0.01,
+                ..default()
+            },
+            Transform::default(),
+        ))
+        .id();
+    run(&mut app, 3);
+    assert_eq!(needs(&app, cat).level(Need::Hunger), NeedLevel::Low);
+    assert_eq!(
+        app.world().resource::<NeedLog>().0,
+        vec![NeedLevelChanged {
+            cat,
+            need: Need::Hunger,
+            level: NeedLevel::Low,
+        }]
+    );
+}
+
+#[test]
+fn using_source_restores_need_until_full() {
+    let mut app = new_app();
+    let bowl = app
+        .world_mut()
+        .spawn((
+            NeedSource {
+                need: Need::Hunger,
+                per_tick: 5.0,
+            },
+            Transform::from_xyz(1.0, 0.7, 0.0),
+        ))
+        .id();
+    let cat = app
+        .world_mut()
+        .spawn((
+            Needs {
+                hunger: 50.0,
+                ..default()
+            },
+            Using(bowl),
+            Transform::default(),
+        ))
+        .id();
+    run(&mut app, 3);
+    assert!(needs(&app, cat).hunger > 50.0);
+    assert!(app.world().get::<Using>(cat).is_some());
+
+    // Cat stops eating once full and the need starts decaying again
+    run(&mut app, 20);
+    assert!(needs(&app, cat).hunger > NEED_MAX - 1.0);
+    assert!(app.world().get::<Using>(cat).is_none());
+}
+
+#[test]
+fn source_out_of_reach_is_not_used() {
+    let mut app = new_app();
+    let bowl = app
+        .world_mut()
+        .spawn((
+            NeedSource {
+                need: Need::Hunger,
+                per_tick: 5.0,
+            },
+            Transform::from_xyz(5.0, 0.0, 5.0),
+        ))
+        .id();
+    let cat = app
+        .world_mut()
+        .spawn((
+            Needs {
+                hunger: 50.0,
+                ..default()
+            },
+            Using(bowl),
+            Transform::default(),
+        ))
+        .id();
+    run(&mut app, 3);
+    assert!(needs(&app, cat).hunger < 50.0);
+    assert!(app.world().get::<Using>(cat).is_none());
+}
+
+#[test]
+fn company_restores_social_need() {
+    let mut app = new_app();
+    let lonely = Needs {
+        social: 50.0,
+        ..default()
+    };
+    let alone = app
+        .world_mut()
+        .spawn((lonely.clone(), Transform::from_xyz(9.0, 0.0, 9.0)))
+        .id();
+    let a = app
+        .world_mut()
+        .spawn((lonely.clone(), Transform::from_xyz(0.0, 0.0, 0.0)))
+        .id();
+    let b = app
+        .world_mut()
+        .spawn((lonely, Transform::from_xyz(1.0, 0.0, 0.0)))
+        .id();
+    run(&mut app, 10);
+    assert!(needs(&app, alone).social < 50.0);
+    assert!(needs(&app, a).social > 50.0);
+    assert!(needs(&app, b).social > 50.0);
+}
+
+#[test]
+fn amenities_are_spawned() {
+    let mut app = new_app();
+    let needs: Vec<Need> = app
+        .world_mut()
+        .query::<&NeedSource>()
+        .iter(app.world())
+        .map(|source| source.need)
+        .collect();
+    assert!(needs.contains(&Need::Hunger));
+    assert!(needs.contains(&Need::Energy));
+}
+
+#[test]
+fn amenities_fit_the_smallest_map() {
+    for (name, _, _, (col, row)) in AMENITIES {
+        let size = MIN_MAP_SIZE as usize;
+        assert!(col < size && row < size, "{name}");
+    }
+}
@@ -0,0 +1,220 @@
+use std::time::Duration;
+
+use approx::assert_abs_diff_eq;
+use bevy::{
+    input::InputPlugin, prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    game_state_plugin::GameStatePlugin,
+    player_control_plugin::{CameraMode, MoveCameraXZ, Player, PlayerCommand, PlayerControlPlugin},
+    player_input_stage::PlayerInputStagesPlugin,
+    test_utils::BaseTestSuite,
+};
+
+use super::{
+    EDGE_SCROLL_MARGIN, ORBIT_FOCUS_Y, ORBIT_MAX_DISTANCE, ORBIT_MIN_DISTANCE, OrbitCameraPlugin,
+    OrbitCameraRig, OrbitPose,
+};
+
+struct OrbitCameraTestSuite {
+    app: App,
+    window: Entity,
+}
+
+impl BaseTestSuite for OrbitCameraTestSuite {
+    fn app(&mut self) -> &mut App {
+        &mut self.app
+    }
+}
+
+impl OrbitCameraTestSuite {
+    fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            InputPlugin,
+            StatesPlugin,
+            GameStatePlugin,
+            PlayerInputStagesPlugin,
+            PlayerControlPlugin,
+            OrbitCameraPlugin,
+        ));
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            1.0 / 60.0,
+        )));
+        let window = app
+            .world_mut()
+            .spawn((
+                Window {
+                    focused: true,
+                    resolution: (800.0, 600.0).into(),
+                    ..default()
+                },
+                PrimaryWindow,
+            ))
+            .id();
+
+        app.update();
+        app.update();
+        Self { app, window }
+    }
+
+    fn orbit(mut self) -> Self {
+        self.app
+            .world_mut()
+            .resource_mut::<NextState<CameraMode>>()
+            .set(CameraMode::Orbit);
+        self.update().update()
+    }
+
+    fn send(mut self, cmd: PlayerCommand) -> Self {
+        self.app.world_mut().send_event(cmd);
+        self.update()
+    }
+
+    fn cursor_at(mut self, pos: Vec2) -> Self {
+        let mut window = self.app.world_mut().get_mut::<Window>(self.window).unwrap();
+        window.set_cursor_position(Some(pos));
+        self
+    }
+
+    fn rig(&mut self) -> Option<&OrbitCameraRig> {
+        self.app
+            .world_mut()
+            .query_filtered::<&OrbitCameraRig, With<Player>>()
+            .single(self.app.world())
+            .ok()
+    }
+
+    fn target(&mut self) -> OrbitPose {
+        self.rig().unwrap().target
+    }
+
+    fn current(&mut self) -> OrbitPose {
+        self.rig().unwrap().current
+    }
+
+    fn camera_mode(&self) -> CameraMode {
+        *self.app.world().resource::<State<CameraMode>>().get()
+    }
+}
+
+#[test]
+fn orbit_pose_roundtrips_transform() {
+    let tr = Transform::from_xyz(0.0, 4.0, 4.0).looking_at(Vec3::new(5.0, 0.0, 5.0), Vec3::Y);
+    let pose = OrbitPose::from_transform(&tr);
+    assert_abs_diff_eq!(pose.focus.y, ORBIT_FOCUS_Y);
+    let restored = pose.to_transform();
+    assert!(restored.translation.abs_diff_eq(tr.translation, 1e-4));
+    assert!(restored.rotation.angle_between(tr.rotation) < 1e-3);
+}
+
+#[test]
+fn camera_mode_is_toggled_with_c() {
+    let mut suite = OrbitCameraTestSuite::new();
+    assert_eq!(suite.camera_mode(), CameraMode::FreeFly);
+    assert!(suite.rig().is_none());
+
+    let mut suite = suite.press(KeyCode::KeyC).release(KeyCode::KeyC).update();
+    assert_eq!(suite.camera_mode(), CameraMode::Orbit);
+    assert!(suite.rig().is_some());
+
+    let mut suite = suite.press(KeyCode::KeyC).release(KeyCode::KeyC).update();
+    assert_eq!(suite.camera_mode(), CameraMode::FreeFly);
+    assert!(suite.rig().is_none());
+}
+
+#[test]
+fn orbit_zoom_is_clamped() {
+    let mut suite = OrbitCameraTestSuite::new().orbit();
+    for _ in 0..100 {
+        suite = suite.send(PlayerCommand::MoveCameraInOut(15.0));
+    }
+    assert_abs_diff_eq!(suite.target().distance, ORBIT_MIN_DISTANCE);
+
+    for _ in 0..100 {
+        suite = suite.send(PlayerCommand::MoveCameraInOut(-15.0));
+    }
+    assert_abs_diff_eq!(suite.target().distance, ORBIT_MAX_DISTANCE);
+}
+
+#[test]
+fn orbit_eases_towards_target() {
+    let mut suite = OrbitCameraTestSuite::new().orbit();
+    for _ in 0..200 {
+        suite = suite.update();
+    }
+    let start = suite.current();
+
+    let mut suite = suite.send(PlayerCommand::MoveCameraXZ(MoveCameraXZ::new(1.0, 0.0)));
+    let target = suite.target();
+    let current = suite.current();
+    assert_ne!(target.focus, start.focus);
+    // Moved, but not all the way
+    assert!(current.focus.distance(start.focus) > 0.0);
+    assert!(current.focus.distance(start.focus) < target.focus.distance(start.focus));
+
+    for _ in 0..200 {
+        suite = suite.update();
+    }
+    assert!(suite.current().focus.abs_diff_eq(target.focus, 1e-3));
+    assert_abs_diff_eq!(suite.current().focus.y, ORBIT_FOCUS_Y);
+}
+
+#[test]
+fn orbit_wasd_pans_focus_on_the_ground() {
+    let mut suite = OrbitCameraTestSuite::new().orbit();
+    let start = suite.target();
+    let mut suite = suite.press(KeyCode::KeyW).update().update();
+    let target = suite.target();
+    assert_abs_diff_eq!(target.focus.y, ORBIT_FOCUS_Y);
+    assert_abs_diff_eq!(target.distance, start.distance);
+    let forward = (Quat::from_rotation_y(start.yaw) * Vec3::NEG_Z).normalize();
+    assert!(
+        (target.focus - start.focus)
+            .normalize()
+            .abs_diff_eq(forward, 1e-3)
+    );
+}
+
+#[test]
+fn orbit_edge_scroll_pans_focus() {
+    let mut suite = OrbitCameraTestSuite::new()
+        .orbit()
+        .cursor_at(Vec2::new(400.0, 300.0));
+    let start = suite.target();
+    let mut suite = suite.update().update();
+    assert_eq!(suite.target().focus, start.focus);
+
+    let mut suite = suite
+        .cursor_at(Vec2::new(EDGE_SCROLL_MARGIN / 2.0, 300.0))
+        .update();
+    let right = Quat::from_rotation_y(start.yaw) * Vec3::X;
+    let moved = suite.target().focus - start.focus;
+    assert!(moved.dot(right) < 0.0);
+}
+
+#[test]
+fn free_fly_controls_are_disabled_in_orbit_mode() {
+    let mut suite = OrbitCameraTestSuite::new().orbit();
+    for _ in 0..200 {
+        suite = suite.update();
+    }
+    // Eased camera stays where the rig places it, free fly camera movement is ignored
+    let expected = suite.current().to_transform().translation;
+    let mut suite = suite.send(PlayerCommand::MoveCameraInOut(-15.0));
+    let target = suite.target();
+    let tr = *suite
+        .app
+        .world_mut()
+        .query_filtered::<&Transform, With<Player>>()
+        .single(suite.app.world())
+        .unwrap();
+    assert!(
+        tr.translation
+            .abs_diff_eq(suite.current().to_transform().translation, 1e-4)
+    );
+    assert!(target.distance > (expected - target.focus).length());
+}
@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+
+use crate::game_map_plugin::{GameMap, GameMapCell};
+
+use super::{cell_center, cell_of, find_path, is_walkable};
+
+fn map() -> GameMap {
+    GameMap::new(2, 5, 5)
+}
+
+fn dig(map: &mut GameMap, cells: &[(u32, u32)]) {
+    for (col, row) in cells {
+        map.cells[0][*row as usize][*col as usize] = GameMapCell::new_empty();
+    }
+}
+
+fn is_connected(from: UVec2, path: &[UVec2]) -> bool {
+    let mut prev = from;
+    path.iter().all(|cell| {
+        let step = prev.x.abs_diff(cell.x) + prev.y.abs_diff(cell.y) == 1;
+        prev = *cell;
+        step
+    })
+}
+
+#[test]
+fn cells_match_world_positions() {
+    assert_eq!(cell_of(Vec3::new(2.4, 0.0, 6.6)), UVec2::new(2, 7));
+    assert_eq!(cell_of(Vec3::new(-0.4, 0.0, -3.0)), UVec2::ZERO);
+    assert_eq!(cell_center(UVec2::new(2, 7), 0.5), Vec3::new(2.0, 0.5, 7.0));
+}
+
+#[test]
+fn only_cells_with_floor_are_walkable() {
+    let map = map();
+    assert!(is_walkable(&map, 0, UVec2::new(4, 4)));
+    assert!(!is_walkable(&map, 0, UVec2::new(5, 4)));
+    assert!(!is_walkable(&map, 1, UVec2::new(0, 0)));
+    assert!(!is_walkable(&map, 2, UVec2::new(0, 0)));
+}
+
+#[test]
+fn straight_path() {
+    let map = map();
+    let path = find_path(&map, 0, UVec2::new(0, 0), UVec2::new(3, 0)).unwrap();
+    assert_eq!(
+        path,
+        vec![UVec2::new(1, 0), UVec2::new(2, 0), UVec2::new(3, 0)]
+    );
+    assert_eq!(
+        find_path(&map, 0, UVec2::new(2, 2), UVec2::new(2, 2)),
+        Some(vec![])
+    );
+}
+
+#[test]
+fn path_goes_around_holes() {
+    let mut map = map();
+    // Wall with a gap in the bottom row
+    dig(&mut map, &[(2, 0), (2, 1), (2, 2), (2, 3)]);
+    let from = UVec2::new(0, 0);
+    let path = find_path(&map, 0, from, UVec2::new(4, 0)).unwrap();
+    assert_eq!(path.len(), 12);
+    assert!(path.contains(&UVec2::new(2, 4)));
+    assert!(is_connected(from, &path));
+}
+
+#[test]
+fn unreachable_cells_have_no_path() {
+    let mut map = map();
+    dig(&mut map, &[(2, 0), (2, 1), (2, 2), (2, 3), (2, 4)]);
+    assert_eq!(find_path(&map, 0, UVec2::new(0, 0), UVec2::new(4, 0)), None);
+    assert_eq!(find_path(&map, 0, UVec2::new(0, 0), UVec2::new(2, 2)), None);
+    assert_eq!(find_path(&map, 0, UVec2::new(0, 0), UVec2::new(9, 9)), None);
+}
+
+#[test]
+fn equal_paths_are_chosen_reproducibly() {
+    let map = map();
+    let from = UVec2::new(0, 0);
+    let to = UVec2::new(3, 3);
+    let path = find_path(&map, 0, from, to).unwrap();
+    assert_eq!(path.len(), 6);
+    assert!(is_connected(from, &path));
+    for _ in 0..10 {
+        assert_eq!(find_path(&map, 0, from, to).unwrap(), path);
+    }
+}
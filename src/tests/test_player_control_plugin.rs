@@ -1,9 +1,19 @@
 use std::time::Duration;
 
-use bevy::{input::InputPlugin, prelude::*, time::TimeUpdateStrategy};
+use approx::assert_abs_diff_eq;
+use bevy::{
+    input::{
+        ButtonState, InputPlugin,
+        mouse::{MouseButtonInput, MouseMotion},
+    },
+    prelude::*,
+    time::TimeUpdateStrategy,
+    window::PrimaryWindow,
+};
 
 use crate::{
     player_input_stage::PlayerInputStagesPlugin,
+    settings_plugin::{MouseSettings, Settings},
     test_utils::{contains_exact_event, press_key},
 };
 
@@ -74,10 +84,10 @@ fn impl_camera_move_test(initial_pos: Vec3, keycode: KeyCode) -> Vec3 {
 
     // Move camera to its initial pos
     {
-        let mut world = app.world_mut();
+        let world = app.world_mut();
         let mut player = world
             .query_filtered::<&mut Transform, With<Player>>()
-            .single_mut(&mut world)
+            .single_mut(world)
             .unwrap();
 
         *player = player
@@ -94,7 +104,7 @@ fn impl_camera_move_test(initial_pos: Vec3, keycode: KeyCode) -> Vec3 {
     let player = app
         .world_mut()
         .query_filtered::<&Transform, With<Player>>()
-        .single(&app.world())
+        .single(app.world())
         .unwrap();
     assert_eq!(initial_pos.y, player.translation.y);
     let delta = player.translation - initial_pos;
@@ -134,3 +144,105 @@ fn move_camera_right_diagonal_direction() {
     assert!(delta.z < 0.0);
     assert!(delta.x.abs() > delta.z.abs());
 }
+
+// Hold middle mouse button and move the mouse by `total_motion` split evenly across `frames`
+// at the given frame rate.
+// Returns (yaw, pitch) change of the camera
+fn impl_mouse_look_test(fps: f32, frames: usize, total_motion: Vec2, mouse: MouseSettings) -> Vec2 {
+    let mut app = make_app();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+        1.0 / fps,
+    )));
+    app.world_mut().spawn((
+        Window {
+            focused: true,
+            ..default()
+        },
+        PrimaryWindow,
+    ));
+    app.world_mut().resource_mut::<Settings>().mouse = mouse;
+    app.update();
+    app.update();
+
+    let get_yaw_pitch = |app: &mut App| {
+        let player = app
+            .world_mut()
+            .query_filtered::<&Transform, With<Player>>()
+            .single(app.world())
+            .unwrap();
+        let (yaw, pitch, _) = player.rotation.to_euler(EulerRot::YXZ);
+        Vec2::new(yaw, pitch)
+    };
+    let initial = get_yaw_pitch(&mut app);
+
+    app.world_mut().send_event(MouseButtonInput {
+        button: MouseButton::Middle,
+        state: ButtonState::Pressed,
+        window: Entity::PLACEHOLDER,
+    });
+    for _ in 0..frames {
+        app.world_mut().send_event(MouseMotion {
+            delta: total_motion / frames as f32,
+        });
+        app.update();
+    }
+    get_yaw_pitch(&mut app) - initial
+}
+
+#[test]
+fn mouse_look_doesnt_depend_on_frame_rate() {
+    let motion = Vec2::new(120.0, 30.0);
+    let at_30 = impl_mouse_look_test(30.0, 15, motion, MouseSettings::default());
+    let at_60 = impl_mouse_look_test(60.0, 30, motion, MouseSettings::default());
+    let at_144 = impl_mouse_look_test(144.0, 72, motion, MouseSettings::default());
+
+    assert!(at_30.x.abs() > 0.1);
+    assert!(at_30.y.abs() > 0.01);
+    assert_abs_diff_eq!(at_30.x, at_60.x, epsilon = 1e-4);
+    assert_abs_diff_eq!(at_30.y, at_60.y, epsilon = 1e-4);
+    assert_abs_diff_eq!(at_30.x, at_144.x, epsilon = 1e-4);
+    assert_abs_diff_eq!(at_30.y, at_144.y, epsilon = 1e-4);
+}
+
+#[test]
+fn mouse_look_moving_mouse_right_and_up_turns_right_and_up() {
+    let delta = impl_mouse_look_test(60.0, 10, Vec2::new(50.0, -50.0), MouseSettings::default());
+    // Turning right is a negative rotation around Y
+    assert!(delta.x < 0.0);
+    assert!(delta.y > 0.0);
+}
+
+#[test]
+fn mouse_look_invert_y() {
+    let motion = Vec2::new(0.0, -50.0);
+    let normal = impl_mouse_look_test(60.0, 10, motion, MouseSettings::default());
+    let inverted = impl_mouse_look_test(
+        60.0,
+        10,
+        motion,
+        MouseSettings {
+            invert_y: true,
+            ..default()
+        },
+    );
+    assert!(normal.y > 0.0);
+    assert_abs_diff_eq!(normal.y, -inverted.y, epsilon = 1e-4);
+    assert_abs_diff_eq!(inverted.x, 0.0, epsilon = 1e-4);
+}
+
+#[test]
+fn mouse_look_sensitivity_scales_rotation() {
+    let motion = Vec2::new(100.0, 0.0);
+    let base = MouseSettings::default();
+    let slow = impl_mouse_look_test(60.0, 10, motion, base.clone());
+    let fast = impl_mouse_look_test(
+        60.0,
+        10,
+        motion,
+        MouseSettings {
+            sensitivity: base.sensitivity * 2.0,
+            ..base
+        },
+    );
+    assert_abs_diff_eq!(fast.x, slow.x * 2.0, epsilon = 1e-4);
+}
@@ -0,0 +1,227 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use super::{ContestOdds, ContestOutcome, ParseRollError, Roll, contest};
+
+#[test]
+fn roll_is_displayed_like_in_design() {
+    assert_eq!(Roll::DEFAULT.to_string(), "8d10(drop 4 high)");
+    assert_eq!(Roll::attribute(13, 3).to_string(), "1d13+3");
+    assert_eq!(Roll::attribute(13, -3).to_string(), "1d13-3");
+    let roll = Roll {
+        drop_low: 2,
+        ..Roll::new(4, 5)
+    };
+    assert_eq!(roll.to_string(), "4d5(drop 2 low)");
+}
+
+#[test]
+fn default_roll_stays_in_range() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let results: Vec<i32> = (0..10_000).map(|_| Roll::DEFAULT.roll(&mut rng)).collect();
+    assert!(results.iter().all(|x| (4..=40).contains(x)));
+    let average = results.iter().sum::<i32>() as f64 / results.len() as f64;
+    assert!((12.0..15.0).contains(&average), "average is {average}");
+}
+
+#[test]
+fn dropping_dice_keeps_the_rest() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    let low = Roll {
+        drop_high: 2,
+        ..Roll::new(3, 6)
+    };
+    let high = Roll {
+        drop_low: 2,
+        ..Roll::new(3, 6)
+    };
+    let everything = Roll {
+        drop_high: 5,
+        ..Roll::new(3, 6)
+    };
+    let low_avg: i32 = (0..1000).map(|_| low.roll(&mut rng)).sum();
+    let high_avg: i32 = (0..1000).map(|_| high.roll(&mut rng)).sum();
+    assert!(low_avg < high_avg);
+    assert_eq!(everything.roll(&mut rng), 0);
+}
+
+#[test]
+fn zero_sided_die_rolls_bonus() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    assert_eq!(Roll::attribute(-2, 3).roll(&mut rng), 3);
+    assert_eq!(Roll::attribute(0, 0).roll(&mut rng), 0);
+}
+
+#[test]
+fn rolls_are_reproducible_with_seed() {
+    let roll = |seed| {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        (0..16)
+            .map(|_| Roll::DEFAULT.roll(&mut rng))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(roll(42), roll(42));
+    assert_ne!(roll(42), roll(43));
+}
+
+fn assert_close(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-9, "{a} != {b}");
+}
+
+#[test]
+fn detailed_roll_shows_dropped_dice() {
+    let mut rng = ChaCha8Rng::seed_from_u64(4);
+    let detail = Roll::DEFAULT.roll_detailed(&mut rng);
+    assert_eq!(detail.dice.len(), 8);
+    assert!(detail.dice.is_sorted());
+    assert_eq!(detail.kept, 0..4);
+    assert_eq!(detail.total, detail.dice[0..4].iter().sum::<i32>());
+    let shown = detail.to_string();
+    assert_eq!(shown.matches('(').count(), 4);
+    assert!(shown.ends_with(&format!("= {}", detail.total)));
+
+    let mut rng = ChaCha8Rng::seed_from_u64(4);
+    assert_eq!(Roll::DEFAULT.roll(&mut rng), detail.total);
+}
+
+#[test]
+fn distribution_of_plain_dice() {
+    let d6 = Roll::new(1, 6).distribution();
+    assert_eq!(d6.min, 0);
+    assert_close(d6.probabilities[0], 0.0);
+    for p in &d6.probabilities[1..] {
+        assert_close(*p, 1.0 / 6.0);
+    }
+
+    let two_d6 = Roll {
+        bonus: 2,
+        ..Roll::new(2, 6)
+    }
+    .distribution();
+    assert_eq!(two_d6.min, 2);
+    assert_close(two_d6.probabilities[7], 6.0 / 36.0);
+    assert_close(two_d6.probabilities[2], 1.0 / 36.0);
+    assert_close(two_d6.mean(), 9.0);
+
+    let zero = Roll::attribute(0, 3).distribution();
+    assert_eq!(zero.min, 3);
+    assert_eq!(zero.probabilities, vec![1.0]);
+}
+
+#[test]
+fn distribution_with_dropped_dice() {
+    // 4d6 drop lowest, well known mean
+    let stats = Roll {
+        drop_low: 1,
+        ..Roll::new(4, 6)
+    }
+    .distribution();
+    assert_close(stats.probabilities.iter().sum(), 1.0);
+    assert_close(stats.mean(), 15869.0 / 1296.0);
+    assert_close(stats.probabilities[18], 21.0 / 1296.0);
+    assert_close(stats.probabilities[3], 1.0 / 1296.0);
+
+    let default = Roll::DEFAULT.distribution();
+    assert_close(default.probabilities.iter().sum(), 1.0);
+    assert_eq!((default.lowest(), default.highest()), (4, 40));
+    assert!((12.0..15.0).contains(&default.mean()));
+}
+
+#[test]
+fn contest_is_strictly_greater() {
+    assert_eq!(ContestOutcome::of(5, 4), ContestOutcome::Win);
+    assert_eq!(ContestOutcome::of(4, 5), ContestOutcome::Lose);
+    assert_eq!(ContestOutcome::of(4, 4), ContestOutcome::Tie);
+
+    let mut rng = ChaCha8Rng::seed_from_u64(5);
+    let strong = Roll::attribute(0, 10);
+    let weak = Roll::attribute(0, 3);
+    assert_eq!(contest(&strong, &weak, &mut rng), ContestOutcome::Win);
+    assert_eq!(contest(&weak, &strong, &mut rng), ContestOutcome::Lose);
+    assert_eq!(contest(&weak, &weak, &mut rng), ContestOutcome::Tie);
+}
+
+#[test]
+fn contest_odds_are_exact() {
+    let coin = Roll::new(1, 2);
+    let odds = ContestOdds::of(&coin, &coin);
+    assert_close(odds.win, 0.25);
+    assert_close(odds.tie, 0.5);
+    assert_close(odds.lose, 0.25);
+
+    let d6 = Roll::new(1, 6);
+    let odds = ContestOdds::of(&Roll { bonus: 1, ..d6 }, &d6);
+    assert_close(odds.win, 21.0 / 36.0);
+    assert_close(odds.tie, 5.0 / 36.0);
+
+    // Sampled contests agree with the exact odds
+    let luck = Roll::attribute(13, 3);
+    let odds = ContestOdds::of(&luck, &Roll::CHAOS);
+    assert_close(odds.win + odds.lose + odds.tie, 1.0);
+    let mut rng = ChaCha8Rng::seed_from_u64(6);
+    let wins = (0..20_000)
+        .filter(|_| contest(&luck, &Roll::CHAOS, &mut rng) == ContestOutcome::Win)
+        .count();
+    let sampled = wins as f64 / 20_000.0;
+    assert!(
+        (sampled - odds.win).abs() < 0.02,
+        "{sampled} vs {}",
+        odds.win
+    );
+}
+
+#[test]
+fn percentiles_of_distribution() {
+    let d4 = Roll::new(1, 4).distribution();
+    assert_eq!(d4.percentile(0.0), 1);
+    assert_eq!(d4.percentile(0.25), 1);
+    assert_eq!(d4.percentile(0.5), 2);
+    assert_eq!(d4.percentile(0.51), 3);
+    assert_eq!(d4.percentile(1.0), 4);
+    assert_eq!(Roll::new(2, 6).distribution().percentile(0.5), 7);
+}
+
+#[test]
+fn roll_notation_round_trips() {
+    for roll in [
+        Roll::DEFAULT,
+        Roll::attribute(13, 3),
+        Roll::attribute(13, -3),
+        Roll {
+            drop_low: 1,
+            ..Roll::new(4, 6)
+        },
+        Roll {
+            drop_high: 1,
+            drop_low: 2,
+            bonus: 5,
+            ..Roll::new(6, 8)
+        },
+    ] {
+        assert_eq!(roll.to_string().parse::<Roll>(), Ok(roll));
+    }
+    assert_eq!("d20".parse::<Roll>(), Ok(Roll::new(1, 20)));
+    assert_eq!("20d100".parse::<Roll>(), Ok(Roll::new(20, 100)));
+    assert_eq!(" 8d10 (drop 4 high) ".parse::<Roll>(), Ok(Roll::DEFAULT));
+}
+
+#[test]
+fn bad_roll_notation_is_rejected() {
+    for notation in [
+        "",
+        "10",
+        "xd6",
+        "2d",
+        "2d6+",
+        "2d6(drop 1)",
+        "2d6(keep 1 high)",
+        "21d6",
+        "1d101",
+        "4000000000d4000000000",
+    ] {
+        assert!(
+            matches!(notation.parse::<Roll>(), Err(ParseRollError(_))),
+            "{notation} parsed"
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use super::RonTree;
+
+#[test]
+fn documents_are_written_back_whole() {
+    let text = r#"(
+        // Comments are dropped
+        name: "Tom \"the\" cat",
+        letter: '\'',
+        season: Winter,
+        task: Some(Work(3, -1.5e-3)),
+        slot: Lesson(day: 2, index: 0),
+        empty: (),
+        cells: [[Grass, None], []],
+        counts: {Herb: 2, "key": [1, 2,],},
+        named: Calendar(year: 1,),
+    )"#;
+    let tree = RonTree::parse(text).unwrap();
+    assert_eq!(tree.field("season"), Some(&RonTree::Ident("Winter".into())));
+    assert_eq!(
+        tree.field("task"),
+        Some(&RonTree::Tuple(
+            Some("Some".into()),
+            vec![RonTree::Tuple(
+                Some("Work".into()),
+                vec![
+                    RonTree::Literal("3".into()),
+                    RonTree::Literal("-1.5e-3".into())
+                ]
+            )]
+        ))
+    );
+    let written = tree.to_string();
+    assert_eq!(RonTree::parse(&written).unwrap(), tree);
+    assert_eq!(
+        written,
+        r#"(name: "Tom \"the\" cat", letter: '\'', season: Winter, task: Some(Work(3, -1.5e-3)), slot: Lesson(day: 2, index: 0), empty: (), cells: [[Grass, None], []], counts: {Herb: 2, "key": [1, 2]}, named: Calendar(year: 1))"#
+    );
+}
+
+#[test]
+fn fields_are_changed_in_place() {
+    let mut tree = RonTree::parse("(version: 3, gold: 10)").unwrap();
+    assert_eq!(tree.field("version").and_then(RonTree::as_u32), Some(3));
+    tree.rename_field("gold", "coins");
+    tree.set_field("version", RonTree::Literal("4".into()));
+    tree.set_field("books", RonTree::Ident("None".into()));
+    assert_eq!(tree.remove_field("missing"), None);
+    assert_eq!(tree.to_string(), "(version: 4, coins: 10, books: None)");
+    assert_eq!(
+        tree.remove_field("coins"),
+        Some(RonTree::Literal("10".into()))
+    );
+    assert_eq!(tree.to_string(), "(version: 4, books: None)");
+}
+
+#[test]
+fn broken_documents_are_rejected() {
+    for text in ["(a: 1", "[1 2]", "\"open", "(a: 1) x", "", "{1 2}", "#"] {
+        assert!(RonTree::parse(text).is_err(), "{text}");
+    }
+}
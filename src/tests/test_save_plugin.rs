@@ -0,0 +1,499 @@
+use std::{path::PathBuf, time::Duration};
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    alchemy_plugin::{AlchemyPlugin, OrderCraft, PlaceWorkstation, StationKind, Workstation},
+    calendar_plugin::{CalendarConfig, CalendarPlugin},
+    cat::CatSheet,
+    cat_ai_plugin::{Action, Task, WalkPath},
+    designation_plugin::{DesignationKind, DesignationPlugin, JobQueue, JobState},
+    economy_plugin::{EconomyPlugin, Treasury, holdings},
+    game_state_plugin::GameState,
+    hauling_plugin::{HaulingPlugin, PlaceStockpile, RequestHaul, Stockpile},
+    item::{Inventory, ItemKind, ItemQuality},
+    main_menu_plugin::NewGameSettings,
+    needs_plugin::NeedsPlugin,
+    player_control_plugin::PlayerCommand,
+    rng_plugin::{GameRng, GameSeed, RngPlugin},
+    ron_tree::RonTree,
+    simulation_clock_plugin::SimulationClock,
+    student_plugin::{Alumni, AlumniRecord, Education, Enrollment, StudentPlugin},
+    test_utils::{contains_exact_event, sheet, simulation_app, start_game},
+    timetable_plugin::{Class, Classroom, Classrooms, LessonSlot, Timetable, TimetablePlugin},
+};
+
+use super::{
+    GameSaved, LoadGame, MIGRATIONS, PendingLoad, SAVE_VERSION, SaveError, SaveGame, SavePlugin,
+    SavedGame, SavedRng, migrate,
+};
+
+fn temp_save_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("macatemy-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn new_app(path: PathBuf) -> App {
+    new_app_with_timetable(path, false)
+}
+
+fn new_app_with_timetable(path: PathBuf, timetable: bool) -> App {
+    let mut app = simulation_app();
+    if timetable {
+        app.add_plugins(TimetablePlugin);
+    }
+    app.add_plugins((
+        CalendarPlugin,
+        RngPlugin { seed: Some(1) },
+        NeedsPlugin,
+        StudentPlugin,
+        EconomyPlugin,
+        DesignationPlugin,
+        AlchemyPlugin,
+        HaulingPlugin,
+        SavePlugin { path },
+    ));
+    // A season lasts two ticks
+    app.insert_resource(CalendarConfig {
+        ticks_per_day: 1,
+        days_per_season: 2,
+    });
+    start_game(&mut app);
+    app
+}
+
+fn set_paused(app: &mut App, paused: bool) {
+    app.world_mut().resource_mut::<SimulationClock>().paused = paused;
+}
+
+/// No more ticks, a loaded or restarted game has a clock of its own which isn't paused
+fn stop_time(app: &mut App) {
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+}
+
+fn load(app: &mut App, path: PathBuf) {
+    app.world_mut().trigger(LoadGame(path));
+    for _ in 0..5 {
+        app.update();
+    }
+    assert_eq!(*app.world().resource::<State<GameState>>(), GameState::Game);
+}
+
+fn stored_goods(app: &mut App) -> [i64; 5] {
+    let mut stockpiles = app
+        .world_mut()
+        .query_filtered::<&Inventory, With<Stockpile>>();
+    holdings(
+        app.world().resource::<Treasury>(),
+        stockpiles.iter(app.world()),
+    )
+}
+
+fn cats(app: &mut App) -> Vec<Entity> {
+    let mut cats: Vec<Entity> = app
+        .world_mut()
+        .query_filtered::<Entity, With<CatSheet>>()
+        .iter(app.world())
+        .collect();
+    cats.sort();
+    cats
+}
+
+#[test]
+fn rng_goes_on_with_the_same_numbers() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    rng.set_stream(5);
+    for _ in 0..37 {
+        rng.r#gen::<u32>();
+    }
+    let mut restored = SavedRng::new(&rng).restore();
+    let expected: Vec<u64> = (0..10).map(|_| rng.r#gen()).collect();
+    let drawn: Vec<u64> = (0..10).map(|_| restored.r#gen()).collect();
+    assert_eq!(drawn, expected);
+}
+
+#[test]
+fn unknown_versions_are_rejected() {
+    for version in [0, SAVE_VERSION + 1] {
+        let result = SavedGame::from_ron(&format!("SavedGame(version: {version})"));
+        assert!(
+            matches!(result, Err(SaveError::Version(v)) if v == version),
+            "{result:?}"
+        );
+    }
+    assert!(matches!(
+        SavedGame::from_ron("SavedGame(version: 1)"),
+        Err(SaveError::Parse(_))
+    ));
+}
+
+#[test]
+fn older_saves_are_migrated() {
+    let path = temp_save_path("migrated.ron");
+    let mut app = new_app(path.clone());
+    app.world_mut().trigger(SaveGame(path.clone()));
+    let save = SavedGame::load(&path).unwrap();
+
+    // Version 1 knew the treasury as coffers and had no books
+    let mut tree = RonTree::parse(&save.to_ron().unwrap()).unwrap();
+    tree.rename_field("treasury", "coffers");
+    tree.field_mut("coffers").unwrap().remove_field("books");
+    tree.set_field("version", RonTree::Literal("1".into()));
+    assert!(ron::from_str::<SavedGame>(&tree.to_string()).is_err());
+
+    let migrations: [fn(&mut RonTree); 3] = [
+        |save| save.rename_field("coffers", "treasury"),
+        |save| {
+            let treasury = save.field_mut("treasury").unwrap();
+            treasury.set_field("books", RonTree::Literal("7".into()));
+        },
+        |save| {
+            let treasury = save.field_mut("treasury").unwrap();
+            treasury.set_field("gold", RonTree::Literal("5".into()));
+        },
+    ];
+    let mut newer = RonTree::parse(&save.to_ron().unwrap()).unwrap();
+    newer.set_field("version", RonTree::Literal("3".into()));
+    migrate(&mut newer, &migrations);
+    let newer: SavedGame = ron::from_str(&newer.to_string()).unwrap();
+    assert_eq!(newer.version, 4);
+    assert_eq!(
+        newer.treasury.books, save.treasury.books,
+        "only newer migrations run"
+    );
+    assert_eq!(newer.treasury.gold, 5);
+
+    migrate(&mut tree, &migrations);
+    let migrated: SavedGame = ron::from_str(&tree.to_string()).unwrap();
+    assert_eq!(migrated.version, 4);
+    assert_eq!((migrated.treasury.books, migrated.treasury.gold), (7, 5));
+    assert_eq!(
+        SavedGame {
+            version: save.version,
+            treasury: save.treasury,
+            ..migrated
+        },
+        save
+    );
+}
+
+#[test]
+fn version_one_saves_get_the_usual_classrooms() {
+    let path = temp_save_path("version-one.ron");
+    let mut app = new_app(path.clone());
+    app.world_mut().trigger(SaveGame(path.clone()));
+    let save = SavedGame::load(&path).unwrap();
+    let mut tree = RonTree::parse(&save.to_ron().unwrap()).unwrap();
+    for field in ["alumni", "classrooms", "timetable"] {
+        tree.remove_field(field);
+    }
+    tree.set_field("version", RonTree::Literal("1".into()));
+    migrate(&mut tree, &MIGRATIONS);
+    let migrated: SavedGame = ron::from_str(&tree.to_string()).unwrap();
+    assert_eq!(migrated.classrooms, Classrooms::default().0);
+    assert_eq!(
+        SavedGame {
+            classrooms: save.classrooms.clone(),
+            ..migrated
+        },
+        save
+    );
+}
+
+#[test]
+fn saves_replace_files_whole() {
+    let path = temp_save_path("replaced.ron");
+    let mut app = new_app(path.clone());
+    std::fs::write(&path, "old").unwrap();
+    // Left over from a crash in the middle of writing
+    std::fs::write(path.with_extension("tmp"), "SavedGame(vers").unwrap();
+    app.world_mut().trigger(SaveGame(path.clone()));
+    assert!(SavedGame::load(&path).is_ok());
+    assert!(!path.with_extension("tmp").exists());
+    assert!(contains_exact_event(&app, GameSaved(path)));
+}
+
+#[test]
+fn game_round_trips_through_uninit_and_init() {
+    let path = temp_save_path("roundtrip.ron");
+    let mut app = new_app(path.clone());
+    for _ in 0..5 {
+        app.update();
+    }
+    set_paused(&mut app, true);
+    let cats_before = cats(&mut app);
+    assert!(cats_before.len() > 1, "kittens have arrived");
+    app.world_mut().entity_mut(cats_before[0]).insert((
+        Task {
+            action: Action::Wander,
+            target: None,
+            destination: UVec2::new(3, 4),
+            lesson: None,
+            job: None,
+            started: 2,
+            arrived: false,
+        },
+        WalkPath(vec![UVec2::new(3, 3), UVec2::new(3, 4)]),
+    ));
+    // Tasks bound to map objects are not saved
+    app.world_mut().entity_mut(cats_before[1]).insert(Task {
+        action: Action::Eat,
+        target: Some(cats_before[0]),
+        destination: UVec2::ZERO,
+        lesson: None,
+        job: None,
+        started: 2,
+        arrived: true,
+    });
+    app.world_mut().trigger(SaveGame(path.clone()));
+    let saved = SavedGame::load(&path).unwrap();
+    assert_eq!(saved.cats.len(), cats_before.len());
+    assert!(saved.cats[0].task.is_some());
+    assert!(saved.cats[1].task.is_none());
+
+    // Carry on with the game for a while
+    set_paused(&mut app, false);
+    for _ in 0..6 {
+        app.update();
+    }
+    app.world_mut().resource_mut::<Treasury>().gold += 1000;
+    stop_time(&mut app);
+
+    app.world_mut().trigger(LoadGame(path.clone()));
+    let mut states = vec![];
+    for _ in 0..5 {
+        app.update();
+        states.push(*app.world().resource::<State<GameState>>().get());
+    }
+    assert!(states.contains(&GameState::Uninit));
+    assert!(states.contains(&GameState::Init));
+    assert_eq!(states.last(), Some(&GameState::Game));
+    assert!(!app.world().contains_resource::<PendingLoad>());
+
+    let cats_after = cats(&mut app);
+    assert_eq!(cats_after.len(), cats_before.len(), "no new arrivals");
+    let paths: Vec<Vec<UVec2>> = app
+        .world_mut()
+        .query::<&WalkPath>()
+        .iter(app.world())
+        .map(|path| path.0.clone())
+        .collect();
+    assert_eq!(paths, vec![vec![UVec2::new(3, 3), UVec2::new(3, 4)]]);
+
+    // Entities are reused in another order, cats may be saved in another order too
+    app.world_mut().trigger(SaveGame(path.clone()));
+    let mut resaved = SavedGame::load(&path).unwrap();
+    assert!(saved.cats.iter().all(|cat| resaved.cats.contains(cat)));
+    resaved.cats = saved.cats.clone();
+    assert_eq!(resaved, saved);
+}
+
+#[test]
+fn stored_goods_and_jobs_survive_loading() {
+    let path = temp_save_path("goods.ron");
+    let mut app = new_app(path.clone());
+    set_paused(&mut app, true);
+    let (from, to) = (UVec2::new(1, 1), UVec2::new(2, 1));
+    for cell in [from, to] {
+        app.world_mut().trigger(PlaceStockpile { layer: 0, cell });
+    }
+    app.world_mut().trigger(PlaceWorkstation {
+        kind: StationKind::Cauldron,
+        layer: 0,
+        cell: UVec2::new(4, 4),
+    });
+    app.update();
+    let mut stockpiles = app.world_mut().query::<(Entity, &Stockpile)>();
+    let stockpiles: Vec<(Entity, UVec2)> = stockpiles
+        .iter(app.world())
+        .map(|(entity, stockpile)| (entity, stockpile.cell))
+        .collect();
+    let site = |cell| stockpiles.iter().find(|(_, at)| *at == cell).unwrap().0;
+    let (from, to) = (site(from), site(to));
+    let mut stations = app
+        .world_mut()
+        .query_filtered::<Entity, With<Workstation>>();
+    let station = stations.single(app.world()).unwrap();
+    let world = app.world_mut();
+    let mut inventory = world.get_mut::<Inventory>(from).unwrap();
+    inventory.add(ItemKind::Herb, ItemQuality::Common, 5);
+    inventory.add(ItemKind::Crystal, ItemQuality::Fine, 2);
+    world
+        .get_mut::<Inventory>(station)
+        .unwrap()
+        .add(ItemKind::Mushroom, ItemQuality::Common, 1);
+    world.trigger(OrderCraft {
+        station,
+        recipe: "Hearty Broth".into(),
+    });
+    world.trigger(RequestHaul {
+        from,
+        to,
+        kind: ItemKind::Herb,
+        count: 1,
+    });
+    let dig = world
+        .resource_mut::<JobQueue>()
+        .designate(DesignationKind::Dig, 0, UVec2::new(6, 6));
+    app.update();
+    let goods = stored_goods(&mut app);
+    assert_ne!(goods, [0; 5]);
+    app.world_mut().trigger(SaveGame(path.clone()));
+    let saved = SavedGame::load(&path).unwrap();
+    assert_eq!(saved.stockpiles.len(), 2);
+    assert_eq!(saved.jobs.len(), 1, "the haul job is left out");
+
+    // Spend everything before loading
+    let world = app.world_mut();
+    world.despawn(from);
+    world.get_mut::<Inventory>(station).unwrap().0.clear();
+    world.resource_mut::<JobQueue>().jobs.clear();
+    stop_time(&mut app);
+    load(&mut app, path);
+
+    assert_eq!(stored_goods(&mut app), goods);
+    let mut stations = app.world_mut().query::<(&Workstation, &Inventory)>();
+    let (workstation, inventory) = stations.single(app.world()).unwrap();
+    assert_eq!(workstation.orders, vec!["Hearty Broth".to_string()]);
+    assert_eq!(inventory.count(ItemKind::Mushroom), 1);
+    let queue = app.world().resource::<JobQueue>();
+    let job = queue.get(dig).unwrap();
+    assert_eq!(
+        (job.kind, job.state),
+        (DesignationKind::Dig, JobState::Pending)
+    );
+    let next =
+        app.world_mut()
+            .resource_mut::<JobQueue>()
+            .designate(DesignationKind::Dig, 0, UVec2::ZERO);
+    assert!(next > dig, "ids of loaded jobs are not reused");
+}
+
+fn restart(app: &mut App) {
+    stop_time(app);
+    app.world_mut().send_event(PlayerCommand::RestartGame);
+    for _ in 0..3 {
+        app.update();
+    }
+    assert_eq!(*app.world().resource::<State<GameState>>(), GameState::Game);
+}
+
+/// Names of the cats and the next random number
+fn cat_names_and_rng(app: &mut App) -> (Vec<String>, u64) {
+    let mut names: Vec<String> = app
+        .world_mut()
+        .query_filtered::<&Name, With<CatSheet>>()
+        .iter(app.world())
+        .map(|name| name.to_string())
+        .collect();
+    names.sort();
+    (names, app.world_mut().resource_mut::<GameRng>().r#gen())
+}
+
+#[test]
+fn restarting_a_loaded_game_uses_its_seed() {
+    let path = temp_save_path("seed.ron");
+    let mut app = new_app(path.clone());
+    let settings = NewGameSettings::new(9);
+    app.insert_resource(GameSeed(9));
+    app.insert_resource(settings.clone());
+    app.world_mut().trigger(SaveGame(path.clone()));
+    let mut expected = new_app(temp_save_path("seed-fresh.ron"));
+    expected.insert_resource(GameSeed(9));
+    restart(&mut expected);
+
+    // Another game of the same session
+    app.insert_resource(GameSeed(2));
+    app.insert_resource(NewGameSettings::new(2));
+    load(&mut app, path);
+    assert_eq!(*app.world().resource::<GameSeed>(), GameSeed(9));
+    assert_eq!(*app.world().resource::<NewGameSettings>(), settings);
+    restart(&mut app);
+    assert_eq!(
+        cat_names_and_rng(&mut app),
+        cat_names_and_rng(&mut expected)
+    );
+}
+
+/// Classes with the names of their cats, entities change with loading
+fn named_classes(app: &mut App) -> Vec<(String, Vec<String>, LessonSlot)> {
+    let classes = app.world().resource::<Timetable>().classes.clone();
+    let name = |cat: Entity| app.world().get::<Name>(cat).unwrap().to_string();
+    classes
+        .into_iter()
+        .map(|class| {
+            let students = class.students.into_iter().map(name).collect();
+            (name(class.teacher), students, class.slot)
+        })
+        .collect()
+}
+
+#[test]
+fn school_records_survive_loading() {
+    let path = temp_save_path("school.ron");
+    let mut app = new_app_with_timetable(path.clone(), true);
+    set_paused(&mut app, true);
+    let mut spawn = |name: &str| {
+        app.world_mut()
+            .spawn((Name::new(name.to_string()), sheet(), Transform::default()))
+            .id()
+    };
+    let (teacher, student, departed) = (spawn("Tom"), spawn("Kit"), spawn("Gone"));
+    let sheet = sheet();
+    let alumnus = AlumniRecord {
+        name: "Old Whiskers".to_string(),
+        sheet: sheet.clone(),
+        enrollment: Enrollment {
+            enrolled_season: 0,
+            education: Education::General,
+            education_started: 0,
+        },
+        departed_season: 3,
+    };
+    app.world_mut().resource_mut::<Alumni>().0.push(alumnus);
+    let attic = Classroom {
+        name: "Attic".to_string(),
+        layer: 1,
+        min: UVec2::ZERO,
+        max: UVec2::ONE,
+    };
+    app.world_mut()
+        .resource_mut::<Classrooms>()
+        .0
+        .push(attic.clone());
+    let slot = LessonSlot {
+        weekday: 2,
+        lesson: 1,
+    };
+    app.world_mut().resource_mut::<Timetable>().classes = vec![Class {
+        school: sheet.best_school(),
+        teacher,
+        students: vec![student, departed],
+        classroom: 2,
+        slot,
+    }];
+    app.world_mut().despawn(departed);
+    app.world_mut().trigger(SaveGame(path.clone()));
+    let saved = SavedGame::load(&path).unwrap();
+    assert_eq!(
+        saved.timetable[0].students.len(),
+        1,
+        "departed cats are left out"
+    );
+
+    app.world_mut().resource_mut::<Alumni>().0.clear();
+    app.world_mut().resource_mut::<Timetable>().classes.clear();
+    stop_time(&mut app);
+    load(&mut app, path);
+    assert_eq!(app.world().resource::<Alumni>().0, saved.alumni);
+    assert_eq!(app.world().resource::<Classrooms>().0.last(), Some(&attic));
+    assert_eq!(
+        named_classes(&mut app),
+        vec![("Tom".to_string(), vec!["Kit".to_string()], slot)]
+    );
+}
@@ -0,0 +1,61 @@
+use approx::assert_abs_diff_eq;
+use bevy::prelude::*;
+
+use super::{MouseSettings, Settings, SettingsPlugin};
+
+fn temp_settings_path(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("macatemy-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn settings_roundtrip_through_file() {
+    let path = temp_settings_path("roundtrip.ron");
+    let settings = Settings {
+        mouse: MouseSettings {
+            sensitivity: 0.01,
+            invert_y: true,
+        },
+    };
+    settings.save(&path).unwrap();
+    assert_eq!(Settings::load(&path).unwrap(), settings);
+}
+
+#[test]
+fn missing_settings_file_gives_defaults() {
+    let path = temp_settings_path("missing.ron");
+    assert_eq!(Settings::load(&path).unwrap(), Settings::default());
+}
+
+#[test]
+fn missing_settings_fields_are_defaulted() {
+    let settings = Settings::from_ron("(mouse: (invert_y: true))").unwrap();
+    assert!(settings.mouse.invert_y);
+    assert_abs_diff_eq!(
+        settings.mouse.sensitivity,
+        MouseSettings::default().sensitivity
+    );
+}
+
+#[test]
+fn broken_settings_file_is_an_error() {
+    assert!(Settings::from_ron("(mouse: (invert_y: maybe))").is_err());
+}
+
+#[test]
+fn settings_are_saved_when_changed() {
+    let path = temp_settings_path("plugin.ron");
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, SettingsPlugin { path: path.clone() }));
+
+    // Loading settings doesn't write them back
+    app.update();
+    assert!(!path.exists());
+
+    app.world_mut().resource_mut::<Settings>().mouse.invert_y = true;
+    app.update();
+    assert!(Settings::load(&path).unwrap().mouse.invert_y);
+}
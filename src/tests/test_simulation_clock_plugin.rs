@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+
+use crate::{
+    player_control_plugin::{Player, PlayerCommand},
+    test_utils::{BaseTestSuite, simulation_app, start_game},
+};
+
+use super::{SimulationClock, SimulationSet, SimulationSpeed};
+
+#[derive(Resource, Default)]
+struct TickCounter(u64);
+
+fn count_ticks(mut counter: ResMut<TickCounter>) {
+    counter.0 += 1;
+}
+
+struct SimulationClockTestSuite {
+    app: App,
+}
+
+impl BaseTestSuite for SimulationClockTestSuite {
+    fn app(&mut self) -> &mut App {
+        &mut self.app
+    }
+}
+
+impl SimulationClockTestSuite {
+    fn new() -> Self {
+        let mut app = simulation_app();
+        app.init_resource::<TickCounter>();
+        app.add_systems(FixedUpdate, count_ticks.in_set(SimulationSet));
+        start_game(&mut app);
+        Self { app }
+    }
+
+    fn send(mut self, cmd: PlayerCommand) -> Self {
+        self.app.world_mut().send_event(cmd);
+        self.update()
+    }
+
+    /// Ticks run during the given number of frames
+    fn ticks_during(&mut self, frames: usize) -> u64 {
+        let before = self.app.world().resource::<TickCounter>().0;
+        for _ in 0..frames {
+            self.app.update();
+        }
+        self.app.world().resource::<TickCounter>().0 - before
+    }
+
+    fn clock(&self) -> &SimulationClock {
+        self.app.world().resource::<SimulationClock>()
+    }
+}
+
+#[test]
+fn simulation_speed_is_saturated() {
+    assert_eq!(SimulationSpeed::Normal.shifted(-1), SimulationSpeed::Normal);
+    assert_eq!(SimulationSpeed::Normal.shifted(1), SimulationSpeed::Fast);
+    assert_eq!(
+        SimulationSpeed::Normal.shifted(100),
+        SimulationSpeed::Fastest
+    );
+    assert_eq!(SimulationSpeed::Fastest.shifted(-1), SimulationSpeed::Fast);
+}
+
+#[test]
+fn simulation_ticks_follow_speed() {
+    let mut suite = SimulationClockTestSuite::new();
+    assert_eq!(suite.ticks_during(10), 10);
+
+    let mut suite = suite.send(PlayerCommand::ChangeSimulationSpeed(1));
+    assert_eq!(suite.clock().speed, SimulationSpeed::Fast);
+    assert_eq!(suite.ticks_during(10), 20);
+
+    let mut suite = suite.send(PlayerCommand::ChangeSimulationSpeed(1));
+    assert_eq!(suite.clock().speed, SimulationSpeed::Fastest);
+    assert_eq!(suite.ticks_during(10), 50);
+
+    let mut suite = suite.send(PlayerCommand::ChangeSimulationSpeed(-2));
+    assert_eq!(suite.clock().speed, SimulationSpeed::Normal);
+    assert_eq!(suite.ticks_during(10), 10);
+}
+
+#[test]
+fn clock_tick_counts_simulation_ticks() {
+    let mut suite = SimulationClockTestSuite::new();
+    let start = suite.clock().tick;
+    let ran = suite.ticks_during(7);
+    assert_eq!(suite.clock().tick - start, ran);
+}
+
+#[test]
+fn pause_stops_simulation_but_not_camera() {
+    let mut suite = SimulationClockTestSuite::new()
+        .press(KeyCode::Space)
+        .release(KeyCode::Space);
+    assert!(suite.clock().paused);
+    assert_eq!(suite.ticks_during(10), 0);
+
+    let camera = |suite: &mut SimulationClockTestSuite| {
+        suite
+            .app
+            .world_mut()
+            .query_filtered::<&Transform, With<Player>>()
+            .single(suite.app.world())
+            .unwrap()
+            .translation
+    };
+    let before = camera(&mut suite);
+    let mut suite = suite.press(KeyCode::KeyW).update().release(KeyCode::KeyW);
+    assert_ne!(camera(&mut suite), before);
+
+    let mut suite = suite.press(KeyCode::Space).release(KeyCode::Space);
+    assert!(!suite.clock().paused);
+    assert_eq!(suite.ticks_during(10), 10);
+}
+
+#[test]
+fn step_runs_exactly_one_tick() {
+    let mut suite = SimulationClockTestSuite::new().send(PlayerCommand::TogglePause);
+    assert_eq!(suite.ticks_during(5), 0);
+
+    let before = suite.app.world().resource::<TickCounter>().0;
+    let mut suite = suite.press(KeyCode::KeyN).release(KeyCode::KeyN);
+    assert_eq!(suite.app.world().resource::<TickCounter>().0 - before, 1);
+    assert_eq!(suite.ticks_during(5), 0);
+
+    let before = suite.app.world().resource::<TickCounter>().0;
+    let mut suite = suite
+        .send(PlayerCommand::StepSimulation)
+        .send(PlayerCommand::StepSimulation);
+    assert_eq!(suite.app.world().resource::<TickCounter>().0 - before, 2);
+    assert_eq!(suite.ticks_during(5), 0);
+    assert!(suite.clock().paused);
+}
+
+#[test]
+fn step_pauses_running_simulation() {
+    let suite = SimulationClockTestSuite::new();
+    let before = suite.app.world().resource::<TickCounter>().0;
+    let mut suite = suite.send(PlayerCommand::StepSimulation);
+    assert_eq!(suite.app.world().resource::<TickCounter>().0 - before, 1);
+    assert!(suite.clock().paused);
+    assert_eq!(suite.ticks_during(5), 0);
+}
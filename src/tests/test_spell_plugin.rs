@@ -0,0 +1,255 @@
+use bevy::prelude::*;
+
+use crate::{
+    cat::{CatSheet, MagicSchool},
+    combat_plugin::{CombatPlugin, Creature, CreatureKind, Fighting, Health},
+    game_map_plugin::{GameMapCellFloor, LayerObject, SetCellFloorEvent},
+    needs_plugin::Needs,
+    rng_plugin::RngPlugin,
+    roll::Roll,
+    test_utils::{sheet, simulation_app, start_game},
+};
+
+use super::{
+    CastOutcome, CastSpell, MANA_PER_MAGIC, MANA_REGEN_TICKS, Mana, SPELLS, SpellBook, SpellCast,
+    SpellEffect, SpellError, SpellPlugin, SpellTarget,
+};
+
+#[test]
+fn builtin_spells_cover_every_school() {
+    let book = SpellBook::from_ron(SPELLS).unwrap();
+    for school in MagicSchool::ALL {
+        assert!(
+            book.0.iter().any(|spell| spell.school == school),
+            "{school:?}"
+        );
+    }
+    let firebolt = book.get("Firebolt").unwrap();
+    assert_eq!(firebolt.difficulty, Roll::new(1, 10));
+    assert_eq!(firebolt.effect, SpellEffect::Damage(6));
+}
+
+#[test]
+fn bad_spell_books_are_rejected() {
+    let spell = |name: &str, school: &str| {
+        format!(
+            r#"(name: "{name}", school: {school}, discipline: Fire, cost: 1, difficulty: "1d4", effect: Damage(1))"#
+        )
+    };
+    let book = |spells: &[String]| SpellBook::from_ron(&format!("[{}]", spells.join(",")));
+    assert!(book(&[spell("Spark", "Elemental")]).is_ok());
+    assert!(matches!(
+        book(&[spell("Spark", "Medical")]),
+        Err(SpellError::WrongSchool(_))
+    ));
+    assert!(matches!(
+        book(&[spell("Spark", "Elemental"), spell("Spark", "Elemental")]),
+        Err(SpellError::Duplicate(_))
+    ));
+    assert!(matches!(
+        SpellBook::from_ron(r#"[(name: "Spark", difficulty: "1x4")]"#),
+        Err(SpellError::Parse(_))
+    ));
+}
+
+#[derive(Resource, Default)]
+struct CastLog {
+    casts: Vec<SpellCast>,
+    floors: Vec<SetCellFloorEvent>,
+}
+
+fn log_casts(mut evs: EventReader<SpellCast>, mut log: ResMut<CastLog>) {
+    log.casts.extend(evs.read().cloned());
+}
+
+fn log_floors(ev: Trigger<SetCellFloorEvent>, mut log: ResMut<CastLog>) {
+    log.floors.push(*ev);
+}
+
+fn new_app() -> App {
+    let mut app = simulation_app();
+    app.add_plugins((RngPlugin { seed: Some(1) }, CombatPlugin, SpellPlugin));
+    app.init_resource::<CastLog>();
+    app.add_systems(Update, log_casts);
+    app.add_observer(log_floors);
+    start_game(&mut app);
+    app
+}
+
+/// Cat who casts everything with ease
+fn archmage() -> CatSheet {
+    let mut sheet = sheet();
+    sheet.primary.magic = 40;
+    sheet.primary.intelligence = 40;
+    sheet.primary.charm = 40;
+    sheet
+}
+
+fn cast(app: &mut App, caster: Entity, spell: &str, target: SpellTarget) -> CastOutcome {
+    app.world_mut().trigger(CastSpell {
+        caster,
+        spell: spell.to_string(),
+        target,
+    });
+    app.update();
+    app.world()
+        .resource::<CastLog>()
+        .casts
+        .last()
+        .unwrap()
+        .outcome
+}
+
+fn mana(app: &App, cat: Entity) -> Mana {
+    *app.world().get::<Mana>(cat).unwrap()
+}
+
+#[test]
+fn mana_follows_magic() {
+    let mut app = new_app();
+    let cat = app.world_mut().spawn(archmage()).id();
+    assert_eq!(
+        mana(&app, cat),
+        Mana {
+            current: 40 * MANA_PER_MAGIC,
+            max: 40 * MANA_PER_MAGIC
+        }
+    );
+    app.world_mut().get_mut::<Mana>(cat).unwrap().current = 0;
+    for _ in 0..MANA_REGEN_TICKS * 3 {
+        app.update();
+    }
+    assert_eq!(mana(&app, cat).current, 3);
+}
+
+#[test]
+fn spells_change_cats() {
+    let mut app = new_app();
+    let mage = app.world_mut().spawn(archmage()).id();
+    let patient = app
+        .world_mut()
+        .spawn((
+            archmage(),
+            Needs {
+                fun: 0.0,
+                ..default()
+            },
+        ))
+        .id();
+    let max = app.world().get::<Health>(patient).unwrap().max;
+    let target = SpellTarget::Entity(patient);
+    let health = |app: &App| app.world().get::<Health>(patient).unwrap().current;
+
+    assert_eq!(cast(&mut app, mage, "Firebolt", target), CastOutcome::Cast);
+    assert_eq!(health(&app), max - 6);
+    assert_eq!(
+        cast(&mut app, mage, "Mend Wounds", target),
+        CastOutcome::Cast
+    );
+    assert_eq!(health(&app), max);
+    assert_eq!(
+        cast(&mut app, mage, "Divine Shield", target),
+        CastOutcome::Cast
+    );
+    assert_eq!(health(&app), max + 10);
+    assert_eq!(
+        cast(&mut app, mage, "Enchanted Toy", target),
+        CastOutcome::Cast
+    );
+    assert_eq!(app.world().get::<Needs>(patient).unwrap().fun, 40.0);
+    // Only fighters can be stunned
+    assert_eq!(cast(&mut app, mage, "Gust", target), CastOutcome::BadTarget);
+    assert_eq!(
+        mana(&app, mage).current,
+        mana(&app, mage).max - 4 - 4 - 6 - 2
+    );
+}
+
+#[test]
+fn spells_change_map_cells() {
+    let mut app = new_app();
+    let mage = app.world_mut().spawn(archmage()).id();
+    let cell = UVec2::new(2, 3);
+    let target = SpellTarget::Cell { layer: 0, cell };
+    assert_eq!(
+        cast(&mut app, mage, "Raise Stone", target),
+        CastOutcome::Cast
+    );
+    assert_eq!(
+        app.world().resource::<CastLog>().floors,
+        vec![SetCellFloorEvent {
+            layer: 0,
+            cell,
+            floor: GameMapCellFloor::Stone
+        }]
+    );
+    assert_eq!(
+        cast(&mut app, mage, "Summon Golem", target),
+        CastOutcome::Cast
+    );
+    let mut creatures = app.world_mut().query::<(&Creature, &Transform)>();
+    let (creature, tr) = creatures.single(app.world()).unwrap();
+    assert_eq!(creature.0, CreatureKind::Golem);
+    assert_eq!(tr.translation, Vec3::new(2.0, 0.0, 3.0));
+    let upper = SpellTarget::Cell { layer: 1, cell };
+    assert_eq!(cast(&mut app, mage, "Summon Imp", upper), CastOutcome::Cast);
+    let mut imps = app
+        .world_mut()
+        .query::<(&Creature, &Transform, &LayerObject)>();
+    let (_, tr, layer) = imps
+        .iter(app.world())
+        .find(|(creature, ..)| creature.0 == CreatureKind::Imp)
+        .unwrap();
+    assert_eq!(tr.translation, Vec3::new(2.0, 1.0, 3.0));
+    assert_eq!(*layer, LayerObject(1));
+    assert_eq!(
+        cast(&mut app, mage, "Firebolt", target),
+        CastOutcome::BadTarget
+    );
+}
+
+#[test]
+fn casting_costs_mana_and_can_fail() {
+    let mut app = new_app();
+    let mut novice = archmage();
+    novice.primary.magic = 15;
+    novice.primary.charm = 4;
+    let novice = app.world_mut().spawn(novice).id();
+    let target = SpellTarget::Cell {
+        layer: 0,
+        cell: UVec2::ZERO,
+    };
+    // Enough mana for five imps
+    let mut outcomes = vec![];
+    for _ in 0..6 {
+        outcomes.push(cast(&mut app, novice, "Summon Imp", target));
+    }
+    assert!(outcomes.contains(&CastOutcome::Fizzled));
+    assert_eq!(outcomes.last(), Some(&CastOutcome::NoMana));
+    assert_eq!(mana(&app, novice).current, 0);
+    assert_eq!(
+        cast(&mut app, novice, "Unheard Of", target),
+        CastOutcome::Unknown
+    );
+}
+
+#[test]
+fn summoned_creatures_attack_cats_nearby() {
+    let mut app = new_app();
+    let mage = app.world_mut().spawn(archmage()).id();
+    let student = app
+        .world_mut()
+        .spawn((sheet(), Transform::from_xyz(1.0, 0.0, 0.0)))
+        .id();
+    let target = SpellTarget::Cell {
+        layer: 0,
+        cell: UVec2::ZERO,
+    };
+    assert_eq!(
+        cast(&mut app, mage, "Summon Imp", target),
+        CastOutcome::Cast
+    );
+    app.update();
+    let fighting = app.world().get::<Fighting>(student).unwrap();
+    assert_eq!(fighting.side, 0);
+}
@@ -0,0 +1,228 @@
+use bevy::prelude::*;
+
+use crate::{
+    calendar_plugin::{Calendar, CalendarConfig, CalendarPlugin},
+    cat::CatSheet,
+    game_state_plugin::GameObject,
+    rng_plugin::RngPlugin,
+    test_utils::{sheet, simulation_app, start_game},
+};
+
+use super::{
+    Alumni, Education, Enrollment, StudentDeparted, StudentGraduated, StudentLifecycleConfig,
+    StudentPlugin, Teacher,
+};
+
+#[derive(Resource, Default)]
+struct GraduationLog(Vec<StudentGraduated>);
+
+fn log_graduations(mut evs: EventReader<StudentGraduated>, mut log: ResMut<GraduationLog>) {
+    log.0.extend(evs.read().cloned());
+}
+
+#[derive(Resource, Default)]
+struct DepartureLog(usize);
+
+fn log_departures(mut evs: EventReader<StudentDeparted>, mut log: ResMut<DepartureLog>) {
+    log.0 += evs.read().count();
+}
+
+const CONFIG: StudentLifecycleConfig = StudentLifecycleConfig {
+    initial_arrivals: 3,
+    arrivals_per_season: 2,
+    general_education_seasons: 2,
+    specific_education_seasons: 3,
+};
+
+struct StudentTestSuite {
+    app: App,
+}
+
+impl StudentTestSuite {
+    fn new(seed: u64) -> Self {
+        let mut app = simulation_app();
+        app.add_plugins((
+            CalendarPlugin,
+            RngPlugin { seed: Some(seed) },
+            StudentPlugin,
+        ));
+        // A season lasts two ticks
+        app.insert_resource(CalendarConfig {
+            ticks_per_day: 1,
+            days_per_season: 2,
+        });
+        app.insert_resource(CONFIG);
+        app.init_resource::<GraduationLog>();
+        app.init_resource::<DepartureLog>();
+        app.add_systems(Update, (log_graduations, log_departures));
+        start_game(&mut app);
+        Self { app }
+    }
+
+    /// Run until the given season index starts
+    fn run_until_season(mut self, season: u32) -> Self {
+        while self.app.world().resource::<Calendar>().season_index() < season {
+            self.app.update();
+        }
+        // Let Update readers see events from the last tick
+        self.app.update();
+        self
+    }
+
+    fn students(&mut self) -> Vec<(String, CatSheet, Enrollment)> {
+        let mut students: Vec<_> = self
+            .app
+            .world_mut()
+            .query::<(Entity, &Name, &CatSheet, &Enrollment)>()
+            .iter(self.app.world())
+            .map(|(entity, name, sheet, enrollment)| {
+                (
+                    entity,
+                    (name.to_string(), sheet.clone(), enrollment.clone()),
+                )
+            })
+            .collect();
+        students.sort_by_key(|(entity, _)| *entity);
+        students.into_iter().map(|(_, student)| student).collect()
+    }
+
+    fn teachers(&mut self) -> Vec<(CatSheet, Teacher)> {
+        self.app
+            .world_mut()
+            .query::<(&CatSheet, &Teacher)>()
+            .iter(self.app.world())
+            .map(|(sheet, teacher)| (sheet.clone(), teacher.clone()))
+            .collect()
+    }
+
+    fn alumni(&self) -> &Alumni {
+        self.app.world().resource::<Alumni>()
+    }
+}
+
+#[test]
+fn kittens_arrive_when_game_starts() {
+    let mut suite = StudentTestSuite::new(1);
+    let students = suite.students();
+    let initial = students
+        .iter()
+        .filter(|(_, _, enrollment)| enrollment.enrolled_season == 0)
+        .count();
+    assert_eq!(initial, CONFIG.initial_arrivals as usize);
+    for (_, _, enrollment) in &students {
+        assert_eq!(enrollment.education, Education::General);
+    }
+    let world = suite.app.world_mut();
+    let without_model = world
+        .query_filtered::<(), (With<Enrollment>, With<GameObject>, Without<SceneRoot>)>()
+        .iter(world)
+        .count();
+    assert_eq!(without_model, students.len());
+}
+
+#[test]
+fn kittens_arrive_every_season() {
+    let mut suite = StudentTestSuite::new(1).run_until_season(1);
+    let students = suite.students();
+    // Nobody finished the education yet
+    assert_eq!(
+        students.len(),
+        (CONFIG.initial_arrivals + CONFIG.arrivals_per_season) as usize
+    );
+    assert_eq!(students.last().unwrap().2.enrolled_season, 1);
+}
+
+#[test]
+fn students_graduate_after_general_education() {
+    let mut suite = StudentTestSuite::new(2).run_until_season(CONFIG.general_education_seasons);
+    let log = &suite.app.world().resource::<GraduationLog>().0;
+    assert_eq!(log.len(), CONFIG.initial_arrivals as usize);
+    assert!(log.iter().all(|ev| ev.education == Education::General));
+
+    let stayed = log.iter().filter(|ev| ev.stays).count();
+    let left = log.len() - stayed;
+    assert_eq!(suite.alumni().0.len(), left);
+    assert_eq!(suite.app.world().resource::<DepartureLog>().0, left);
+
+    let specific = suite
+        .students()
+        .into_iter()
+        .filter(|(_, _, enrollment)| enrollment.education == Education::Specific)
+        .count();
+    assert_eq!(specific, stayed);
+}
+
+#[test]
+fn nobody_studies_longer_than_allowed() {
+    let mut suite = StudentTestSuite::new(3).run_until_season(16);
+    let season = suite.app.world().resource::<Calendar>().season_index();
+    for (_, _, enrollment) in suite.students() {
+        let limit = match enrollment.education {
+            Education::General => CONFIG.general_education_seasons,
+            Education::Specific => CONFIG.specific_education_seasons,
+        };
+        assert!(season - enrollment.education_started < limit);
+    }
+
+    let alumni = suite.alumni().0.clone();
+    assert!(!alumni.is_empty());
+    for record in &alumni {
+        let studied = record.departed_season - record.enrollment.enrolled_season;
+        let limit = match record.enrollment.education {
+            Education::General => CONFIG.general_education_seasons,
+            Education::Specific => {
+                CONFIG.general_education_seasons + CONFIG.specific_education_seasons
+            }
+        };
+        assert_eq!(studied, limit, "{record:?}");
+    }
+
+    // Everyone who arrived is either a student, a teacher or an alumnus
+    let arrived = CONFIG.initial_arrivals + CONFIG.arrivals_per_season * season;
+    assert_eq!(
+        suite.students().len() + suite.teachers().len() + alumni.len(),
+        arrived as usize,
+        "{alumni:?}"
+    );
+}
+
+#[test]
+fn some_graduates_become_teachers_of_their_best_school() {
+    let mut suite = StudentTestSuite::new(6).run_until_season(24);
+    let teachers = suite.teachers();
+    assert!(!teachers.is_empty());
+    for (sheet, teacher) in teachers {
+        assert_eq!(teacher.school, sheet.best_school());
+    }
+}
+
+#[test]
+fn lifecycle_is_reproducible_with_seed() {
+    let run = |seed| {
+        let mut suite = StudentTestSuite::new(seed).run_until_season(8);
+        (suite.students(), suite.alumni().0.clone())
+    };
+    assert_eq!(run(4), run(4));
+    assert_ne!(run(4), run(5));
+}
+
+#[test]
+fn students_from_later_seasons_keep_studying() {
+    // Calendar of a save which is behind the enrollments
+    let mut suite = StudentTestSuite::new(1);
+    let enrollment = Enrollment {
+        enrolled_season: 10,
+        education: Education::General,
+        education_started: 10,
+    };
+    let student = suite
+        .app
+        .world_mut()
+        .spawn((sheet(), enrollment.clone()))
+        .id();
+    let suite = suite.run_until_season(1);
+    assert_eq!(
+        suite.app.world().get::<Enrollment>(student),
+        Some(&enrollment)
+    );
+}
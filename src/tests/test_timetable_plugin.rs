@@ -0,0 +1,313 @@
+use bevy::prelude::*;
+
+use crate::{
+    calendar_plugin::CalendarPlugin,
+    cat::MagicSchool,
+    game_map_plugin::GameMap,
+    main_menu_plugin::MIN_MAP_SIZE,
+    student_plugin::{Education, Enrollment, Teacher},
+    test_utils::{sheet, simulation_app, start_game},
+};
+
+use super::{
+    Class, Classroom, Classrooms, DAYS_PER_WEEK, LessonSlot, Timetable, TimetableConflict,
+    TimetableConflicts, TimetablePlugin,
+};
+
+const MONDAY_FIRST: LessonSlot = LessonSlot {
+    weekday: 0,
+    lesson: 0,
+};
+const MONDAY_SECOND: LessonSlot = LessonSlot {
+    weekday: 0,
+    lesson: 1,
+};
+
+fn entities(count: u32) -> Vec<Entity> {
+    (1..=count).map(Entity::from_raw).collect()
+}
+
+fn class(teacher: Entity, students: Vec<Entity>, classroom: usize, slot: LessonSlot) -> Class {
+    Class {
+        school: MagicSchool::Elemental,
+        teacher,
+        students,
+        classroom,
+        slot,
+    }
+}
+
+fn classrooms() -> Classrooms {
+    Classrooms(vec![
+        Classroom {
+            name: "Big".to_string(),
+            layer: 0,
+            min: UVec2::new(0, 0),
+            max: UVec2::new(3, 3),
+        },
+        Classroom {
+            name: "Tiny".to_string(),
+            layer: 0,
+            min: UVec2::new(0, 0),
+            max: UVec2::new(1, 0),
+        },
+        Classroom {
+            name: "Air".to_string(),
+            layer: 1,
+            min: UVec2::new(0, 0),
+            max: UVec2::new(3, 3),
+        },
+    ])
+}
+
+#[test]
+fn classroom_capacity_counts_floor_cells() {
+    let map = GameMap::new(2, 10, 10);
+    let rooms = classrooms();
+    assert_eq!(rooms.0[0].capacity(&map), 16);
+    assert_eq!(rooms.0[1].capacity(&map), 2);
+    // Upper layers are empty
+    assert_eq!(rooms.0[2].capacity(&map), 0);
+
+    let outside = Classroom {
+        name: "Outside".to_string(),
+        layer: 5,
+        min: UVec2::ZERO,
+        max: UVec2::ONE,
+    };
+    assert_eq!(outside.capacity(&map), 0);
+    let clipped = Classroom {
+        name: "Clipped".to_string(),
+        layer: 0,
+        min: UVec2::new(8, 8),
+        max: UVec2::new(20, 20),
+    };
+    assert_eq!(clipped.capacity(&map), 4);
+}
+
+#[test]
+fn valid_timetable_has_no_conflicts() {
+    let [t1, t2, s1, s2] = entities(4).try_into().unwrap();
+    let timetable = Timetable {
+        classes: vec![
+            class(t1, vec![s1, s2], 0, MONDAY_FIRST),
+            class(t2, vec![s1], 1, MONDAY_FIRST),
+            class(t1, vec![s2], 0, MONDAY_SECOND),
+        ],
+    };
+    let map = GameMap::new(2, 10, 10);
+    assert!(timetable.conflicts(&classrooms(), Some(&map)).is_empty());
+}
+
+#[test]
+fn double_booked_teacher_and_room_are_detected() {
+    let [t1, t2] = entities(2).try_into().unwrap();
+    let timetable = Timetable {
+        classes: vec![
+            class(t1, vec![], 0, MONDAY_FIRST),
+            class(t1, vec![], 1, MONDAY_FIRST),
+            class(t2, vec![], 0, MONDAY_FIRST),
+        ],
+    };
+    let conflicts = timetable.conflicts(&classrooms(), None);
+    assert_eq!(
+        conflicts,
+        vec![
+            TimetableConflict::TeacherDoubleBooked {
+                teacher: t1,
+                slot: MONDAY_FIRST,
+                classes: [0, 1],
+            },
+            TimetableConflict::ClassroomDoubleBooked {
+                classroom: 0,
+                slot: MONDAY_FIRST,
+                classes: [0, 2],
+            },
+        ]
+    );
+}
+
+#[test]
+fn small_and_unknown_rooms_are_detected() {
+    let [t1, t2, t3, s1, s2] = entities(5).try_into().unwrap();
+    let timetable = Timetable {
+        classes: vec![
+            class(t1, vec![s1], 1, MONDAY_FIRST),
+            class(t2, vec![s1, s2], 1, MONDAY_SECOND),
+            class(t3, vec![], 9, MONDAY_FIRST),
+            class(
+                t3,
+                vec![],
+                0,
+                LessonSlot {
+                    weekday: 7,
+                    lesson: 0,
+                },
+            ),
+        ],
+    };
+    let map = GameMap::new(2, 10, 10);
+    assert_eq!(
+        timetable.conflicts(&classrooms(), Some(&map)),
+        vec![
+            TimetableConflict::ClassroomTooSmall {
+                class: 1,
+                capacity: 2,
+                required: 3,
+            },
+            TimetableConflict::UnknownClassroom { class: 2 },
+            TimetableConflict::InvalidSlot { class: 3 },
+        ]
+    );
+    // Sizes are unknown without the map
+    assert_eq!(timetable.conflicts(&classrooms(), None).len(), 2);
+}
+
+#[test]
+fn departed_cats_are_removed_from_timetable() {
+    let mut app = simulation_app();
+    app.add_plugins((CalendarPlugin, TimetablePlugin));
+    app.insert_resource(classrooms());
+    let enrollment = Enrollment {
+        enrolled_season: 0,
+        education: Education::General,
+        education_started: 0,
+    };
+    let teacher = |app: &mut App| {
+        app.world_mut()
+            .spawn(Teacher {
+                school: MagicSchool::Alchemy,
+            })
+            .id()
+    };
+    let t1 = teacher(&mut app);
+    let t2 = teacher(&mut app);
+    let s1 = app.world_mut().spawn(enrollment.clone()).id();
+    let s2 = app.world_mut().spawn(enrollment).id();
+    app.insert_resource(Timetable {
+        classes: vec![
+            class(t1, vec![s1, s2], 0, MONDAY_FIRST),
+            class(t2, vec![s2], 0, MONDAY_FIRST),
+        ],
+    });
+    app.update(); // Init
+    app.update();
+    assert_eq!(app.world().resource::<TimetableConflicts>().0.len(), 1);
+
+    app.world_mut().despawn(s2);
+    app.world_mut().despawn(t2);
+    app.update();
+    let timetable = app.world().resource::<Timetable>();
+    assert_eq!(
+        timetable.classes,
+        vec![class(t1, vec![s1], 0, MONDAY_FIRST)]
+    );
+    assert!(app.world().resource::<TimetableConflicts>().0.is_empty());
+}
+
+#[test]
+fn plan_gives_every_teacher_a_lesson_a_day() {
+    let [
+        alchemist,
+        elementalist,
+        general,
+        alchemy_student,
+        elemental_student,
+    ] = entities(5).try_into().unwrap();
+    let teachers = [
+        (alchemist, MagicSchool::Alchemy),
+        (elementalist, MagicSchool::Elemental),
+    ];
+    let students = [
+        (general, None),
+        (alchemy_student, Some(MagicSchool::Alchemy)),
+        (elemental_student, Some(MagicSchool::Elemental)),
+    ];
+    let rooms = classrooms();
+    let timetable = Timetable::plan(&teachers, &students, &rooms, None);
+    assert!(timetable.conflicts(&rooms, None).is_empty());
+    for (teacher, school) in teachers {
+        let classes: Vec<&Class> = timetable
+            .classes
+            .iter()
+            .filter(|class| class.teacher == teacher)
+            .collect();
+        assert_eq!(classes.len(), DAYS_PER_WEEK as usize);
+        for class in classes {
+            assert_eq!(class.school, school);
+            assert!(!class.students.is_empty());
+        }
+    }
+    let attends = |student: Entity, school: MagicSchool| {
+        timetable
+            .classes
+            .iter()
+            .any(|class| class.school == school && class.students.contains(&student))
+    };
+    assert!(attends(general, MagicSchool::Alchemy));
+    assert!(attends(general, MagicSchool::Elemental));
+    assert!(attends(alchemy_student, MagicSchool::Alchemy));
+    assert!(!attends(alchemy_student, MagicSchool::Elemental));
+    assert!(!attends(elemental_student, MagicSchool::Alchemy));
+}
+
+#[test]
+fn plan_fills_classrooms_up_to_capacity() {
+    let map = GameMap::new(2, 10, 10);
+    let teachers = [(Entity::from_raw(100), MagicSchool::Alchemy)];
+    let students: Vec<(Entity, Option<MagicSchool>)> = entities(20)
+        .into_iter()
+        .map(|student| (student, None))
+        .collect();
+    let rooms = classrooms();
+    let timetable = Timetable::plan(&teachers, &students, &rooms, Some(&map));
+    assert!(timetable.conflicts(&rooms, Some(&map)).is_empty());
+    assert_eq!(timetable.classes.len(), DAYS_PER_WEEK as usize);
+    for class in &timetable.classes {
+        // The big classroom fits the teacher and 15 students
+        assert_eq!(class.classroom, 0);
+        assert_eq!(class.students.len(), 15);
+    }
+}
+
+#[test]
+fn classes_are_planned_when_the_game_starts() {
+    let mut app = simulation_app();
+    app.add_plugins((CalendarPlugin, TimetablePlugin));
+    let teacher = app
+        .world_mut()
+        .spawn(Teacher {
+            school: MagicSchool::Alchemy,
+        })
+        .id();
+    let student = app
+        .world_mut()
+        .spawn((
+            sheet(),
+            Enrollment {
+                enrolled_season: 0,
+                education: Education::General,
+                education_started: 0,
+            },
+        ))
+        .id();
+    start_game(&mut app);
+    let timetable = app.world().resource::<Timetable>();
+    assert_eq!(timetable.classes.len(), DAYS_PER_WEEK as usize);
+    for class in &timetable.classes {
+        assert_eq!(class.teacher, teacher);
+        assert_eq!(class.students, vec![student]);
+    }
+    assert!(app.world().resource::<TimetableConflicts>().0.is_empty());
+}
+
+#[test]
+fn classrooms_fit_the_smallest_map() {
+    for classroom in Classrooms::default().0 {
+        assert!(
+            classroom.max.cmplt(UVec2::splat(MIN_MAP_SIZE)).all(),
+            "{}",
+            classroom.name
+        );
+    }
+}
@@ -1,12 +1,26 @@
 #![cfg(test)]
 #![allow(dead_code)]
+use std::time::Duration;
+
 use bevy::{
     app::PluginGroupBuilder,
-    input::keyboard::KeyboardInput,
+    input::{InputPlugin, keyboard::KeyboardInput},
     prelude::*,
     render::{RenderPlugin, settings::WgpuSettings},
+    state::app::StatesPlugin,
+    time::TimeUpdateStrategy,
     winit::{WakeUp, WinitPlugin},
 };
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    cat::{CatSheet, CatTraits},
+    game_state_plugin::GameStatePlugin,
+    player_control_plugin::PlayerControlPlugin,
+    player_input_stage::PlayerInputStagesPlugin,
+    simulation_clock_plugin::{SIMULATION_TICKS_PER_SECOND, SimulationClockPlugin},
+};
 
 pub fn is_key_just_pressed(app: &App, keycode: KeyCode) -> bool {
     let input = app.world().resource::<ButtonInput<KeyCode>>();
@@ -49,6 +63,38 @@ where
         .any(|ev| *ev == event)
 }
 
+/// Headless app with game states, player commands and the simulation clock.
+/// Every frame advances the time by exactly one tick
+pub fn simulation_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        InputPlugin,
+        StatesPlugin,
+        GameStatePlugin,
+        PlayerInputStagesPlugin,
+        PlayerControlPlugin,
+        SimulationClockPlugin,
+    ));
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / SIMULATION_TICKS_PER_SECOND,
+    )));
+    app
+}
+
+/// Run `Init` and the first frame of `Game`
+pub fn start_game(app: &mut App) {
+    app.update();
+    app.update();
+}
+
+/// The same cat every time, without traits so the attributes are what tests set
+pub fn sheet() -> CatSheet {
+    let mut sheet = CatSheet::generate(&mut ChaCha8Rng::seed_from_u64(1));
+    sheet.traits = CatTraits::default();
+    sheet
+}
+
 pub fn make_defaullt_plugins_for_headless_test() -> PluginGroupBuilder {
     // #[tests] are run in separate threads which winit doesn't like
     let mut winit = WinitPlugin::<WakeUp>::default();
@@ -109,7 +155,7 @@ pub trait BaseTestSuite {
     where
         Self: Sized,
     {
-        release_key(&mut self.app(), keycode);
+        release_key(self.app(), keycode);
         self.update()
     }
 }
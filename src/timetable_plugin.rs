@@ -0,0 +1,373 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    calendar_plugin::{Calendar, CalendarConfig, SeasonChanged},
+    cat::{CatSheet, MagicSchool},
+    game_map_plugin::{GameMap, GameMapCellFloor, GameMapData},
+    game_state_plugin::{GameResourceAppExt, GameState},
+    student_plugin::{Education, Enrollment, Teacher},
+};
+
+/// Weekly schedule of classes
+pub struct TimetablePlugin;
+
+pub const DAYS_PER_WEEK: u32 = 7;
+pub const LESSONS_PER_DAY: u32 = 4;
+
+/// Time of a lesson within a week
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect, Serialize, Deserialize,
+)]
+pub struct LessonSlot {
+    pub weekday: u32,
+    pub lesson: u32,
+}
+
+impl LessonSlot {
+    /// Lesson going on at the given time. Lessons split the day evenly
+    pub fn at(calendar: &Calendar, config: &CalendarConfig) -> Self {
+        let day = calendar.season_index() * config.days_per_season + calendar.day;
+        Self {
+            weekday: day % DAYS_PER_WEEK,
+            lesson: calendar.tick_of_day * LESSONS_PER_DAY / config.ticks_per_day.max(1),
+        }
+    }
+}
+
+/// Rectangular area of the map where classes are held
+#[derive(Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct Classroom {
+    pub name: String,
+    pub layer: usize,
+    /// Top left cell, (column, row)
+    pub min: UVec2,
+    /// Bottom right cell, inclusive
+    pub max: UVec2,
+}
+
+impl Classroom {
+    /// Number of cats who fit into the room: one per cell with a floor
+    pub fn capacity(&self, map: &GameMap) -> usize {
+        let Some(layer) = map.cells.get(self.layer) else {
+            return 0;
+        };
+        let rows = layer
+            .iter()
+            .take(self.max.y as usize + 1)
+            .skip(self.min.y as usize);
+        rows.map(|row| {
+            row.iter()
+                .take(self.max.x as usize + 1)
+                .skip(self.min.x as usize)
+                .filter(|cell| cell.floor() != GameMapCellFloor::None)
+                .count()
+        })
+        .sum()
+    }
+}
+
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct Classrooms(pub Vec<Classroom>);
+
+impl Default for Classrooms {
+    fn default() -> Self {
+        // Grass on both sides of the road
+        Self(vec![
+            Classroom {
+                name: "Meadow".to_string(),
+                layer: 0,
+                min: UVec2::new(0, 6),
+                max: UVec2::new(4, 9),
+            },
+            Classroom {
+                name: "Orchard".to_string(),
+                layer: 0,
+                min: UVec2::new(5, 6),
+                max: UVec2::new(9, 9),
+            },
+        ])
+    }
+}
+
+/// Lesson of the teacher to the students in the classroom (index in `Classrooms`)
+#[derive(Debug, Clone, PartialEq, Eq, Reflect)]
+pub struct Class {
+    pub school: MagicSchool,
+    pub teacher: Entity,
+    pub students: Vec<Entity>,
+    pub classroom: usize,
+    pub slot: LessonSlot,
+}
+
+#[derive(Resource, Debug, Default, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct Timetable {
+    pub classes: Vec<Class>,
+}
+
+/// Classes are referred by their index in `Timetable::classes`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimetableConflict {
+    TeacherDoubleBooked {
+        teacher: Entity,
+        slot: LessonSlot,
+        classes: [usize; 2],
+    },
+    ClassroomDoubleBooked {
+        classroom: usize,
+        slot: LessonSlot,
+        classes: [usize; 2],
+    },
+    /// Room must fit the teacher and all the students
+    ClassroomTooSmall {
+        class: usize,
+        capacity: usize,
+        required: usize,
+    },
+    UnknownClassroom {
+        class: usize,
+    },
+    /// Slot is outside of the week
+    InvalidSlot {
+        class: usize,
+    },
+}
+
+impl Timetable {
+    pub fn classes_at(&self, slot: LessonSlot) -> impl Iterator<Item = &Class> {
+        self.classes.iter().filter(move |class| class.slot == slot)
+    }
+
+    /// Check the timetable. Without the map sizes of classrooms are not checked
+    pub fn conflicts(
+        &self,
+        classrooms: &Classrooms,
+        map: Option<&GameMap>,
+    ) -> Vec<TimetableConflict> {
+        let mut conflicts = vec![];
+        for (idx, class) in self.classes.iter().enumerate() {
+            if class.slot.weekday >= DAYS_PER_WEEK || class.slot.lesson >= LESSONS_PER_DAY {
+                conflicts.push(TimetableConflict::InvalidSlot { class: idx });
+            }
+            for (other_idx, other) in self.classes.iter().enumerate().skip(idx + 1) {
+                if class.slot != other.slot {
+                    continue;
+                }
+                if class.teacher == other.teacher {
+                    conflicts.push(TimetableConflict::TeacherDoubleBooked {
+                        teacher: class.teacher,
+                        slot: class.slot,
+                        classes: [idx, other_idx],
+                    });
+                }
+                if class.classroom == other.classroom {
+                    conflicts.push(TimetableConflict::ClassroomDoubleBooked {
+                        classroom: class.classroom,
+                        slot: class.slot,
+                        classes: [idx, other_idx],
+                    });
+                }
+            }
+
+            let Some(classroom) = classrooms.0.get(class.classroom) else {
+                conflicts.push(TimetableConflict::UnknownClassroom { class: idx });
+                continue;
+            };
+            if let Some(map) = map {
+                let capacity = classroom.capacity(map);
+                let required = class.students.len() + 1;
+                if capacity < required {
+                    conflicts.push(TimetableConflict::ClassroomTooSmall {
+                        class: idx,
+                        capacity,
+                        required,
+                    });
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Every teacher gives a lesson a day to the students of its school who are free then,
+    /// as many as the classroom fits. Students in general education learn every school.
+    /// Students are `None` during general education, otherwise the school they study
+    pub fn plan(
+        teachers: &[(Entity, MagicSchool)],
+        students: &[(Entity, Option<MagicSchool>)],
+        classrooms: &Classrooms,
+        map: Option<&GameMap>,
+    ) -> Self {
+        let mut timetable = Self::default();
+        for &(teacher, school) in teachers {
+            let pupils: Vec<Entity> = students
+                .iter()
+                .filter(|(_, studies)| studies.is_none_or(|studies| studies == school))
+                .map(|(student, _)| *student)
+                .collect();
+            for weekday in 0..DAYS_PER_WEEK {
+                let class = Class {
+                    school,
+                    teacher,
+                    students: vec![],
+                    classroom: 0,
+                    slot: LessonSlot { weekday, lesson: 0 },
+                };
+                timetable.add_lesson(class, &pupils, classrooms, map);
+            }
+        }
+        timetable
+    }
+
+    /// Put the class into the lesson of its day and the classroom where most of the pupils
+    /// are free and it has no conflicts, the earliest one among equals
+    fn add_lesson(
+        &mut self,
+        class: Class,
+        pupils: &[Entity],
+        classrooms: &Classrooms,
+        map: Option<&GameMap>,
+    ) {
+        let mut best: Option<Class> = None;
+        for lesson in 0..LESSONS_PER_DAY {
+            let slot = LessonSlot {
+                lesson,
+                ..class.slot
+            };
+            for (idx, classroom) in classrooms.0.iter().enumerate() {
+                // The teacher takes a place too
+                let seats = map.map_or(usize::MAX, |map| classroom.capacity(map).saturating_sub(1));
+                let students: Vec<Entity> = pupils
+                    .iter()
+                    .filter(|pupil| {
+                        !self
+                            .classes_at(slot)
+                            .any(|other| other.students.contains(pupil))
+                    })
+                    .take(seats)
+                    .copied()
+                    .collect();
+                let best_size = best.as_ref().map_or(0, |best| best.students.len());
+                if students.len() <= best_size {
+                    continue;
+                }
+                self.classes.push(Class {
+                    students,
+                    classroom: idx,
+                    slot,
+                    ..class.clone()
+                });
+                if self.conflicts(classrooms, map).is_empty() {
+                    best = self.classes.pop();
+                } else {
+                    self.classes.pop();
+                }
+            }
+        }
+        self.classes.extend(best);
+    }
+}
+
+/// Problems of the current timetable, updated whenever it changes
+#[derive(Resource, Debug, Default)]
+pub struct TimetableConflicts(pub Vec<TimetableConflict>);
+
+fn detect_timetable_conflicts(
+    timetable: Res<Timetable>,
+    classrooms: Res<Classrooms>,
+    map_data: Option<Res<GameMapData>>,
+    mut conflicts: ResMut<TimetableConflicts>,
+) {
+    let map_changed = map_data.as_ref().is_some_and(|map| map.is_changed());
+    if !(timetable.is_changed() || classrooms.is_changed() || map_changed) {
+        return;
+    }
+    conflicts.0 = timetable.conflicts(&classrooms, map_data.as_deref().map(|x| x.map()));
+    for conflict in &conflicts.0 {
+        warn!("Timetable conflict: {conflict:?}");
+    }
+}
+
+/// Classes are planned anew every season, and whenever there are none,
+/// like at the start of the game
+fn plan_timetable(
+    mut seasons: EventReader<SeasonChanged>,
+    teachers: Query<(Entity, &Teacher)>,
+    students: Query<(Entity, &CatSheet, &Enrollment)>,
+    classrooms: Res<Classrooms>,
+    map_data: Option<Res<GameMapData>>,
+    mut timetable: ResMut<Timetable>,
+) {
+    let new_season = seasons.read().count() > 0;
+    if !new_season && !timetable.classes.is_empty() {
+        return;
+    }
+    let mut teachers: Vec<(Entity, MagicSchool)> = teachers
+        .iter()
+        .map(|(entity, teacher)| (entity, teacher.school))
+        .collect();
+    teachers.sort_by_key(|(entity, _)| *entity);
+    let mut students: Vec<(Entity, Option<MagicSchool>)> = students
+        .iter()
+        .map(|(entity, sheet, enrollment)| {
+            let school = match enrollment.education {
+                Education::General => None,
+                Education::Specific => Some(sheet.best_school()),
+            };
+            (entity, school)
+        })
+        .collect();
+    students.sort_by_key(|(entity, _)| *entity);
+    let planned = Timetable::plan(
+        &teachers,
+        &students,
+        &classrooms,
+        map_data.as_deref().map(|x| x.map()),
+    );
+    // Keep change detection quiet while nothing can be planned
+    if planned.classes != timetable.classes {
+        *timetable = planned;
+    }
+}
+
+/// Departed students leave their classes, classes of departed teachers are cancelled
+fn prune_timetable(
+    mut students_gone: RemovedComponents<Enrollment>,
+    mut teachers_gone: RemovedComponents<Teacher>,
+    mut timetable: ResMut<Timetable>,
+) {
+    let students_gone: Vec<Entity> = students_gone.read().collect();
+    let teachers_gone: Vec<Entity> = teachers_gone.read().collect();
+    if students_gone.is_empty() && teachers_gone.is_empty() {
+        return;
+    }
+    timetable
+        .classes
+        .retain(|class| !teachers_gone.contains(&class.teacher));
+    for class in &mut timetable.classes {
+        class
+            .students
+            .retain(|student| !students_gone.contains(student));
+    }
+}
+
+impl Plugin for TimetablePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Timetable>();
+        app.register_type::<Classrooms>();
+        app.init_game_resource::<Timetable>();
+        app.init_game_resource::<Classrooms>();
+        app.init_game_resource::<TimetableConflicts>();
+        app.add_systems(
+            Update,
+            (prune_timetable, plan_timetable, detect_timetable_conflicts)
+                .chain()
+                .run_if(in_state(GameState::Game)),
+        );
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/test_timetable_plugin.rs"]
+mod test_timetable_plugin;